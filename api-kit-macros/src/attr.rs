@@ -0,0 +1,77 @@
+//! Shared attribute parsing.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, Attribute, Expr, ExprLit, Lit, LitStr, Meta, MetaNameValue, Type,
+};
+
+/// Call `f` for each item in the `#[api_kit(...)]` attributes of `attrs`.
+pub fn parse_api_kit_attrs(
+    attrs: &[Attribute],
+    mut f: impl FnMut(ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("api_kit")) {
+        attr.parse_nested_meta(&mut f)?;
+    }
+    Ok(())
+}
+
+/// Set `slot` to `value`, returning an error if it is already set.
+pub fn set_once<T>(meta: &ParseNestedMeta, slot: &mut Option<T>, value: T) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicate api_kit attribute"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+/// The default `BodyFormat` of derived endpoints.
+pub fn default_format() -> TokenStream {
+    quote!(::api_kit::body::JsonFormat)
+}
+
+/// The first non-empty line of the `#[doc]` attributes of `attrs`.
+pub fn doc_summary(attrs: &[Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(MetaNameValue {
+                path,
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(doc), ..
+                    }),
+                ..
+            }) if path.is_ident("doc") => Some(doc.value()),
+            _ => None,
+        })
+        .map(|doc| doc.trim().to_owned())
+        .find(|doc| !doc.is_empty())
+}
+
+/// Parse a `header = "Name"` value into a lowercase header name.
+pub fn parse_header_name(meta: &ParseNestedMeta) -> syn::Result<LitStr> {
+    let name: LitStr = meta.value()?.parse()?;
+    let value = name.value();
+    let valid = !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+    if !valid {
+        return Err(syn::Error::new_spanned(name, "invalid header name"));
+    }
+    Ok(LitStr::new(&value.to_ascii_lowercase(), name.span()))
+}
+
+/// Whether `ty` is an `Option`.
+pub fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
@@ -0,0 +1,81 @@
+//! Serde helper structs holding a subset of the fields of a derive input.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, DeriveInput, Ident, Type};
+
+/// A field copied into a helper struct.
+pub struct HelperField<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+    pub serde_attrs: Vec<&'a Attribute>,
+}
+
+impl<'a> HelperField<'a> {
+    pub fn new(field: &'a syn::Field) -> Self {
+        Self {
+            ident: field.ident.as_ref().expect("named field"),
+            ty: &field.ty,
+            serde_attrs: serde_attrs(&field.attrs),
+        }
+    }
+}
+
+/// The `#[serde]` attributes of `attrs`.
+pub fn serde_attrs(attrs: &[Attribute]) -> Vec<&Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .collect()
+}
+
+/// The definition of a helper struct `name` with `fields` deriving `derive`.
+///
+/// The struct has the generics of `input`, so it can hold fields of generic types.
+pub fn definition(
+    input: &DeriveInput,
+    name: &Ident,
+    derive: &TokenStream,
+    attrs: &[&Attribute],
+    fields: &[&HelperField],
+) -> TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let phantom = if input.generics.params.is_empty() {
+        TokenStream::new()
+    } else {
+        quote! {
+            #[serde(skip)]
+            __phantom: ::core::marker::PhantomData<fn() -> #ident #ty_generics>,
+        }
+    };
+    let idents = fields.iter().map(|field| field.ident);
+    let types = fields.iter().map(|field| field.ty);
+    let serde_attrs = fields.iter().map(|field| &field.serde_attrs);
+
+    quote! {
+        #[derive(#derive)]
+        #[serde(crate = "::api_kit::__private::serde")]
+        #(#attrs)*
+        struct #name #impl_generics #where_clause {
+            #(
+                #(#serde_attrs)*
+                #idents: #types,
+            )*
+            #phantom
+        }
+    }
+}
+
+/// An expression constructing the helper struct `name` from the variables named after `fields`.
+pub fn value(input: &DeriveInput, name: &Ident, fields: &[&HelperField]) -> TokenStream {
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let turbofish = ty_generics.as_turbofish();
+    let idents = fields.iter().map(|field| field.ident);
+    let phantom = if input.generics.params.is_empty() {
+        TokenStream::new()
+    } else {
+        quote!(__phantom: ::core::marker::PhantomData,)
+    };
+    quote!(#name #turbofish { #(#idents,)* #phantom })
+}
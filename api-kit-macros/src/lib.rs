@@ -0,0 +1,97 @@
+//! Derive macros for `api-kit`.
+//!
+//! These are re-exported by `api-kit` with the `derive` feature;
+//! see [`OutgoingRequest`] and [`IncomingResponse`].
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod attr;
+mod helper;
+mod request;
+mod response;
+
+/// Derive `Endpoint` and `OutgoingRequest` for a struct with named fields.
+///
+/// ```ignore
+/// /// Get a user by ID.
+/// #[derive(Clone, OutgoingRequest)]
+/// #[api_kit(method = GET, path = "/users/{id}", response = User)]
+/// struct GetUser {
+///     id: u64,
+///     #[serde(skip_serializing_if = "Option::is_none")]
+///     fields: Option<String>,
+/// }
+/// ```
+///
+/// # Container attributes
+///
+/// * `method = GET`: the HTTP method. Required.
+/// * `path = "/users/{id}"`: the endpoint path. Required.
+/// * `response = Type`: the `IncomingResponse` type. Required.
+/// * `auth = expr`: an authentication scheme accepted by the endpoint.
+///   May be repeated; defaults to no authentication.
+/// * `error = Type`: the endpoint error type. Defaults to `api_kit::error::ApiError`.
+/// * `format = Type`: the `BodyFormat` of the body. Defaults to `api_kit::body::JsonFormat`.
+/// * `query_style = Bracketed`: the `QueryStyle` of the query string.
+///   Defaults to `UrlEncoded`.
+///
+/// The first line of the struct documentation is used as the endpoint summary.
+/// The `Accept` header is set to the content type of the response
+/// with `api_kit::body::set_accept`.
+///
+/// # Field attributes
+///
+/// * `#[api_kit(path)]`: the field is a path parameter.
+/// * `#[api_kit(query)]`: the field is a query parameter.
+/// * `#[api_kit(body)]`: the field is part of the request body.
+/// * `#[api_kit(header = "Idempotency-Key")]`: the field is sent in a header,
+///   using its `Display` value. `None` values of `Option` fields are not sent.
+///
+/// Fields without a location are path parameters if their name is a placeholder in `path`,
+/// body fields for `POST`, `PUT` and `PATCH` requests, and query parameters otherwise.
+/// `#[serde]` field attributes are applied when serializing the field.
+#[proc_macro_derive(OutgoingRequest, attributes(api_kit, serde))]
+pub fn derive_outgoing_request(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    request::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `IncomingResponse` for a type deserialized from the response body.
+///
+/// The response body is deserialized with `api_kit::body::from_response`,
+/// so its `Content-Type` must match the body format.
+/// The content type of the body format is also the `EXPECTED_CONTENT_TYPE` of the response.
+///
+/// ```ignore
+/// #[derive(Deserialize, IncomingResponse)]
+/// #[api_kit(request = GetUser)]
+/// struct User {
+///     id: u64,
+///     name: String,
+/// }
+/// ```
+///
+/// # Container attributes
+///
+/// * `request = Type`: the `OutgoingRequest` type. Required.
+/// * `format = Type`: the `BodyFormat` of the body. Defaults to `api_kit::body::JsonFormat`.
+///
+/// # Field attributes
+///
+/// * `#[api_kit(header = "ETag")]`: the field is read from a header.
+///   The field must be a `HeaderValue`, or an `Option<HeaderValue>` if the header is optional.
+///   Missing required headers are a `DeserializeError::MissingHeader` error.
+///
+/// Responses with header fields must be structs with named fields.
+/// Their other fields are deserialized from the body with the `#[serde]` attributes of the
+/// struct, so the struct doesn't implement `Deserialize` itself.
+#[proc_macro_derive(IncomingResponse, attributes(api_kit, serde))]
+pub fn derive_incoming_response(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    response::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
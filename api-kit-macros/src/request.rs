@@ -0,0 +1,301 @@
+//! `#[derive(OutgoingRequest)]`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{Attribute, Data, DeriveInput, Expr, Fields, Ident, LitStr, Type};
+
+use crate::{
+    attr::{
+        default_format, doc_summary, is_option, parse_api_kit_attrs, parse_header_name, set_once,
+    },
+    helper::{self, HelperField},
+};
+
+/// Where a field goes in the request.
+#[derive(Clone, PartialEq, Eq)]
+enum Location {
+    Path,
+    Query,
+    Body,
+    /// A header with a lowercase name.
+    Header(String),
+}
+
+struct Field<'a> {
+    helper: HelperField<'a>,
+    location: Location,
+}
+
+/// The container attributes of a request.
+struct Container {
+    method: Ident,
+    path: LitStr,
+    auth: Vec<Expr>,
+    response: Type,
+    error: TokenStream,
+    format: TokenStream,
+    query_style: Ident,
+}
+
+impl Container {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut method: Option<Ident> = None;
+        let mut path: Option<LitStr> = None;
+        let mut auth = Vec::new();
+        let mut response: Option<Type> = None;
+        let mut error: Option<Type> = None;
+        let mut format: Option<Type> = None;
+        let mut query_style: Option<Ident> = None;
+        parse_api_kit_attrs(attrs, |meta| {
+            if meta.path.is_ident("method") {
+                set_once(&meta, &mut method, meta.value()?.parse()?)
+            } else if meta.path.is_ident("path") {
+                set_once(&meta, &mut path, meta.value()?.parse()?)
+            } else if meta.path.is_ident("auth") {
+                auth.push(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("response") {
+                set_once(&meta, &mut response, meta.value()?.parse()?)
+            } else if meta.path.is_ident("error") {
+                set_once(&meta, &mut error, meta.value()?.parse()?)
+            } else if meta.path.is_ident("format") {
+                set_once(&meta, &mut format, meta.value()?.parse()?)
+            } else if meta.path.is_ident("query_style") {
+                set_once(&meta, &mut query_style, meta.value()?.parse()?)
+            } else {
+                Err(meta.error("unknown api_kit attribute"))
+            }
+        })?;
+
+        let missing = |name| {
+            syn::Error::new(
+                Span::call_site(),
+                format!("missing `#[api_kit({name} = ...)]`"),
+            )
+        };
+        Ok(Self {
+            method: method.ok_or_else(|| missing("method"))?,
+            path: path.ok_or_else(|| missing("path"))?,
+            auth,
+            response: response.ok_or_else(|| missing("response"))?,
+            error: error.map_or_else(
+                || quote!(::api_kit::error::ApiError),
+                ToTokens::into_token_stream,
+            ),
+            format: format.map_or_else(default_format, ToTokens::into_token_stream),
+            query_style: query_style.unwrap_or_else(|| format_ident!("UrlEncoded")),
+        })
+    }
+
+    /// Whether fields without a location are sent in the body.
+    fn has_body(&self) -> bool {
+        ["POST", "PUT", "PATCH"]
+            .iter()
+            .any(|method| self.method == method)
+    }
+}
+
+/// The names of the placeholders in `path`, e.g. `id` for `/users/{id}`.
+fn placeholders(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}'))
+        .map(|(name, _)| {
+            let name = name.trim_end_matches('?');
+            name.split_once('.').map_or(name, |(name, _)| name)
+        })
+        .collect()
+}
+
+fn parse_fields<'a>(input: &'a DeriveInput, container: &Container) -> syn::Result<Vec<Field<'a>>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "OutgoingRequest can only be derived for structs",
+        ));
+    };
+    let fields = match &data.fields {
+        Fields::Named(fields) => &fields.named,
+        Fields::Unit => return Ok(Vec::new()),
+        Fields::Unnamed(fields) => {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "OutgoingRequest can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let path = container.path.value();
+    let placeholders = placeholders(&path);
+    fields
+        .iter()
+        .map(|field| {
+            let helper = HelperField::new(field);
+            let ident = helper.ident;
+            let mut location = None;
+            parse_api_kit_attrs(&field.attrs, |meta| {
+                let value = if meta.path.is_ident("path") {
+                    Location::Path
+                } else if meta.path.is_ident("query") {
+                    Location::Query
+                } else if meta.path.is_ident("body") {
+                    Location::Body
+                } else if meta.path.is_ident("header") {
+                    Location::Header(parse_header_name(&meta)?.value())
+                } else {
+                    return Err(meta.error("unknown api_kit attribute"));
+                };
+                set_once(&meta, &mut location, value)
+            })?;
+            let location = location.unwrap_or_else(|| {
+                if placeholders.iter().any(|name| ident == name) {
+                    Location::Path
+                } else if container.has_body() {
+                    Location::Body
+                } else {
+                    Location::Query
+                }
+            });
+            Ok(Field { helper, location })
+        })
+        .collect()
+}
+
+/// A serializable struct holding the fields at `location`.
+///
+/// Returns the struct definition and a reference to it constructed from the
+/// destructured fields, or `&()` if there are no such fields.
+fn helper(
+    input: &DeriveInput,
+    fields: &[Field],
+    location: &Location,
+    name: &str,
+) -> (TokenStream, TokenStream) {
+    let fields: Vec<_> = fields
+        .iter()
+        .filter(|field| field.location == *location)
+        .map(|field| &field.helper)
+        .collect();
+    if fields.is_empty() {
+        return (TokenStream::new(), quote!(&()));
+    }
+
+    let name = format_ident!("{}", name);
+    let derive = quote!(::api_kit::__private::serde::Serialize);
+    let definition = helper::definition(input, &name, &derive, &[], &fields);
+    let value = helper::value(input, &name, &fields);
+    (definition, quote!(&#value))
+}
+
+/// Statements inserting the header fields into the headers of `req`.
+fn headers(fields: &[Field]) -> TokenStream {
+    fields
+        .iter()
+        .filter_map(|field| match &field.location {
+            Location::Header(name) => Some((field.helper.ident, field.helper.ty, name)),
+            _ => None,
+        })
+        .map(|(ident, ty, name)| {
+            let insert = |value| {
+                quote! {
+                    req.headers_mut().insert(
+                        ::api_kit::http::HeaderName::from_static(#name),
+                        ::api_kit::http::HeaderValue::try_from(
+                            ::std::string::ToString::to_string(#value),
+                        )?,
+                    );
+                }
+            };
+            if is_option(ty) {
+                let insert = insert(quote!(&value));
+                quote! {
+                    if let ::core::option::Option::Some(value) = #ident {
+                        #insert
+                    }
+                }
+            } else {
+                insert(quote!(&#ident))
+            }
+        })
+        .collect()
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let container = Container::parse(&input.attrs)?;
+    let fields = parse_fields(input, &container)?;
+
+    let Container {
+        method,
+        path,
+        auth,
+        response,
+        error,
+        format,
+        query_style,
+    } = &container;
+    let summary = doc_summary(&input.attrs).map_or_else(
+        || quote!(::core::option::Option::None),
+        |summary| quote!(::core::option::Option::Some(#summary)),
+    );
+
+    let idents = fields.iter().map(|field| field.helper.ident);
+    let (path_struct, path_args) = helper(input, &fields, &Location::Path, "__Path");
+    let (query_struct, query) = helper(input, &fields, &Location::Query, "__Query");
+    let (body_struct, body) = helper(input, &fields, &Location::Body, "__Body");
+    let headers = headers(&fields);
+    let build = if fields.iter().any(|field| field.location == Location::Body) {
+        quote!(::api_kit::body::build_request::<#format, _>(builder, #body)?)
+    } else {
+        quote!(builder.body(::api_kit::__private::bytes::BytesMut::new())?)
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::api_kit::Endpoint for #ident #ty_generics #where_clause {
+            type Error = #error;
+
+            const METADATA: ::api_kit::metadata::Metadata<'static> = ::api_kit::metadata::Metadata {
+                method: ::api_kit::http::Method::#method,
+                auth: &[#(&#auth),*],
+                path: #path,
+                query_style: ::api_kit::metadata::QueryStyle::#query_style,
+                summary: #summary,
+                ..::api_kit::metadata::Metadata::DEFAULT
+            };
+        }
+
+        impl #impl_generics ::api_kit::OutgoingRequest for #ident #ty_generics #where_clause {
+            type IncomingResponse = #response;
+
+            // The `Serialize` impls of the field structs repeat their bounds
+            #[allow(clippy::trait_duplication_in_bounds)]
+            fn try_into_http_request<A>(
+                self,
+                base_url: &str,
+                auth: A,
+                auth_data: &A::AuthData,
+            ) -> ::core::result::Result<
+                ::api_kit::http::Request<::api_kit::__private::bytes::BytesMut>,
+                ::api_kit::error::IntoHttpError,
+            >
+            where
+                A: ::api_kit::auth::Authenticator,
+            {
+                #path_struct
+                #query_struct
+                #body_struct
+
+                let Self { #(#idents),* } = self;
+                let url = <Self as ::api_kit::Endpoint>::METADATA.make_url(base_url, #path_args, #query)?;
+                let builder = <Self as ::api_kit::Endpoint>::METADATA.request_builder(url);
+                let mut req = #build;
+                ::api_kit::body::set_accept::<Self>(&mut req);
+                #headers
+                auth.authenticate(&mut req, auth_data)?;
+                ::core::result::Result::Ok(req)
+            }
+        }
+    })
+}
@@ -0,0 +1,144 @@
+//! `#[derive(IncomingResponse)]`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::{Data, DeriveInput, Fields, LitStr, Type};
+
+use crate::{
+    attr::{default_format, is_option, parse_api_kit_attrs, parse_header_name, set_once},
+    helper::{self, HelperField},
+};
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let mut request: Option<Type> = None;
+    let mut format: Option<Type> = None;
+    parse_api_kit_attrs(&input.attrs, |meta| {
+        if meta.path.is_ident("request") {
+            set_once(&meta, &mut request, meta.value()?.parse()?)
+        } else if meta.path.is_ident("format") {
+            set_once(&meta, &mut format, meta.value()?.parse()?)
+        } else {
+            Err(meta.error("unknown api_kit attribute"))
+        }
+    })?;
+
+    let request = request
+        .ok_or_else(|| syn::Error::new(Span::call_site(), "missing `#[api_kit(request = ...)]`"))?;
+    let format = format.map_or_else(default_format, ToTokens::into_token_stream);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let body = header_fields(input)?.map_or_else(
+        || quote!(::api_kit::body::from_response::<#format, _>(&res)?),
+        |(body_fields, header_fields)| from_headers(input, &format, &body_fields, &header_fields),
+    );
+
+    Ok(quote! {
+        impl #impl_generics ::api_kit::IncomingResponse for #ident #ty_generics #where_clause {
+            type OutgoingRequest = #request;
+
+            const EXPECTED_CONTENT_TYPE: ::core::option::Option<&'static str> =
+                ::core::option::Option::Some(<#format as ::api_kit::body::BodyFormat>::CONTENT_TYPE);
+
+            fn try_from_http_response(
+                res: ::api_kit::http::Response<::api_kit::__private::bytes::Bytes>,
+            ) -> ::core::result::Result<
+                Self,
+                ::api_kit::error::FromHttpResponseError<
+                    <#request as ::api_kit::Endpoint>::Error,
+                >,
+            > {
+                ::core::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+/// A header field and its lowercase header name.
+type HeaderField<'a> = (HelperField<'a>, LitStr);
+
+/// Split the fields of `input` into body and header fields,
+/// or `None` if there are no header fields.
+fn header_fields(
+    input: &DeriveInput,
+) -> syn::Result<Option<(Vec<HelperField<'_>>, Vec<HeaderField<'_>>)>> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    let mut body_fields = Vec::new();
+    let mut header_fields = Vec::new();
+    for field in fields {
+        let mut header = None;
+        parse_api_kit_attrs(&field.attrs, |meta| {
+            if meta.path.is_ident("header") {
+                set_once(&meta, &mut header, parse_header_name(&meta)?)
+            } else {
+                Err(meta.error("unknown api_kit attribute"))
+            }
+        })?;
+        match header {
+            Some(name) => header_fields.push((HelperField::new(field), name)),
+            None => body_fields.push(HelperField::new(field)),
+        }
+    }
+
+    Ok((!header_fields.is_empty()).then_some((body_fields, header_fields)))
+}
+
+/// Construct the response from its header fields and a helper struct holding its body fields.
+fn from_headers(
+    input: &DeriveInput,
+    format: &TokenStream,
+    body_fields: &[HelperField],
+    header_fields: &[HeaderField],
+) -> TokenStream {
+    let body = if body_fields.is_empty() {
+        TokenStream::new()
+    } else {
+        let name = format_ident!("__Body");
+        let fields: Vec<_> = body_fields.iter().collect();
+        let derive = quote!(::api_kit::__private::serde::Deserialize);
+        let attrs = helper::serde_attrs(&input.attrs);
+        let definition = helper::definition(input, &name, &derive, &attrs, &fields);
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+        let idents = body_fields.iter().map(|field| field.ident);
+        quote! {
+            #definition
+            let #name { #(#idents,)* .. } =
+                ::api_kit::body::from_response::<#format, #name #ty_generics>(&res)?;
+        }
+    };
+
+    let headers = header_fields.iter().map(|(field, name)| {
+        let ident = field.ident;
+        let ty = field.ty;
+        let value = quote!(res.headers().get(#name).cloned());
+        let value = if is_option(ty) {
+            value
+        } else {
+            quote! {
+                #value.ok_or_else(|| {
+                    ::api_kit::error::DeserializeError::MissingHeader(
+                        ::api_kit::http::HeaderName::from_static(#name),
+                    )
+                })?
+            }
+        };
+        quote!(let #ident: #ty = #value;)
+    });
+
+    let idents = body_fields
+        .iter()
+        .chain(header_fields.iter().map(|(field, _)| field))
+        .map(|field| field.ident);
+    quote! {{
+        #body
+        #(#headers)*
+        Self { #(#idents),* }
+    }}
+}
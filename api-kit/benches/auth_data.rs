@@ -0,0 +1,90 @@
+//! Compares building requests with borrowed authentication data
+//! to cloning the data for every request, as a high-QPS client would have to
+//! if authenticators took their data by value.
+
+// The benchmark only uses some of the dependencies
+#![allow(unused_crate_dependencies)]
+
+use std::hint::black_box;
+
+use api_kit::{
+    auth::{bearer::BearerAuth, Authenticator},
+    error::{FromHttpResponseError, GenericError, IntoHttpError},
+    http::{self, Method},
+    metadata::Metadata,
+    Endpoint, IncomingResponse, OutgoingRequest,
+};
+use bytes::{Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+#[derive(Debug, Clone)]
+struct GetItem;
+
+#[derive(Debug)]
+struct Item;
+
+impl Endpoint for GetItem {
+    type Error = GenericError;
+
+    const METADATA: Metadata<'static> = Metadata {
+        method: Method::GET,
+        auth: &[&BearerAuth::new()],
+        path: "/items/1",
+        ..Metadata::DEFAULT
+    };
+}
+
+impl OutgoingRequest for GetItem {
+    type IncomingResponse = Item;
+
+    fn try_into_http_request<A>(
+        self,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<http::Request<BytesMut>, IntoHttpError>
+    where
+        A: Authenticator,
+    {
+        let url = Self::METADATA.make_url(base_url, &(), &())?;
+        let mut req = Self::METADATA.request_builder(url).body(BytesMut::new())?;
+        auth.authenticate(&mut req, auth_data)?;
+        Ok(req)
+    }
+}
+
+impl IncomingResponse for Item {
+    type OutgoingRequest = GetItem;
+
+    fn try_from_http_response(
+        _res: http::Response<Bytes>,
+    ) -> Result<Self, FromHttpResponseError<GenericError>> {
+        Ok(Self)
+    }
+}
+
+fn auth_data(c: &mut Criterion) {
+    // A JWT-sized token
+    let token = "t".repeat(1024);
+
+    let mut group = c.benchmark_group("try_into_http_request");
+    group.bench_function("borrowed", |b| {
+        b.iter(|| {
+            GetItem
+                .try_into_http_request("https://example.com", BearerAuth::new(), black_box(&token))
+                .unwrap()
+        });
+    });
+    group.bench_function("cloned", |b| {
+        b.iter(|| {
+            let token = black_box(&token).clone();
+            GetItem
+                .try_into_http_request("https://example.com", BearerAuth::new(), &token)
+                .unwrap()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, auth_data);
+criterion_main!(benches);
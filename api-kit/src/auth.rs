@@ -1,20 +1,62 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug, future::Future};
 
 use bytes::BytesMut;
-use http::Request;
+use http::{HeaderName, Request};
 
 use crate::error::IntoHttpError;
 
+mod and;
+#[cfg(feature = "api-key-auth")]
+pub mod api_key;
 #[cfg(feature = "basic-auth")]
 pub mod basic;
 pub mod bearer;
+mod challenge;
+#[cfg(feature = "hmac-auth")]
+pub mod hmac;
+#[cfg(feature = "oauth2")]
+pub mod oauth2;
+pub mod query_key;
+mod registry;
+#[cfg(feature = "sigv4-auth")]
+pub mod sigv4;
+
+pub use self::{
+    and::AndAuth,
+    challenge::{parse_www_authenticate, Challenge},
+    registry::DynAuthenticator,
+};
+
+/// Returns the canonical form of an authentication scheme identifier,
+/// its ASCII lowercase, e.g. `bearer` for `Bearer`.
+///
+/// Scheme identifiers are compared in this form when matching the authenticator
+/// of a request with the [`Metadata::auth`](crate::metadata::Metadata::auth) schemes.
+#[must_use]
+pub fn canonical_scheme(scheme: &str) -> Cow<'_, str> {
+    if scheme.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(scheme.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(scheme)
+    }
+}
 
 /// Authentication schemes
 pub trait AuthScheme: Debug {
     /// Returns the name of the authentication scheme.
     ///
-    /// This must return a unique identifier for the scheme.
+    /// This must return a unique identifier for the scheme, which should be lowercase.
+    /// Identifiers are compared in their [canonical](canonical_scheme) ASCII lowercase form,
+    /// so `Bearer` and `bearer` are the same scheme.
     fn scheme(&self) -> &'static str;
+
+    /// Returns the headers the authenticator writes credentials to.
+    ///
+    /// This allows e.g. logging middleware to redact credentials without running the
+    /// authenticator. Schemes writing to a configurable header return `&[]` by default.
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        &[]
+    }
 }
 
 /// Authenticators
@@ -28,6 +70,9 @@ pub trait Authenticator: AuthScheme {
     ///
     /// For example, a bearer token authenticator would require a token,
     /// while a basic authenticator would require a username and password.
+    ///
+    /// Authentication data is passed by reference,
+    /// so the same credentials can be reused across requests without cloning them.
     type AuthData;
 
     /// Authenticate the request.
@@ -45,7 +90,7 @@ pub trait Authenticator: AuthScheme {
     fn authenticate(
         &self,
         req: &mut Request<BytesMut>,
-        data: Self::AuthData,
+        data: &Self::AuthData,
     ) -> Result<(), IntoHttpError>;
 }
 
@@ -61,8 +106,42 @@ impl Authenticator for () {
     fn authenticate(
         &self,
         _req: &mut Request<BytesMut>,
-        _data: Self::AuthData,
+        _data: &Self::AuthData,
     ) -> Result<(), IntoHttpError> {
         Ok(())
     }
 }
+
+/// Asynchronous authenticators
+///
+/// Like an [`Authenticator`], but authentication may perform I/O,
+/// e.g. to refresh an expired token from an OAuth token endpoint before signing.
+///
+/// Every [`Authenticator`] is also an asynchronous authenticator that completes immediately.
+pub trait AsyncAuthenticator: AuthScheme {
+    /// The type of extra data required for authentication.
+    ///
+    /// See [`Authenticator::AuthData`].
+    type AuthData;
+
+    /// Authenticate the request.
+    ///
+    /// See [`Authenticator::authenticate`].
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        data: &Self::AuthData,
+    ) -> impl Future<Output = Result<(), IntoHttpError>> + Send;
+}
+
+impl<A: Authenticator> AsyncAuthenticator for A {
+    type AuthData = A::AuthData;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        data: &Self::AuthData,
+    ) -> impl Future<Output = Result<(), IntoHttpError>> + Send {
+        std::future::ready(Authenticator::authenticate(self, req, data))
+    }
+}
@@ -0,0 +1,199 @@
+//! Combining multiple authentication schemes.
+
+use std::{
+    collections::BTreeMap,
+    sync::{PoisonError, RwLock},
+};
+
+use bytes::BytesMut;
+use http::{HeaderName, Request};
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::IntoHttpError,
+};
+
+/// Authenticator applying two authenticators in order.
+///
+/// This is used for endpoints requiring multiple credentials,
+/// e.g. both an API key header and a bearer token.
+/// Its scheme is the schemes of both authenticators joined by `+`, e.g. `api_key+bearer`,
+/// and its [`auth_headers`](AuthScheme::auth_headers) the headers of both.
+///
+/// As schemes and headers must be `'static`, the combined scheme and headers are
+/// allocated and leaked once per distinct combination of component schemes and headers,
+/// on first use. Later calls look them up without allocating.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct AndAuth<A, B>(pub A, pub B);
+
+impl<A, B> AndAuth<A, B> {
+    /// Combines two authenticators, applying `a` before `b`.
+    #[inline]
+    #[must_use]
+    pub const fn new(a: A, b: B) -> Self {
+        Self(a, b)
+    }
+}
+
+impl<A: AuthScheme, B: AuthScheme> AuthScheme for AndAuth<A, B> {
+    fn scheme(&self) -> &'static str {
+        // Combined schemes are interned, as schemes must be `'static`.
+        // Lookups are keyed by the component schemes, so they don't allocate.
+        static SCHEMES: RwLock<BTreeMap<(&'static str, &'static str), &'static str>> =
+            RwLock::new(BTreeMap::new());
+
+        let key = (self.0.scheme(), self.1.scheme());
+        let interned = SCHEMES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .copied();
+        interned.unwrap_or_else(|| {
+            *SCHEMES
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(key)
+                .or_insert_with(|| Box::leak(format!("{}+{}", key.0, key.1).into_boxed_str()))
+        })
+    }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        // Combined headers are interned by the addresses of both static slices,
+        // so each distinct combination is only allocated once.
+        static HEADERS: RwLock<BTreeMap<[usize; 4], &'static [HeaderName]>> =
+            RwLock::new(BTreeMap::new());
+
+        let (a, b) = (self.0.auth_headers(), self.1.auth_headers());
+        if b.is_empty() {
+            return a;
+        }
+        if a.is_empty() {
+            return b;
+        }
+        let key = [a.as_ptr().addr(), a.len(), b.as_ptr().addr(), b.len()];
+        let interned = HEADERS
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+            .copied();
+        interned.unwrap_or_else(|| {
+            *HEADERS
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(key)
+                .or_insert_with(|| {
+                    let combined: Vec<_> = a
+                        .iter()
+                        .chain(b.iter().filter(|name| !a.contains(name)))
+                        .cloned()
+                        .collect();
+                    Box::leak(combined.into_boxed_slice())
+                })
+        })
+    }
+}
+
+impl<A: Authenticator, B: Authenticator> Authenticator for AndAuth<A, B> {
+    type AuthData = (A::AuthData, B::AuthData);
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        data: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        self.0.authenticate(req, &data.0)?;
+        self.1.authenticate(req, &data.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header::AUTHORIZATION, HeaderValue};
+
+    use super::*;
+    use crate::auth::bearer::BearerAuth;
+
+    /// Writes a key to the `x-api-key` header.
+    #[derive(Debug)]
+    struct KeyAuth;
+
+    static X_API_KEY: [HeaderName; 1] = [HeaderName::from_static("x-api-key")];
+
+    impl AuthScheme for KeyAuth {
+        fn scheme(&self) -> &'static str {
+            "key"
+        }
+
+        fn auth_headers(&self) -> &'static [HeaderName] {
+            &X_API_KEY
+        }
+    }
+
+    impl Authenticator for KeyAuth {
+        type AuthData = String;
+
+        fn authenticate(
+            &self,
+            req: &mut Request<BytesMut>,
+            key: &Self::AuthData,
+        ) -> Result<(), IntoHttpError> {
+            req.headers_mut().insert(
+                HeaderName::from_static("x-api-key"),
+                HeaderValue::from_str(key)?,
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn both_applied() {
+        let auth = AndAuth::new(KeyAuth, BearerAuth::new());
+        assert_eq!(auth.scheme(), "key+bearer");
+        assert!(std::ptr::eq(auth.scheme(), auth.scheme()));
+        assert_eq!(
+            AndAuth::new(BearerAuth::new(), KeyAuth).scheme(),
+            "bearer+key"
+        );
+        assert_eq!(
+            AndAuth::new(AndAuth::new(KeyAuth, BearerAuth::new()), KeyAuth).scheme(),
+            "key+bearer+key"
+        );
+
+        let mut req = Request::new(BytesMut::new());
+        auth.authenticate(&mut req, &("key".to_owned(), "token".to_owned()))
+            .unwrap();
+        assert_eq!(req.headers()["x-api-key"], "key");
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+    }
+
+    #[test]
+    fn combined_auth_headers() {
+        let auth = AndAuth::new(KeyAuth, BearerAuth::new());
+        assert_eq!(auth.auth_headers(), ["x-api-key", AUTHORIZATION.as_str()]);
+        assert!(std::ptr::eq(auth.auth_headers(), auth.auth_headers()));
+
+        // Headers of both authenticators are listed once
+        let auth = AndAuth::new(BearerAuth::new(), AndAuth::new(KeyAuth, BearerAuth::new()));
+        assert_eq!(auth.auth_headers(), [AUTHORIZATION.as_str(), "x-api-key"]);
+        assert_eq!(
+            AndAuth::new((), BearerAuth::new()).auth_headers(),
+            [AUTHORIZATION]
+        );
+        assert!(AndAuth::new((), ()).auth_headers().is_empty());
+    }
+
+    #[test]
+    fn errors_propagate() {
+        let auth = AndAuth::new(KeyAuth, BearerAuth::new());
+        for data in [
+            ("bad\nkey".to_owned(), "token".to_owned()),
+            ("key".to_owned(), "bad\ntoken".to_owned()),
+        ] {
+            let mut req = Request::new(BytesMut::new());
+            assert!(matches!(
+                auth.authenticate(&mut req, &data),
+                Err(IntoHttpError::Header(_))
+            ));
+        }
+    }
+}
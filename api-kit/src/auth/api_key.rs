@@ -0,0 +1,78 @@
+//! API key authentication scheme.
+
+use bytes::BytesMut;
+use http::{HeaderName, HeaderValue, Request};
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::IntoHttpError,
+};
+
+/// API key authentication scheme.
+///
+/// This authenticator adds a static API key to a custom header of the request,
+/// such as `X-Api-Key` or `Api-Token`.
+/// The header value is marked as [sensitive](HeaderValue::set_sensitive).
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct ApiKeyAuth {
+    header: HeaderName,
+}
+
+impl ApiKeyAuth {
+    /// Creates an API key authenticator that writes the key to `header`.
+    #[inline]
+    #[must_use]
+    pub const fn new(header: HeaderName) -> Self {
+        Self { header }
+    }
+
+    /// Returns the header the API key is written to.
+    #[inline]
+    #[must_use]
+    pub const fn header(&self) -> &HeaderName {
+        &self.header
+    }
+}
+
+impl AuthScheme for ApiKeyAuth {
+    fn scheme(&self) -> &'static str {
+        "api_key"
+    }
+}
+
+impl Authenticator for ApiKeyAuth {
+    type AuthData = String;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        key: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        let mut value = HeaderValue::from_str(key)?;
+        value.set_sensitive(true);
+
+        req.headers_mut().insert(self.header.clone(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_key_header() {
+        let auth = ApiKeyAuth::new(HeaderName::from_static("x-api-key"));
+        assert_eq!(auth.scheme(), "api_key");
+
+        let mut req = Request::new(BytesMut::new());
+        auth.authenticate(&mut req, &"secret".to_owned()).unwrap();
+        assert_eq!(req.headers()["x-api-key"], "secret");
+        assert!(req.headers()["x-api-key"].is_sensitive());
+
+        assert!(matches!(
+            auth.authenticate(&mut req, &"bad\nkey".to_owned()),
+            Err(IntoHttpError::Header(_))
+        ));
+    }
+}
@@ -2,7 +2,7 @@
 
 use base64::{engine::general_purpose::STANDARD, Engine};
 use bytes::BytesMut;
-use http::{header::AUTHORIZATION, HeaderValue, Request};
+use http::{header::AUTHORIZATION, HeaderName, HeaderValue, Request};
 
 use crate::{
     auth::{AuthScheme, Authenticator},
@@ -21,6 +21,10 @@ impl AuthScheme for BasicAuth {
     fn scheme(&self) -> &'static str {
         "basic"
     }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        &[AUTHORIZATION]
+    }
 }
 
 impl Authenticator for BasicAuth {
@@ -29,7 +33,7 @@ impl Authenticator for BasicAuth {
     fn authenticate(
         &self,
         req: &mut Request<BytesMut>,
-        data: Self::AuthData,
+        data: &Self::AuthData,
     ) -> Result<(), IntoHttpError> {
         let auth = STANDARD.encode(format!("{}:{}", data.username, data.password));
         let header_val = HeaderValue::from_str(&format!("Basic {auth}"))?;
@@ -1,18 +1,89 @@
 use bytes::BytesMut;
-use http::{header::AUTHORIZATION, HeaderValue, Request};
+use http::{header::AUTHORIZATION, HeaderName, HeaderValue, Request};
 
 use crate::{
-    auth::{AuthScheme, Authenticator},
+    auth::{challenge::is_tchar, AuthScheme, Authenticator},
     error::IntoHttpError,
 };
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
-pub struct BearerAuth;
+/// The default scheme word of bearer tokens.
+const DEFAULT_SCHEME_WORD: &str = "Bearer";
+
+/// Bearer token authentication scheme.
+///
+/// This authenticator adds an `Authorization: Bearer <token>` header to the request.
+/// Some APIs expect a different scheme word, e.g. `Authorization: Token <token>`,
+/// which can be set with [`BearerAuth::with_scheme_word`].
+///
+/// `BearerAuth` was a unit struct before the scheme word was configurable,
+/// so it is now created with [`BearerAuth::new`] instead of `BearerAuth`,
+/// e.g. `auth: &[&BearerAuth::new()]` in [`Metadata`](crate::metadata::Metadata).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct BearerAuth {
+    scheme_word: &'static str,
+}
+
+impl BearerAuth {
+    /// Creates a bearer authenticator using the `Bearer` scheme word.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_scheme_word(DEFAULT_SCHEME_WORD)
+    }
+
+    /// Creates a bearer authenticator using a custom `scheme_word`, e.g. `Token` or `JWT`.
+    ///
+    /// The [scheme](AuthScheme::scheme) is still `bearer`,
+    /// as the authentication data is the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scheme_word` is not an HTTP token,
+    /// e.g. if it is empty or contains spaces,
+    /// which is a compile error when used in a constant.
+    #[inline]
+    #[must_use]
+    pub const fn with_scheme_word(scheme_word: &'static str) -> Self {
+        assert!(is_token(scheme_word), "scheme word must be an HTTP token");
+        Self { scheme_word }
+    }
+
+    /// Returns the scheme word written before the token.
+    #[inline]
+    #[must_use]
+    pub const fn scheme_word(&self) -> &'static str {
+        self.scheme_word
+    }
+}
+
+/// Whether `s` is a non-empty HTTP token (`token` in RFC 9110).
+const fn is_token(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_tchar(bytes[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    !bytes.is_empty()
+}
+
+impl Default for BearerAuth {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl AuthScheme for BearerAuth {
     fn scheme(&self) -> &'static str {
         "bearer"
     }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        &[AUTHORIZATION]
+    }
 }
 
 impl Authenticator for BearerAuth {
@@ -20,13 +91,45 @@ impl Authenticator for BearerAuth {
     fn authenticate(
         &self,
         req: &mut Request<BytesMut>,
-        token: Self::AuthData,
+        token: &Self::AuthData,
     ) -> Result<(), IntoHttpError> {
         let headers = req.headers_mut();
         headers.insert(
             AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {token}"))?,
+            HeaderValue::from_str(&format!("{} {token}", self.scheme_word))?,
         );
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authorization(auth: BearerAuth) -> HeaderValue {
+        let mut req = Request::new(BytesMut::new());
+        auth.authenticate(&mut req, &"abc".to_owned()).unwrap();
+        req.headers()[AUTHORIZATION].clone()
+    }
+
+    #[test]
+    fn scheme_word() {
+        assert_eq!(authorization(BearerAuth::default()), "Bearer abc");
+        assert_eq!(
+            authorization(BearerAuth::with_scheme_word("Token")),
+            "Token abc"
+        );
+        assert_eq!(BearerAuth::with_scheme_word("JWT").scheme(), "bearer");
+    }
+
+    #[test]
+    fn scheme_word_must_be_token() {
+        for word in ["", "Bearer token", "Bear\ner", "Bearer:"] {
+            assert!(
+                std::panic::catch_unwind(|| BearerAuth::with_scheme_word(word)).is_err(),
+                "{word:?}"
+            );
+        }
+        assert!(is_token("X-Token"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Authentication challenges.
+
+use http::HeaderValue;
+
+/// An authentication challenge from a `WWW-Authenticate` or `Proxy-Authenticate` header.
+///
+/// See [RFC 9110 section 11.6.1](https://datatracker.ietf.org/doc/html/rfc9110#section-11.6.1).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Challenge {
+    /// The authentication scheme, in lowercase (e.g. `bearer`).
+    ///
+    /// Lowercasing makes the scheme comparable with [`AuthScheme::scheme`](super::AuthScheme::scheme).
+    pub scheme: String,
+    /// The `token68` value of the challenge, if it has one instead of parameters.
+    pub token68: Option<String>,
+    /// The parameters of the challenge, with lowercase names and unquoted values.
+    pub params: Vec<(String, String)>,
+}
+
+impl Challenge {
+    /// Returns the value of the parameter `name`, compared case-insensitively.
+    #[must_use]
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the `realm` parameter of the challenge.
+    #[inline]
+    #[must_use]
+    pub fn realm(&self) -> Option<&str> {
+        self.param("realm")
+    }
+}
+
+/// Parses the challenges of a `WWW-Authenticate` header.
+///
+/// A header may contain multiple challenges,
+/// each with either a `token68` value or a list of parameters.
+/// Malformed challenges and parameters are skipped.
+#[must_use]
+pub fn parse_www_authenticate(value: &HeaderValue) -> Vec<Challenge> {
+    let mut parser = Parser {
+        input: value.as_bytes(),
+        pos: 0,
+    };
+    let mut challenges = Vec::new();
+
+    loop {
+        parser.skip_list_separators();
+        if parser.is_done() {
+            break;
+        }
+
+        let scheme = parser.token();
+        if scheme.is_empty() {
+            // Not a challenge, skip to the next list element
+            parser.skip_until_comma();
+            continue;
+        }
+
+        let mut challenge = Challenge {
+            scheme: scheme.to_ascii_lowercase(),
+            ..Challenge::default()
+        };
+
+        parser.skip_whitespace();
+        if let Some(token68) = parser.token68() {
+            challenge.token68 = Some(token68);
+        } else {
+            challenge.params = parser.auth_params();
+        }
+        challenges.push(challenge);
+    }
+
+    challenges
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    const fn is_done(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_list_separators(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b',')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_until_comma(&mut self) {
+        while self.peek().is_some_and(|b| b != b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(u8) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.input[start..self.pos]).into_owned()
+    }
+
+    fn token(&mut self) -> String {
+        self.take_while(is_tchar)
+    }
+
+    /// Parse a `token68` value, which must be followed by the end of the challenge.
+    fn token68(&mut self) -> Option<String> {
+        let start = self.pos;
+        let mut value = self.take_while(is_token68_char);
+        value.push_str(&self.take_while(|b| b == b'='));
+        self.skip_whitespace();
+
+        if value.is_empty() || !matches!(self.peek(), None | Some(b',')) || self.is_param(start) {
+            self.pos = start;
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Returns `true` if an auth-param starts at `pos`.
+    fn is_param(&mut self, pos: usize) -> bool {
+        let end = self.pos;
+        self.pos = pos;
+        let name = self.token();
+        self.skip_whitespace();
+        let is_param = !name.is_empty() && self.peek() == Some(b'=') && {
+            self.pos += 1;
+            self.skip_whitespace();
+            self.peek().is_some_and(|b| b == b'"' || is_tchar(b))
+        };
+        self.pos = end;
+        is_param
+    }
+
+    /// Parse the auth-params of the current challenge, skipping malformed ones.
+    fn auth_params(&mut self) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        loop {
+            self.skip_list_separators();
+            if self.is_done() || !self.is_param(self.pos) {
+                return params;
+            }
+
+            let name = self.token().to_ascii_lowercase();
+            self.skip_whitespace();
+            self.pos += 1; // '='
+            self.skip_whitespace();
+
+            let value = if self.peek() == Some(b'"') {
+                self.quoted_string()
+            } else {
+                Some(self.token())
+            };
+            self.skip_whitespace();
+
+            if matches!(self.peek(), None | Some(b',')) {
+                params.extend(value.map(|value| (name, value)));
+            } else {
+                self.skip_until_comma();
+            }
+        }
+    }
+
+    fn quoted_string(&mut self) -> Option<String> {
+        self.pos += 1; // opening quote
+        let mut value = Vec::new();
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.pos += 1;
+                    return Some(String::from_utf8_lossy(&value).into_owned());
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    value.push(self.peek()?);
+                }
+                b => value.push(b),
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+/// Whether `b` is a token character (`tchar` in RFC 9110).
+pub(super) const fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'!' | b'#'
+                | b'$'
+                | b'%'
+                | b'&'
+                | b'\''
+                | b'*'
+                | b'+'
+                | b'-'
+                | b'.'
+                | b'^'
+                | b'_'
+                | b'`'
+                | b'|'
+                | b'~'
+        )
+}
+
+const fn is_token68_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &'static str) -> Vec<Challenge> {
+        parse_www_authenticate(&HeaderValue::from_static(value))
+    }
+
+    fn challenge(scheme: &str, params: &[(&str, &str)]) -> Challenge {
+        Challenge {
+            scheme: scheme.into(),
+            token68: None,
+            params: params.iter().map(|&(n, v)| (n.into(), v.into())).collect(),
+        }
+    }
+
+    #[test]
+    fn bearer_and_basic() {
+        let challenges = parse(
+            r#"Bearer realm="example", error="invalid_token", error_description="The access token expired", Basic realm="simple""#,
+        );
+        assert_eq!(
+            challenges,
+            [
+                challenge(
+                    "bearer",
+                    &[
+                        ("realm", "example"),
+                        ("error", "invalid_token"),
+                        ("error_description", "The access token expired"),
+                    ]
+                ),
+                challenge("basic", &[("realm", "simple")]),
+            ]
+        );
+        assert_eq!(challenges[0].realm(), Some("example"));
+        assert_eq!(challenges[0].param("ERROR"), Some("invalid_token"));
+        assert_eq!(challenges[1].param("error"), None);
+    }
+
+    #[test]
+    fn token68() {
+        assert_eq!(
+            parse("Negotiate YIIB/ZQ==, NTLM"),
+            [
+                Challenge {
+                    scheme: "negotiate".into(),
+                    token68: Some("YIIB/ZQ==".into()),
+                    params: Vec::new(),
+                },
+                challenge("ntlm", &[]),
+            ]
+        );
+        // A single trailing `=` is still a token68, not a parameter
+        assert_eq!(parse("Custom abc=")[0].token68.as_deref(), Some("abc="));
+    }
+
+    #[test]
+    fn params() {
+        assert_eq!(
+            parse(r#"Digest Realm = "a \"quoted\", value" , qop=auth,algorithm=SHA-256,,"#),
+            [challenge(
+                "digest",
+                &[
+                    ("realm", r#"a "quoted", value"#),
+                    ("qop", "auth"),
+                    ("algorithm", "SHA-256"),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse("").is_empty());
+        assert!(parse(" , ,").is_empty());
+        assert_eq!(
+            parse(r#"Bearer realm="unterminated"#),
+            [challenge("bearer", &[])]
+        );
+        assert_eq!(
+            parse(r#"Bearer realm="a" b, error=x, "junk", Basic"#),
+            [
+                challenge("bearer", &[("error", "x")]),
+                challenge("basic", &[])
+            ]
+        );
+    }
+}
@@ -0,0 +1,510 @@
+//! HMAC request signing and verification.
+//!
+//! Requests are signed by computing an HMAC-SHA256 over the request body
+//! and writing the hex-encoded signature into a configurable header.
+//! If a timestamp header is configured, the signed message is `{timestamp}.{body}`,
+//! binding the signature to the time the request was sent.
+//!
+//! Servers receiving signed requests (e.g. webhooks) can check them with [`verify`]
+//! or an [`HmacVerifier`], which optionally provides replay protection.
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::BytesMut;
+use hmac::{Hmac, KeyInit, Mac};
+use http::{HeaderName, HeaderValue, Request};
+use sha2::Sha256;
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::{DeserializeError, FromHttpRequestError, IntoHttpError},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Optional prefix of a signature header value, e.g. `sha256=abcdef...`.
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// HMAC authentication scheme.
+///
+/// This authenticator signs the request body with HMAC-SHA256 using the secret passed as
+/// authentication data, and writes the hex-encoded signature into the configured header.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HmacAuth {
+    header: HeaderName,
+    timestamp_header: Option<HeaderName>,
+}
+
+impl HmacAuth {
+    /// Creates an HMAC authenticator writing the signature into `header`.
+    #[inline]
+    #[must_use]
+    pub const fn new(header: HeaderName) -> Self {
+        Self {
+            header,
+            timestamp_header: None,
+        }
+    }
+
+    /// Creates an HMAC authenticator that also signs the current UNIX timestamp.
+    ///
+    /// The timestamp (in seconds) is written into `timestamp_header`.
+    #[inline]
+    #[must_use]
+    pub const fn with_timestamp(header: HeaderName, timestamp_header: HeaderName) -> Self {
+        Self {
+            header,
+            timestamp_header: Some(timestamp_header),
+        }
+    }
+}
+
+impl AuthScheme for HmacAuth {
+    fn scheme(&self) -> &'static str {
+        "hmac"
+    }
+}
+
+impl Authenticator for HmacAuth {
+    type AuthData = Vec<u8>;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        secret: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        let timestamp = match &self.timestamp_header {
+            Some(header) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let mut buffer = itoa::Buffer::new();
+                let value = HeaderValue::from_str(buffer.format(now))?;
+                req.headers_mut().insert(header.clone(), value.clone());
+                Some(value)
+            }
+            None => None,
+        };
+
+        let mac = signature(
+            secret,
+            timestamp.as_ref().map(HeaderValue::as_bytes),
+            req.body(),
+        );
+        let header_val = HeaderValue::from_str(&hex::encode(mac.finalize().into_bytes()))?;
+        req.headers_mut().insert(self.header.clone(), header_val);
+
+        Ok(())
+    }
+}
+
+/// Verifier of HMAC signed requests.
+///
+/// This is the inverse of [`HmacAuth`].
+///
+/// # Replay protection
+///
+/// A verifier configured with [`HmacVerifier::replay_protection`] rejects requests whose
+/// signed timestamp is outside a tolerance window around the current time.
+/// Additionally, a [`NonceStore`] can be configured with [`HmacVerifier::nonces`]
+/// to reject requests that are replayed within the tolerance window.
+/// The signature of each accepted request is used as its nonce,
+/// as it is unique to the signed timestamp and body.
+#[derive(Debug, Clone)]
+pub struct HmacVerifier {
+    header: HeaderName,
+    timestamp_header: Option<HeaderName>,
+    tolerance: Option<Duration>,
+    nonces: Option<Arc<dyn NonceStore>>,
+}
+
+impl HmacVerifier {
+    /// Creates a verifier reading the signature from `header`.
+    #[inline]
+    #[must_use]
+    pub const fn new(header: HeaderName) -> Self {
+        Self {
+            header,
+            timestamp_header: None,
+            tolerance: None,
+            nonces: None,
+        }
+    }
+
+    /// Include the value of `timestamp_header` in the signed message.
+    #[inline]
+    #[must_use]
+    pub fn timestamp_header(mut self, timestamp_header: HeaderName) -> Self {
+        self.timestamp_header = Some(timestamp_header);
+        self
+    }
+
+    /// Reject requests whose signed UNIX timestamp differs from the current time
+    /// by more than `tolerance`.
+    ///
+    /// The timestamp is read from `timestamp_header` and included in the signed message,
+    /// as with [`HmacVerifier::timestamp_header`].
+    #[inline]
+    #[must_use]
+    pub fn replay_protection(mut self, timestamp_header: HeaderName, tolerance: Duration) -> Self {
+        self.timestamp_header = Some(timestamp_header);
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Track the nonces of accepted requests in `store`, rejecting replayed requests.
+    ///
+    /// Nonces expire after the [replay protection](HmacVerifier::replay_protection)
+    /// tolerance window, as older requests are rejected anyway.
+    /// Without replay protection, nonces never expire.
+    #[inline]
+    #[must_use]
+    pub fn nonces(mut self, store: Arc<dyn NonceStore>) -> Self {
+        self.nonces = Some(store);
+        self
+    }
+
+    /// Verify the signature of an incoming request.
+    ///
+    /// The signature header may optionally be prefixed with `sha256=`.
+    /// Signatures are compared in constant time.
+    ///
+    /// # Arguments
+    ///
+    /// * `req`: the incoming HTTP request object.
+    /// * `secret`: the shared secret the request was signed with.
+    ///
+    /// Returns: `Result<(), FromHttpRequestError>`
+    pub fn verify<B: AsRef<[u8]>>(
+        &self,
+        req: &Request<B>,
+        secret: &[u8],
+    ) -> Result<(), FromHttpRequestError> {
+        self.verify_at(req, secret, SystemTime::now())
+    }
+
+    /// Verify the signature of an incoming request at the given time.
+    ///
+    /// This is the same as [`HmacVerifier::verify`], but uses `now` as the current time
+    /// for replay protection.
+    pub fn verify_at<B: AsRef<[u8]>>(
+        &self,
+        req: &Request<B>,
+        secret: &[u8],
+        now: SystemTime,
+    ) -> Result<(), FromHttpRequestError> {
+        let signature_header = required_header(req, &self.header)?.to_str()?;
+        let signature_hex = signature_header
+            .strip_prefix(SIGNATURE_PREFIX)
+            .unwrap_or(signature_header);
+        let expected =
+            hex::decode(signature_hex).map_err(|_| FromHttpRequestError::InvalidSignature)?;
+
+        let timestamp = match &self.timestamp_header {
+            Some(header) => Some(required_header(req, header)?),
+            None => None,
+        };
+
+        signature(
+            secret,
+            timestamp.map(HeaderValue::as_bytes),
+            req.body().as_ref(),
+        )
+        .verify_slice(&expected)
+        .map_err(|_| FromHttpRequestError::InvalidSignature)?;
+
+        let expires_at = match (timestamp, self.tolerance) {
+            (Some(timestamp), Some(tolerance)) => {
+                let timestamp = parse_timestamp(timestamp)?;
+                let age = now
+                    .duration_since(timestamp)
+                    .unwrap_or_else(|e| e.duration());
+                if age > tolerance {
+                    return Err(FromHttpRequestError::Expired);
+                }
+                Some(timestamp + tolerance)
+            }
+            _ => None,
+        };
+
+        if let Some(nonces) = &self.nonces {
+            if !nonces.insert(&expected, expires_at, now) {
+                return Err(FromHttpRequestError::Replayed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A store of recently seen request nonces.
+///
+/// Implementations must be thread-safe, as a verifier may be shared across requests.
+pub trait NonceStore: Debug + Send + Sync {
+    /// Record `nonce` as seen, returning `false` if it has already been seen.
+    ///
+    /// The nonce may be forgotten once `now` passes `expires_at`.
+    /// A nonce without an expiry must be remembered indefinitely.
+    fn insert(&self, nonce: &[u8], expires_at: Option<SystemTime>, now: SystemTime) -> bool;
+}
+
+/// An in-memory [`NonceStore`].
+///
+/// Expired nonces are discarded on insertion.
+#[derive(Debug, Default)]
+pub struct MemoryNonceStore {
+    nonces: Mutex<HashMap<Vec<u8>, Option<SystemTime>>>,
+}
+
+impl MemoryNonceStore {
+    /// Creates an empty nonce store.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NonceStore for MemoryNonceStore {
+    fn insert(&self, nonce: &[u8], expires_at: Option<SystemTime>, now: SystemTime) -> bool {
+        let mut nonces = self
+            .nonces
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        nonces.retain(|_, expires_at| expires_at.is_none_or(|t| t > now));
+
+        if nonces.contains_key(nonce) {
+            return false;
+        }
+        nonces.insert(nonce.to_vec(), expires_at);
+        true
+    }
+}
+
+/// Verify the HMAC-SHA256 signature of an incoming request.
+///
+/// This is a shorthand for [`HmacVerifier::new(header_name).verify(req, secret)`](HmacVerifier::verify).
+pub fn verify<B: AsRef<[u8]>>(
+    req: &Request<B>,
+    secret: &[u8],
+    header_name: HeaderName,
+) -> Result<(), FromHttpRequestError> {
+    HmacVerifier::new(header_name).verify(req, secret)
+}
+
+fn required_header<'a, B>(
+    req: &'a Request<B>,
+    header: &HeaderName,
+) -> Result<&'a HeaderValue, DeserializeError> {
+    req.headers()
+        .get(header)
+        .ok_or_else(|| DeserializeError::MissingHeader(header.clone()))
+}
+
+fn parse_timestamp(value: &HeaderValue) -> Result<SystemTime, FromHttpRequestError> {
+    value
+        .to_str()
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs)))
+        .ok_or(FromHttpRequestError::InvalidTimestamp)
+}
+
+fn signature(secret: &[u8], timestamp: Option<&[u8]>, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    if let Some(timestamp) = timestamp {
+        mac.update(timestamp);
+        mac.update(b".");
+    }
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGNATURE: HeaderName = HeaderName::from_static("x-signature");
+    const TIMESTAMP: HeaderName = HeaderName::from_static("x-timestamp");
+
+    fn signed_request(auth: &HmacAuth, secret: &[u8]) -> Request<BytesMut> {
+        let mut req = Request::new(BytesMut::from(&b"{\"event\":\"ping\"}"[..]));
+        auth.authenticate(&mut req, &secret.to_vec()).unwrap();
+        req
+    }
+
+    #[test]
+    fn sign_known_vector() {
+        let mut req = Request::new(BytesMut::from(
+            &b"The quick brown fox jumps over the lazy dog"[..],
+        ));
+        HmacAuth::new(SIGNATURE)
+            .authenticate(&mut req, &b"key".to_vec())
+            .unwrap();
+        assert_eq!(
+            req.headers()[SIGNATURE],
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn verify_round_trip() {
+        let req = signed_request(&HmacAuth::new(SIGNATURE), b"secret");
+        verify(&req, b"secret", SIGNATURE).unwrap();
+        assert!(matches!(
+            verify(&req, b"wrong", SIGNATURE).unwrap_err(),
+            FromHttpRequestError::InvalidSignature
+        ));
+    }
+
+    #[test]
+    fn verify_prefixed_signature() {
+        let mut req = signed_request(&HmacAuth::new(SIGNATURE), b"secret");
+        let prefixed = format!("sha256={}", req.headers()[SIGNATURE].to_str().unwrap());
+        req.headers_mut()
+            .insert(SIGNATURE, HeaderValue::from_str(&prefixed).unwrap());
+        verify(&req, b"secret", SIGNATURE).unwrap();
+    }
+
+    #[test]
+    fn verify_tampered_body() {
+        let mut req = signed_request(&HmacAuth::new(SIGNATURE), b"secret");
+        req.body_mut().extend_from_slice(b" ");
+        assert!(matches!(
+            verify(&req, b"secret", SIGNATURE).unwrap_err(),
+            FromHttpRequestError::InvalidSignature
+        ));
+    }
+
+    #[test]
+    fn verify_missing_or_malformed_header() {
+        let req = Request::new(BytesMut::new());
+        assert!(matches!(
+            verify(&req, b"secret", SIGNATURE).unwrap_err(),
+            FromHttpRequestError::Deserialize(DeserializeError::MissingHeader(_))
+        ));
+
+        let mut req = Request::new(BytesMut::new());
+        req.headers_mut()
+            .insert(SIGNATURE, HeaderValue::from_static("not hex"));
+        assert!(matches!(
+            verify(&req, b"secret", SIGNATURE).unwrap_err(),
+            FromHttpRequestError::InvalidSignature
+        ));
+    }
+
+    #[test]
+    fn verify_with_timestamp() {
+        let auth = HmacAuth::with_timestamp(SIGNATURE, TIMESTAMP);
+        let mut req = signed_request(&auth, b"secret");
+        assert!(req.headers().contains_key(TIMESTAMP));
+
+        let verifier = HmacVerifier::new(SIGNATURE).timestamp_header(TIMESTAMP);
+        verifier.verify(&req, b"secret").unwrap();
+
+        // Without the timestamp, the signature doesn't match
+        assert!(verify(&req, b"secret", SIGNATURE).is_err());
+
+        // Changing the timestamp invalidates the signature
+        req.headers_mut()
+            .insert(TIMESTAMP, HeaderValue::from_static("0"));
+        assert!(matches!(
+            verifier.verify(&req, b"secret").unwrap_err(),
+            FromHttpRequestError::InvalidSignature
+        ));
+    }
+
+    fn timestamped_request(secret: &[u8], timestamp: &'static str) -> Request<BytesMut> {
+        let mut req = Request::new(BytesMut::from(&b"{\"event\":\"ping\"}"[..]));
+        req.headers_mut()
+            .insert(TIMESTAMP, HeaderValue::from_static(timestamp));
+        let mac = signature(secret, Some(timestamp.as_bytes()), req.body());
+        let header_val = HeaderValue::from_str(&hex::encode(mac.finalize().into_bytes())).unwrap();
+        req.headers_mut().insert(SIGNATURE, header_val);
+        req
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn replay_protection_window() {
+        let verifier =
+            HmacVerifier::new(SIGNATURE).replay_protection(TIMESTAMP, Duration::from_mins(5));
+        let req = timestamped_request(b"secret", "1000");
+
+        verifier.verify_at(&req, b"secret", at(1000)).unwrap();
+        verifier.verify_at(&req, b"secret", at(1300)).unwrap();
+        verifier.verify_at(&req, b"secret", at(700)).unwrap();
+        assert!(matches!(
+            verifier.verify_at(&req, b"secret", at(1301)).unwrap_err(),
+            FromHttpRequestError::Expired
+        ));
+        assert!(matches!(
+            verifier.verify_at(&req, b"secret", at(699)).unwrap_err(),
+            FromHttpRequestError::Expired
+        ));
+
+        // Signature is checked before the timestamp
+        assert!(matches!(
+            verifier.verify_at(&req, b"wrong", at(2000)).unwrap_err(),
+            FromHttpRequestError::InvalidSignature
+        ));
+    }
+
+    #[test]
+    fn replay_protection_invalid_timestamp() {
+        let verifier =
+            HmacVerifier::new(SIGNATURE).replay_protection(TIMESTAMP, Duration::from_mins(5));
+        let req = timestamped_request(b"secret", "yesterday");
+        assert!(matches!(
+            verifier.verify_at(&req, b"secret", at(1000)).unwrap_err(),
+            FromHttpRequestError::InvalidTimestamp
+        ));
+    }
+
+    #[test]
+    fn replay_protection_current_time() {
+        let auth = HmacAuth::with_timestamp(SIGNATURE, TIMESTAMP);
+        let req = signed_request(&auth, b"secret");
+        HmacVerifier::new(SIGNATURE)
+            .replay_protection(TIMESTAMP, Duration::from_mins(1))
+            .verify(&req, b"secret")
+            .unwrap();
+    }
+
+    #[test]
+    fn replay_protection_nonces() {
+        let verifier = HmacVerifier::new(SIGNATURE)
+            .replay_protection(TIMESTAMP, Duration::from_mins(5))
+            .nonces(Arc::new(MemoryNonceStore::new()));
+        let req = timestamped_request(b"secret", "1000");
+        let other = timestamped_request(b"secret", "1001");
+
+        verifier.verify_at(&req, b"secret", at(1000)).unwrap();
+        assert!(matches!(
+            verifier.verify_at(&req, b"secret", at(1001)).unwrap_err(),
+            FromHttpRequestError::Replayed
+        ));
+        verifier.verify_at(&other, b"secret", at(1001)).unwrap();
+    }
+
+    #[test]
+    fn memory_nonce_store_expiry() {
+        let store = MemoryNonceStore::new();
+        assert!(store.insert(b"a", Some(at(10)), at(0)));
+        assert!(store.insert(b"b", None, at(0)));
+        assert!(!store.insert(b"a", Some(at(10)), at(5)));
+        assert!(!store.insert(b"b", None, at(5)));
+
+        // `a` has expired, `b` never expires
+        assert!(store.insert(b"a", Some(at(20)), at(10)));
+        assert!(!store.insert(b"b", None, at(100)));
+    }
+}
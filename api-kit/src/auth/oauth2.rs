@@ -0,0 +1,363 @@
+//! OAuth 2.0 client credentials authentication.
+
+use std::{
+    fmt,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Bytes, BytesMut};
+use http::{
+    header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+    HeaderName, HeaderValue, Method, Request, Uri,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::{
+    auth::{AsyncAuthenticator, AuthScheme},
+    error::{IntoHttpError, OAuth2Error},
+};
+
+/// How long before expiry a cached token is refreshed by default.
+const DEFAULT_SKEW: Duration = Duration::from_secs(30);
+
+/// An HTTP client sending token requests.
+///
+/// This is implemented for [`reqwest::Client`] when the `reqwest` feature is enabled.
+pub trait TokenClient: Send + Sync {
+    /// Send a token request, returning the response.
+    fn send(
+        &self,
+        req: Request<Bytes>,
+    ) -> impl Future<Output = Result<http::Response<Bytes>, OAuth2Error>> + Send;
+}
+
+#[cfg(feature = "reqwest")]
+impl TokenClient for reqwest::Client {
+    async fn send(&self, req: Request<Bytes>) -> Result<http::Response<Bytes>, OAuth2Error> {
+        let transport = |err: reqwest::Error| OAuth2Error::Transport(Box::new(err));
+
+        let res = self
+            .execute(reqwest::Request::try_from(req).map_err(transport)?)
+            .await
+            .map_err(transport)?;
+        let status = res.status();
+        let headers = res.headers().clone();
+
+        let mut out = http::Response::new(res.bytes().await.map_err(transport)?);
+        *out.status_mut() = status;
+        *out.headers_mut() = headers;
+        Ok(out)
+    }
+}
+
+/// OAuth 2.0 client credentials authenticator.
+///
+/// This [`AsyncAuthenticator`] fetches an access token from the token endpoint using the
+/// [client credentials grant](https://datatracker.ietf.org/doc/html/rfc6749#section-4.4),
+/// and adds it to requests as a `Bearer` token.
+/// The client credentials are sent using HTTP Basic authentication.
+///
+/// The token is cached until it is within a configurable skew of its expiry.
+/// Concurrent requests share a single token refresh.
+pub struct OAuth2ClientCredentials<C> {
+    client: C,
+    token_url: Uri,
+    client_id: String,
+    client_secret: String,
+    scopes: Vec<String>,
+    skew: Duration,
+    token: Mutex<Option<CachedToken>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl<C: TokenClient> OAuth2ClientCredentials<C> {
+    /// Creates an authenticator fetching tokens from `token_url` using `client`.
+    #[must_use]
+    pub fn new(
+        client: C,
+        token_url: Uri,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            token_url,
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scopes: Vec::new(),
+            skew: DEFAULT_SKEW,
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Sets the scopes requested for the token.
+    #[must_use]
+    pub fn scopes<S: Into<String>>(mut self, scopes: impl IntoIterator<Item = S>) -> Self {
+        self.scopes = scopes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets how long before expiry a cached token is refreshed.
+    ///
+    /// Defaults to 30 seconds.
+    #[must_use]
+    pub const fn skew(mut self, skew: Duration) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    /// Discards the cached token, so the next request fetches a new one.
+    pub async fn invalidate(&self) {
+        *self.token.lock().await = None;
+    }
+
+    /// Returns the cached access token, fetching a new one if it is missing or expiring.
+    pub async fn access_token(&self) -> Result<String, OAuth2Error> {
+        // The lock is held while fetching, so concurrent requests share one refresh
+        let mut cached = self.token.lock().await;
+        let now = Instant::now();
+        if let Some(token) = cached.as_ref().filter(|token| {
+            token.expires_at.is_none_or(|exp| {
+                // A skew past the end of time treats every token as expiring
+                now.checked_add(self.skew)
+                    .is_some_and(|deadline| deadline < exp)
+            })
+        }) {
+            return Ok(token.access_token.clone());
+        }
+
+        let token = self.fetch_token().await?;
+        let access_token = token.access_token.clone();
+        *cached = Some(token);
+        drop(cached);
+        Ok(access_token)
+    }
+
+    async fn fetch_token(&self) -> Result<CachedToken, OAuth2Error> {
+        let mut form = vec![("grant_type", "client_credentials".to_owned())];
+        if !self.scopes.is_empty() {
+            form.push(("scope", self.scopes.join(" ")));
+        }
+        let body = serde_urlencoded::to_string(form)
+            .map_err(|err| OAuth2Error::Transport(Box::new(err)))?;
+
+        // Credentials are form-encoded before being used for Basic authentication
+        let credentials = STANDARD.encode(format!(
+            "{}:{}",
+            utf8_percent_encode(&self.client_id, NON_ALPHANUMERIC),
+            utf8_percent_encode(&self.client_secret, NON_ALPHANUMERIC),
+        ));
+        let authorization = HeaderValue::from_str(&format!("Basic {credentials}"))
+            .map_err(|err| OAuth2Error::Transport(Box::new(err)))?;
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(self.token_url.clone())
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            )
+            .header(ACCEPT, HeaderValue::from_static("application/json"))
+            .header(AUTHORIZATION, authorization)
+            .body(Bytes::from(body))
+            .map_err(|err| OAuth2Error::Transport(Box::new(err)))?;
+
+        let requested_at = Instant::now();
+        let res = self.client.send(req).await?;
+        if !res.status().is_success() {
+            return Err(OAuth2Error::Status {
+                status: res.status(),
+                error: serde_json::from_slice::<ErrorResponse>(res.body())
+                    .ok()
+                    .map(|res| res.error),
+            });
+        }
+
+        let token: TokenResponse = serde_json::from_slice(res.body())?;
+        Ok(CachedToken {
+            access_token: token.access_token,
+            // An expiry too far out to represent is treated as never expiring
+            expires_at: token
+                .expires_in
+                .and_then(|secs| requested_at.checked_add(Duration::from_secs(secs))),
+        })
+    }
+}
+
+impl<C> fmt::Debug for OAuth2ClientCredentials<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2ClientCredentials")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("scopes", &self.scopes)
+            .field("skew", &self.skew)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C> AuthScheme for OAuth2ClientCredentials<C> {
+    fn scheme(&self) -> &'static str {
+        "bearer"
+    }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        &[AUTHORIZATION]
+    }
+}
+
+impl<C: TokenClient> AsyncAuthenticator for OAuth2ClientCredentials<C> {
+    type AuthData = ();
+
+    async fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        _data: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        let token = self.access_token().await?;
+        req.headers_mut().insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}"))?,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use http::StatusCode;
+
+    use super::*;
+    use crate::tests::now_or_never;
+
+    /// A token endpoint issuing numbered tokens.
+    #[derive(Debug)]
+    struct MockClient {
+        expires_in: u64,
+        status: StatusCode,
+        calls: AtomicUsize,
+    }
+
+    impl MockClient {
+        const fn new(expires_in: u64) -> Self {
+            Self {
+                expires_in,
+                status: StatusCode::OK,
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl TokenClient for MockClient {
+        async fn send(&self, req: Request<Bytes>) -> Result<http::Response<Bytes>, OAuth2Error> {
+            assert_eq!(req.method(), Method::POST);
+            assert_eq!(req.uri(), "https://auth.example.com/token");
+            // "client id:s3cret"
+            assert_eq!(
+                req.headers()[AUTHORIZATION],
+                "Basic Y2xpZW50JTIwaWQ6czNjcmV0"
+            );
+            assert_eq!(req.body(), "grant_type=client_credentials&scope=read+write");
+
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let body = if self.status.is_success() {
+                format!(
+                    r#"{{"access_token":"token-{n}","token_type":"Bearer","expires_in":{}}}"#,
+                    self.expires_in
+                )
+            } else {
+                r#"{"error":"invalid_client"}"#.to_owned()
+            };
+            let mut res = http::Response::new(Bytes::from(body));
+            *res.status_mut() = self.status;
+            Ok(res)
+        }
+    }
+
+    fn auth(client: MockClient) -> OAuth2ClientCredentials<MockClient> {
+        OAuth2ClientCredentials::new(
+            client,
+            Uri::from_static("https://auth.example.com/token"),
+            "client id",
+            "s3cret",
+        )
+        .scopes(["read", "write"])
+    }
+
+    fn authorization(auth: &OAuth2ClientCredentials<MockClient>) -> HeaderValue {
+        let mut req = Request::new(BytesMut::new());
+        now_or_never(AsyncAuthenticator::authenticate(auth, &mut req, &())).unwrap();
+        req.headers()[AUTHORIZATION].clone()
+    }
+
+    #[test]
+    fn token_cached() {
+        let auth = auth(MockClient::new(3600));
+        assert_eq!(authorization(&auth), "Bearer token-1");
+        assert_eq!(authorization(&auth), "Bearer token-1");
+        assert_eq!(auth.client.calls.load(Ordering::SeqCst), 1);
+
+        now_or_never(auth.invalidate());
+        assert_eq!(authorization(&auth), "Bearer token-2");
+    }
+
+    #[test]
+    fn token_refreshed_within_skew() {
+        let auth = auth(MockClient::new(10)).skew(Duration::from_secs(20));
+        assert_eq!(authorization(&auth), "Bearer token-1");
+        assert_eq!(authorization(&auth), "Bearer token-2");
+    }
+
+    #[test]
+    fn token_huge_expiry() {
+        let auth = auth(MockClient::new(u64::MAX));
+        assert_eq!(authorization(&auth), "Bearer token-1");
+        assert_eq!(authorization(&auth), "Bearer token-1");
+    }
+
+    #[test]
+    fn token_huge_skew() {
+        let auth = auth(MockClient::new(3600)).skew(Duration::MAX);
+        assert_eq!(authorization(&auth), "Bearer token-1");
+        assert_eq!(authorization(&auth), "Bearer token-2");
+    }
+
+    #[test]
+    fn token_error() {
+        let auth = auth(MockClient {
+            status: StatusCode::UNAUTHORIZED,
+            ..MockClient::new(0)
+        });
+        let mut req = Request::new(BytesMut::new());
+        let err = now_or_never(AsyncAuthenticator::authenticate(&auth, &mut req, &())).unwrap_err();
+        assert!(matches!(
+            err,
+            IntoHttpError::OAuth2(OAuth2Error::Status {
+                status: StatusCode::UNAUTHORIZED,
+                error: Some(error),
+            }) if error == "invalid_client"
+        ));
+        assert!(!format!("{auth:?}").contains("s3cret"));
+    }
+}
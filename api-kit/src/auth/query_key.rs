@@ -0,0 +1,105 @@
+//! Query parameter API key authentication scheme.
+
+use bytes::BytesMut;
+use http::{uri::PathAndQuery, Request, Uri};
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::IntoHttpError,
+};
+
+/// Query parameter API key authentication scheme.
+///
+/// This authenticator appends the API key to the query string of the request URI,
+/// e.g. `?key=...`, keeping any existing query parameters.
+/// The key is percent-encoded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct QueryKeyAuth {
+    param: &'static str,
+}
+
+impl QueryKeyAuth {
+    /// Creates an authenticator that adds the key as the query parameter `param`.
+    #[inline]
+    #[must_use]
+    pub const fn new(param: &'static str) -> Self {
+        Self { param }
+    }
+
+    /// Returns the name of the query parameter the key is added as.
+    #[inline]
+    #[must_use]
+    pub const fn param(&self) -> &'static str {
+        self.param
+    }
+}
+
+impl AuthScheme for QueryKeyAuth {
+    fn scheme(&self) -> &'static str {
+        "query_key"
+    }
+}
+
+impl Authenticator for QueryKeyAuth {
+    type AuthData = String;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        key: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        let pair = serde_urlencoded::to_string([(self.param, key)])?;
+
+        let mut parts = std::mem::take(req.uri_mut()).into_parts();
+        let path = parts
+            .path_and_query
+            .as_ref()
+            .map_or("/", PathAndQuery::path);
+        let query = parts
+            .path_and_query
+            .as_ref()
+            .and_then(PathAndQuery::query)
+            .filter(|query| !query.is_empty());
+        let path_and_query = query.map_or_else(
+            || format!("{path}?{pair}"),
+            |query| format!("{path}?{query}&{pair}"),
+        );
+        parts.path_and_query = Some(PathAndQuery::try_from(path_and_query)?);
+
+        *req.uri_mut() = Uri::from_parts(parts).map_err(http::Error::from)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticate(uri: &str, key: &str) -> Uri {
+        let mut req = Request::builder().uri(uri).body(BytesMut::new()).unwrap();
+        QueryKeyAuth::new("key")
+            .authenticate(&mut req, &key.to_owned())
+            .unwrap();
+        req.uri().clone()
+    }
+
+    #[test]
+    fn query_key() {
+        assert_eq!(
+            authenticate("https://example.com/maps", "abc"),
+            "https://example.com/maps?key=abc"
+        );
+        assert_eq!(
+            authenticate("https://example.com/maps?q=a%20b&page=2", "abc"),
+            "https://example.com/maps?q=a%20b&page=2&key=abc"
+        );
+        assert_eq!(
+            authenticate("https://example.com/maps?", "a&b=c d/é"),
+            "https://example.com/maps?key=a%26b%3Dc+d%2F%C3%A9"
+        );
+        assert_eq!(
+            authenticate("https://example.com", "abc"),
+            "https://example.com/?key=abc"
+        );
+    }
+}
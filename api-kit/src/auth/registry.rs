@@ -0,0 +1,233 @@
+//! Selecting authenticators at runtime.
+
+use std::{collections::BTreeMap, fmt};
+
+use bytes::BytesMut;
+use http::Request;
+
+use crate::{
+    auth::{canonical_scheme, Authenticator},
+    error::IntoHttpError,
+    metadata::Metadata,
+};
+
+/// An authenticator together with its authentication data.
+trait BoundAuthenticator: Send + Sync {
+    fn authenticate(&self, req: &mut Request<BytesMut>) -> Result<(), IntoHttpError>;
+}
+
+struct Bound<A: Authenticator> {
+    auth: A,
+    data: A::AuthData,
+}
+
+impl<A> BoundAuthenticator for Bound<A>
+where
+    A: Authenticator + Send + Sync,
+    A::AuthData: Send + Sync,
+{
+    fn authenticate(&self, req: &mut Request<BytesMut>) -> Result<(), IntoHttpError> {
+        self.auth.authenticate(req, &self.data)
+    }
+}
+
+/// A set of authenticators, selected at runtime by the schemes an endpoint accepts.
+///
+/// Each authenticator is registered with its authentication data,
+/// keyed by its [`AuthScheme::scheme`](super::AuthScheme::scheme)
+/// in [canonical](canonical_scheme) lowercase form.
+/// This lets a generic client handle endpoints with differing authentication
+/// without hardcoding the authenticator type.
+#[derive(Default)]
+pub struct DynAuthenticator {
+    authenticators: BTreeMap<String, Box<dyn BoundAuthenticator>>,
+}
+
+impl DynAuthenticator {
+    /// Creates an empty set of authenticators.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            authenticators: BTreeMap::new(),
+        }
+    }
+
+    /// Registers an authenticator with its authentication data.
+    ///
+    /// An authenticator previously registered for the same scheme is replaced.
+    pub fn register<A>(&mut self, auth: A, data: A::AuthData) -> &mut Self
+    where
+        A: Authenticator + Send + Sync + 'static,
+        A::AuthData: Send + Sync + 'static,
+    {
+        self.authenticators.insert(
+            canonical_scheme(auth.scheme()).into_owned(),
+            Box::new(Bound { auth, data }),
+        );
+        self
+    }
+
+    /// Returns `true` if an authenticator is registered for `scheme`, ignoring ASCII case.
+    #[must_use]
+    pub fn contains(&self, scheme: &str) -> bool {
+        self.authenticators
+            .contains_key(canonical_scheme(scheme).as_ref())
+    }
+
+    /// Authenticates a request for the endpoint described by `meta`.
+    ///
+    /// The schemes of [`Metadata::auth`] are tried in order,
+    /// and the first registered authenticator is applied.
+    /// Endpoints without authentication schemes are left unauthenticated.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IntoHttpError::MissingAuth`] if no accepted scheme is registered,
+    /// or the error of the selected authenticator.
+    pub fn authenticate_for(
+        &self,
+        meta: &Metadata<'_>,
+        req: &mut Request<BytesMut>,
+    ) -> Result<(), IntoHttpError> {
+        if meta.auth.is_empty() {
+            return Ok(());
+        }
+        meta.auth
+            .iter()
+            .find_map(|scheme| {
+                self.authenticators
+                    .get(canonical_scheme(scheme.scheme()).as_ref())
+            })
+            .ok_or(IntoHttpError::MissingAuth)?
+            .authenticate(req)
+    }
+}
+
+impl fmt::Debug for DynAuthenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynAuthenticator")
+            .field("schemes", &self.authenticators.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use http::header::AUTHORIZATION;
+
+    use super::*;
+    use crate::auth::{bearer::BearerAuth, query_key::QueryKeyAuth, AuthScheme};
+
+    const KEY: QueryKeyAuth = QueryKeyAuth::new("key");
+
+    fn request() -> Request<BytesMut> {
+        Request::builder()
+            .uri("https://example.com/items")
+            .body(BytesMut::new())
+            .unwrap()
+    }
+
+    #[test]
+    fn first_registered_scheme() {
+        let mut auth = DynAuthenticator::new();
+        auth.register(BearerAuth::new(), "token".to_owned())
+            .register(KEY, "secret".to_owned());
+        assert!(auth.contains("bearer"));
+        assert_eq!(
+            format!("{auth:?}"),
+            r#"DynAuthenticator { schemes: ["bearer", "query_key"] }"#
+        );
+
+        let meta = Metadata {
+            auth: &[&KEY, &BearerAuth::new()],
+            ..Metadata::default()
+        };
+        let mut req = request();
+        auth.authenticate_for(&meta, &mut req).unwrap();
+        assert_eq!(req.uri(), "https://example.com/items?key=secret");
+        assert!(!req.headers().contains_key(AUTHORIZATION));
+
+        let meta = Metadata {
+            auth: &[&(), &BearerAuth::new()],
+            ..Metadata::default()
+        };
+        let mut req = request();
+        auth.authenticate_for(&meta, &mut req).unwrap();
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+    }
+
+    #[test]
+    fn mixed_case_scheme() {
+        #[derive(Debug)]
+        struct UpperBearer;
+
+        impl AuthScheme for UpperBearer {
+            fn scheme(&self) -> &'static str {
+                "Bearer"
+            }
+        }
+
+        impl Authenticator for UpperBearer {
+            type AuthData = ();
+
+            fn authenticate(
+                &self,
+                req: &mut Request<BytesMut>,
+                _data: &Self::AuthData,
+            ) -> Result<(), IntoHttpError> {
+                BearerAuth::new().authenticate(req, &"upper".to_owned())
+            }
+        }
+
+        assert_eq!(canonical_scheme("Bearer"), "bearer");
+        assert!(matches!(
+            canonical_scheme("bearer"),
+            Cow::Borrowed("bearer")
+        ));
+
+        let mut auth = DynAuthenticator::new();
+        auth.register(UpperBearer, ());
+        assert!(auth.contains("bearer"));
+        assert!(auth.contains("BEARER"));
+
+        let meta = Metadata {
+            auth: &[&BearerAuth::new()],
+            ..Metadata::default()
+        };
+        assert!(meta.contains_auth(&UpperBearer));
+        let mut req = request();
+        auth.authenticate_for(&meta, &mut req).unwrap();
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer upper");
+
+        // A later registration for the same scheme replaces it
+        auth.register(BearerAuth::new(), "token".to_owned());
+        assert_eq!(
+            format!("{auth:?}"),
+            r#"DynAuthenticator { schemes: ["bearer"] }"#
+        );
+    }
+
+    #[test]
+    fn missing_scheme() {
+        let mut auth = DynAuthenticator::new();
+        auth.register(BearerAuth::new(), "token".to_owned());
+
+        let meta = Metadata {
+            auth: &[&KEY],
+            ..Metadata::default()
+        };
+        assert!(matches!(
+            auth.authenticate_for(&meta, &mut request()),
+            Err(IntoHttpError::MissingAuth)
+        ));
+
+        // Endpoints without authentication
+        let mut req = request();
+        auth.authenticate_for(&Metadata::default(), &mut req)
+            .unwrap();
+        assert!(req.headers().is_empty());
+    }
+}
@@ -0,0 +1,482 @@
+//! AWS Signature Version 4 request signing.
+//!
+//! Requests are signed as described in the
+//! [AWS documentation](https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html).
+//! The canonical request is built from the method, URI path, sorted query string,
+//! signed headers, and a SHA-256 hash of the body,
+//! so signing must happen after the body is serialized, as [`Authenticator`]s do.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::BytesMut;
+use hmac::{Hmac, KeyInit, Mac};
+use http::{
+    header::{AUTHORIZATION, EXPECT, HOST, USER_AGENT},
+    HeaderName, HeaderValue, Request,
+};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::IntoHttpError,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+const X_AMZ_CONTENT_SHA256: HeaderName = HeaderName::from_static("x-amz-content-sha256");
+const X_AMZ_DATE: HeaderName = HeaderName::from_static("x-amz-date");
+const X_AMZ_SECURITY_TOKEN: HeaderName = HeaderName::from_static("x-amz-security-token");
+
+/// Characters percent-encoded by `SigV4`: everything except unreserved characters.
+const URI_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// AWS Signature Version 4 authentication scheme.
+///
+/// This authenticator signs the request with the [`SigV4Credentials`] passed as
+/// authentication data, writing the `Authorization` and `X-Amz-Date` headers,
+/// and the `X-Amz-Security-Token` header for temporary credentials.
+///
+/// All headers of the request are signed, except `Authorization`, `User-Agent` and `Expect`,
+/// which may be changed by proxies.
+/// The `Host` header is signed as well, taken from the request URI if not set.
+///
+/// Services such as S3 also require the hash of the body in the `X-Amz-Content-Sha256` header,
+/// see [`SigV4Auth::with_content_sha256`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SigV4Auth {
+    encode_path_twice: bool,
+    content_sha256: bool,
+}
+
+impl SigV4Auth {
+    /// Creates a `SigV4` authenticator for most AWS services.
+    ///
+    /// The already percent-encoded URI path is encoded again in the canonical request.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            encode_path_twice: true,
+            content_sha256: false,
+        }
+    }
+
+    /// Creates a `SigV4` authenticator for Amazon S3.
+    ///
+    /// S3 expects the URI path to be percent-encoded only once in the canonical request,
+    /// and the `X-Amz-Content-Sha256` header to be set.
+    #[inline]
+    #[must_use]
+    pub const fn s3() -> Self {
+        Self {
+            encode_path_twice: false,
+            content_sha256: true,
+        }
+    }
+
+    /// Write the hex-encoded SHA-256 hash of the body into the `X-Amz-Content-Sha256` header.
+    ///
+    /// The header is signed along with the other headers.
+    #[inline]
+    #[must_use]
+    pub const fn with_content_sha256(mut self) -> Self {
+        self.content_sha256 = true;
+        self
+    }
+
+    /// Sign the request as if it were sent at `time`.
+    ///
+    /// [`Authenticator::authenticate`] signs requests at the current time.
+    pub fn sign(
+        &self,
+        req: &mut Request<BytesMut>,
+        credentials: &SigV4Credentials,
+        time: SystemTime,
+    ) -> Result<(), IntoHttpError> {
+        let amz_date = format_amz_date(time);
+        let date = &amz_date[..8];
+        // Authenticators run after the body is serialized, so this is the hash of the final body
+        let payload_hash = hex::encode(Sha256::digest(req.body()));
+
+        if !req.headers().contains_key(HOST) {
+            let host = req.uri().authority().ok_or(IntoHttpError::MissingHost)?;
+            let host = HeaderValue::from_str(host.as_str())?;
+            req.headers_mut().insert(HOST, host);
+        }
+        req.headers_mut()
+            .insert(X_AMZ_DATE, HeaderValue::from_str(&amz_date)?);
+        if self.content_sha256 {
+            req.headers_mut()
+                .insert(X_AMZ_CONTENT_SHA256, HeaderValue::from_str(&payload_hash)?);
+        }
+        if let Some(token) = &credentials.session_token {
+            let mut token = HeaderValue::from_str(token)?;
+            token.set_sensitive(true);
+            req.headers_mut().insert(X_AMZ_SECURITY_TOKEN, token);
+        }
+
+        let (canonical_request, signed_headers) = self.canonical_request(req, &payload_hash);
+        let scope = format!(
+            "{date}/{}/{}/aws4_request",
+            credentials.region, credentials.service
+        );
+        let string_to_sign = format!(
+            "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = [
+            date,
+            credentials.region.as_str(),
+            credentials.service.as_str(),
+            "aws4_request",
+        ]
+        .iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+            |key, data| hmac(&key, data.as_bytes()),
+        );
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+        let mut authorization = HeaderValue::from_str(&format!(
+            "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            credentials.access_key_id
+        ))?;
+        authorization.set_sensitive(true);
+        req.headers_mut().insert(AUTHORIZATION, authorization);
+
+        Ok(())
+    }
+
+    /// Build the canonical request, returning it and its signed headers.
+    fn canonical_request(self, req: &Request<BytesMut>, payload_hash: &str) -> (String, String) {
+        let path = match req.uri().path() {
+            "" => "/",
+            path => path,
+        };
+        let path = if self.encode_path_twice {
+            path.split('/')
+                .map(|segment| utf8_percent_encode(segment, URI_ENCODE_SET).to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        } else {
+            path.to_owned()
+        };
+
+        let mut query = req
+            .uri()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (uri_encode(name), uri_encode(value))
+            })
+            .collect::<Vec<_>>();
+        query.sort_unstable();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut headers = BTreeMap::<&str, Vec<String>>::new();
+        for (name, value) in req.headers() {
+            if [AUTHORIZATION, USER_AGENT, EXPECT].contains(name) {
+                continue;
+            }
+            let value = String::from_utf8_lossy(value.as_bytes());
+            headers
+                .entry(name.as_str())
+                .or_default()
+                .push(value.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        let canonical_headers = headers
+            .iter()
+            .fold(String::new(), |mut out, (name, values)| {
+                let _ = writeln!(out, "{name}:{}", values.join(","));
+                out
+            });
+        let signed_headers = headers.keys().copied().collect::<Vec<_>>().join(";");
+
+        let canonical_request = format!(
+            "{}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+            req.method(),
+        );
+        (canonical_request, signed_headers)
+    }
+}
+
+impl Default for SigV4Auth {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The headers `SigV4` writes credentials to.
+static AUTH_HEADERS: [HeaderName; 2] = [AUTHORIZATION, X_AMZ_SECURITY_TOKEN];
+
+impl AuthScheme for SigV4Auth {
+    fn scheme(&self) -> &'static str {
+        "sigv4"
+    }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        &AUTH_HEADERS
+    }
+}
+
+impl Authenticator for SigV4Auth {
+    type AuthData = SigV4Credentials;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        credentials: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        self.sign(req, credentials, SystemTime::now())
+    }
+}
+
+/// `SigV4` authentication data.
+///
+/// This data (credentials and the region and service signed for) is required for `SigV4`
+/// authentication.
+///
+/// The [`Debug`](fmt::Debug) output leaves out the secret access key and session token.
+#[derive(Clone, Eq, PartialEq)]
+pub struct SigV4Credentials {
+    /// Access key ID, e.g. `AKIDEXAMPLE`, which is sent in the `Authorization` header.
+    pub access_key_id: String,
+    /// Secret access key the signing key is derived from. It is never sent.
+    pub secret_access_key: String,
+    /// Session token of temporary credentials.
+    pub session_token: Option<String>,
+    /// The region requests are signed for, e.g. `us-east-1`.
+    pub region: String,
+    /// The service requests are signed for, e.g. `s3`.
+    pub service: String,
+}
+
+impl SigV4Credentials {
+    /// Create credentials for signing requests to `service` in `region`,
+    /// without a session token.
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        access_key_id: String,
+        secret_access_key: String,
+        region: String,
+        service: String,
+    ) -> Self {
+        Self {
+            access_key_id,
+            secret_access_key,
+            session_token: None,
+            region,
+            service,
+        }
+    }
+
+    /// Sets the session token of temporary credentials.
+    #[inline]
+    #[must_use]
+    pub fn session_token(mut self, session_token: String) -> Self {
+        self.session_token = Some(session_token);
+        self
+    }
+}
+
+impl fmt::Debug for SigV4Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigV4Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("region", &self.region)
+            .field("service", &self.service)
+            .finish_non_exhaustive()
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Decode and re-encode a query string component with the `SigV4` encoding.
+fn uri_encode(s: &str) -> String {
+    let decoded = percent_decode_str(&s.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned();
+    utf8_percent_encode(&decoded, URI_ENCODE_SET).to_string()
+}
+
+/// Format `time` as `YYYYMMDD'T'HHMMSS'Z'` in UTC.
+fn format_amz_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // Convert days since the epoch into a civil date,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Credentials of the AWS `SigV4` test suite.
+    fn credentials() -> SigV4Credentials {
+        SigV4Credentials::new(
+            "AKIDEXAMPLE".into(),
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            "us-east-1".into(),
+            "service".into(),
+        )
+    }
+
+    /// 2015-08-30T12:36:00Z
+    fn time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_mins(24_015_636)
+    }
+
+    fn signed(uri: &str, credentials: &SigV4Credentials) -> Request<BytesMut> {
+        let mut req = Request::builder().uri(uri).body(BytesMut::new()).unwrap();
+        SigV4Auth::new()
+            .sign(&mut req, credentials, time())
+            .unwrap();
+        req
+    }
+
+    #[test]
+    fn amz_date() {
+        assert_eq!(format_amz_date(time()), "20150830T123600Z");
+        assert_eq!(format_amz_date(UNIX_EPOCH), "19700101T000000Z");
+        assert_eq!(
+            format_amz_date(UNIX_EPOCH + Duration::from_hours(264_396)),
+            "20000229T120000Z"
+        );
+    }
+
+    #[test]
+    fn get_vanilla() {
+        let req = signed("https://example.amazonaws.com/", &credentials());
+        assert_eq!(req.headers()[X_AMZ_DATE], "20150830T123600Z");
+        assert_eq!(req.headers()[HOST], "example.amazonaws.com");
+        assert_eq!(
+            req.headers()[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_order() {
+        let req = signed(
+            "https://example.amazonaws.com/?Param2=value2&Param1=value1",
+            &credentials(),
+        );
+        assert_eq!(
+            req.headers()[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn content_sha256() {
+        let mut req = Request::builder()
+            .method("PUT")
+            .uri("https://bucket.s3.amazonaws.com/key")
+            .body(BytesMut::from("hello"))
+            .unwrap();
+        SigV4Auth::s3()
+            .sign(&mut req, &credentials(), time())
+            .unwrap();
+        assert_eq!(
+            req.headers()[X_AMZ_CONTENT_SHA256],
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert!(req.headers()[AUTHORIZATION]
+            .to_str()
+            .unwrap()
+            .contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date,"));
+
+        // The hash of an empty body
+        let mut req = signed("https://example.amazonaws.com/", &credentials());
+        assert!(!req.headers().contains_key(X_AMZ_CONTENT_SHA256));
+        SigV4Auth::new()
+            .with_content_sha256()
+            .sign(&mut req, &credentials(), time())
+            .unwrap();
+        assert_eq!(
+            req.headers()[X_AMZ_CONTENT_SHA256],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn session_token() {
+        let credentials = credentials().session_token("token".into());
+        let req = signed("https://example.amazonaws.com/", &credentials);
+        assert_eq!(req.headers()[X_AMZ_SECURITY_TOKEN], "token");
+        // Both credential headers are redactable
+        let auth_headers = SigV4Auth::new().auth_headers();
+        assert_eq!(auth_headers, [AUTHORIZATION, X_AMZ_SECURITY_TOKEN]);
+        assert!(auth_headers
+            .iter()
+            .all(|name| req.headers().contains_key(name)));
+        assert!(req.headers()[AUTHORIZATION]
+            .to_str()
+            .unwrap()
+            .contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+
+        let debug = format!("{credentials:?}");
+        assert!(debug.contains("AKIDEXAMPLE"));
+        assert!(!debug.contains("EXAMPLEKEY"));
+        assert!(!debug.contains("token"));
+    }
+
+    #[test]
+    fn missing_host() {
+        let mut req = Request::new(BytesMut::new());
+        assert!(matches!(
+            SigV4Auth::new()
+                .sign(&mut req, &credentials(), time())
+                .unwrap_err(),
+            IntoHttpError::MissingHost
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+//! Request and response body formats.
+//!
+//! A [`BodyFormat`] serializes request bodies and deserializes response bodies,
+//! so endpoints can use formats other than JSON, such as `MessagePack` or CBOR.
+//! [`FormFormat`] is always provided,
+//! and [`JsonFormat`] is provided when the `json` feature is enabled.
+
+use bytes::{Bytes, BytesMut};
+use http::{
+    header::{ACCEPT, CONTENT_TYPE},
+    request, HeaderValue, Request,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::{DeserializeError, IntoHttpError},
+    response::check_content_type,
+    IncomingResponse, OutgoingRequest,
+};
+
+mod multipart;
+
+pub use self::multipart::Multipart;
+
+/// A serialization format of request and response bodies.
+pub trait BodyFormat {
+    /// The media type of bodies in this format, e.g. `application/json`.
+    const CONTENT_TYPE: &'static str;
+
+    /// Serialize `value` into a body.
+    fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Bytes, IntoHttpError>;
+
+    /// Deserialize a body into a `T`.
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, DeserializeError>;
+}
+
+/// The `application/x-www-form-urlencoded` body format.
+///
+/// This is the format of HTML forms and of OAuth token requests.
+/// Errors are reported as [`IntoHttpError::Query`] and [`DeserializeError::Uri`],
+/// as with query strings.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct FormFormat;
+
+impl BodyFormat for FormFormat {
+    const CONTENT_TYPE: &'static str = "application/x-www-form-urlencoded";
+
+    fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Bytes, IntoHttpError> {
+        Ok(Bytes::from(serde_urlencoded::to_string(value)?))
+    }
+
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, DeserializeError> {
+        Ok(serde_urlencoded::from_bytes(body)?)
+    }
+}
+
+/// The JSON body format.
+#[cfg(feature = "json")]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct JsonFormat;
+
+#[cfg(feature = "json")]
+impl BodyFormat for JsonFormat {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn serialize<T: Serialize + ?Sized>(value: &T) -> Result<Bytes, IntoHttpError> {
+        Ok(Bytes::from(serde_json::to_vec(value)?))
+    }
+
+    /// Errors include the start of the body, see [`DeserializeError::JsonWithBody`].
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, DeserializeError> {
+        serde_json::from_slice(body).map_err(|err| DeserializeError::json_with_body(err, body))
+    }
+}
+
+/// Finish building a request with `value` serialized in format `F` as its body.
+///
+/// The `Content-Type` header is set to [`BodyFormat::CONTENT_TYPE`],
+/// replacing any content type set on the builder.
+pub fn build_request<F: BodyFormat, T: Serialize + ?Sized>(
+    builder: request::Builder,
+    value: &T,
+) -> Result<Request<BytesMut>, IntoHttpError> {
+    let mut req = builder.body(BytesMut::from(F::serialize(value)?))?;
+    req.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(F::CONTENT_TYPE));
+    Ok(req)
+}
+
+/// Set the `Accept` header of a request to the media type its response must have.
+///
+/// The media type is the [`IncomingResponse::EXPECTED_CONTENT_TYPE`] of the response of `R`,
+/// which [`IncomingResponse::try_from_http_response_checked`] checks the `Content-Type` of
+/// responses against.
+/// Nothing is set if it is `None`, or if the request already has an `Accept` header,
+/// e.g. from the endpoint [`Metadata::headers`](crate::metadata::Metadata::headers).
+pub fn set_accept<R: OutgoingRequest>(req: &mut Request<BytesMut>) {
+    if let Some(media_type) = <R::IncomingResponse as IncomingResponse>::EXPECTED_CONTENT_TYPE {
+        req.headers_mut()
+            .entry(ACCEPT)
+            .or_insert_with(|| HeaderValue::from_static(media_type));
+    }
+}
+
+/// Deserialize the body of a response in format `F`.
+///
+/// The `Content-Type` of the response is checked against [`BodyFormat::CONTENT_TYPE`]
+/// with [`check_content_type`] first.
+pub fn from_response<F: BodyFormat, T: DeserializeOwned>(
+    res: &http::Response<impl AsRef<[u8]>>,
+) -> Result<T, DeserializeError> {
+    check_content_type(res, F::CONTENT_TYPE)?;
+    F::deserialize(res.body().as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        id: u32,
+    }
+
+    #[test]
+    fn form_format() {
+        let req = build_request::<FormFormat, _>(
+            http::Request::builder().header(CONTENT_TYPE, "application/json"),
+            &Item { id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            req.headers()[CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(req.headers().get_all(CONTENT_TYPE).iter().count(), 1);
+        assert_eq!(req.body().as_ref(), b"id=1");
+
+        let item: Item = FormFormat::deserialize(req.body()).unwrap();
+        assert_eq!(item, Item { id: 1 });
+
+        let res = http::Response::builder()
+            .header(
+                CONTENT_TYPE,
+                "application/x-www-form-urlencoded; charset=utf-8",
+            )
+            .body(b"id=2")
+            .unwrap();
+        assert_eq!(
+            from_response::<FormFormat, Item>(&res).unwrap(),
+            Item { id: 2 }
+        );
+
+        let res = http::Response::builder()
+            .header(CONTENT_TYPE, "text/html")
+            .body(b"<html>")
+            .unwrap();
+        assert!(matches!(
+            from_response::<FormFormat, Item>(&res).unwrap_err(),
+            DeserializeError::UnexpectedContentType {
+                expected: "application/x-www-form-urlencoded",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn form_round_trip() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct TokenRequest {
+            grant_type: String,
+            scope: Option<String>,
+            redirect_uri: String,
+        }
+
+        let form = TokenRequest {
+            grant_type: "authorization_code".into(),
+            scope: Some("read write".into()),
+            redirect_uri: "https://example.com/cb?a=1&b=2".into(),
+        };
+        let req = build_request::<FormFormat, _>(http::Request::builder(), &form).unwrap();
+        assert_eq!(
+            req.body().as_ref(),
+            b"grant_type=authorization_code&scope=read+write\
+              &redirect_uri=https%3A%2F%2Fexample.com%2Fcb%3Fa%3D1%26b%3D2"
+        );
+
+        // The server side decodes the same form
+        let decoded: TokenRequest = serde_urlencoded::from_bytes(req.body()).unwrap();
+        assert_eq!(decoded, form);
+
+        assert!(matches!(
+            FormFormat::serialize(&1).unwrap_err(),
+            IntoHttpError::Query(_)
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_format() {
+        let req =
+            build_request::<JsonFormat, _>(http::Request::builder(), &Item { id: 1 }).unwrap();
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(req.body().as_ref(), br#"{"id":1}"#);
+
+        let item: Item = JsonFormat::deserialize(req.body()).unwrap();
+        assert_eq!(item, Item { id: 1 });
+        assert!(matches!(
+            JsonFormat::deserialize::<Item>(b"<html>").unwrap_err(),
+            DeserializeError::JsonWithBody { body, .. } if body == "<html>"
+        ));
+    }
+}
@@ -0,0 +1,256 @@
+//! `multipart/form-data` bodies.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http::{header::CONTENT_TYPE, request, HeaderValue, Request};
+
+use crate::error::IntoHttpError;
+
+/// A builder of `multipart/form-data` bodies, as used by file upload endpoints.
+///
+/// See [RFC 7578](https://datatracker.ietf.org/doc/html/rfc7578).
+#[derive(Debug, Clone, Default)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+struct Part {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: Bytes,
+}
+
+impl Multipart {
+    /// Creates an empty multipart body.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { parts: Vec::new() }
+    }
+
+    /// Adds a text field.
+    pub fn text(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: None,
+            content_type: None,
+            body: Bytes::from(value.into()),
+        });
+        self
+    }
+
+    /// Adds a file with the given `filename` and `content_type`.
+    pub fn file(
+        &mut self,
+        name: impl Into<String>,
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        body: impl Into<Bytes>,
+    ) -> &mut Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: Some(filename.into()),
+            content_type: Some(content_type.into()),
+            body: body.into(),
+        });
+        self
+    }
+
+    /// Returns the number of parts.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Returns `true` if there are no parts.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Write the body, returning it and its `Content-Type` header value.
+    ///
+    /// The boundary is chosen so it doesn't occur in any of the parts.
+    pub fn into_body(self) -> Result<(BytesMut, HeaderValue), IntoHttpError> {
+        let boundary = boundary(&self.parts);
+
+        let mut body = BytesMut::new();
+        for part in &self.parts {
+            body.put_slice(b"--");
+            body.put_slice(boundary.as_bytes());
+            body.put_slice(b"\r\nContent-Disposition: form-data; name=\"");
+            body.put_slice(escape(&part.name).as_bytes());
+            body.put_u8(b'"');
+            if let Some(filename) = &part.filename {
+                body.put_slice(b"; filename=\"");
+                body.put_slice(escape(filename).as_bytes());
+                body.put_u8(b'"');
+            }
+            if let Some(content_type) = &part.content_type {
+                body.put_slice(b"\r\nContent-Type: ");
+                body.put_slice(HeaderValue::from_str(content_type)?.as_bytes());
+            }
+            body.put_slice(b"\r\n\r\n");
+            body.put_slice(&part.body);
+            body.put_slice(b"\r\n");
+        }
+        body.put_slice(b"--");
+        body.put_slice(boundary.as_bytes());
+        body.put_slice(b"--\r\n");
+
+        let content_type =
+            HeaderValue::from_str(&format!("multipart/form-data; boundary={boundary}"))?;
+        Ok((body, content_type))
+    }
+
+    /// Finish building a request with this multipart body.
+    ///
+    /// The `Content-Type` header is set to `multipart/form-data` with the generated boundary,
+    /// replacing any content type set on the builder.
+    pub fn build_request(
+        self,
+        builder: request::Builder,
+    ) -> Result<Request<BytesMut>, IntoHttpError> {
+        let (body, content_type) = self.into_body()?;
+        let mut req = builder.body(body)?;
+        req.headers_mut().insert(CONTENT_TYPE, content_type);
+        Ok(req)
+    }
+}
+
+/// Escape a field name or filename for a quoted `Content-Disposition` parameter.
+fn escape(s: &str) -> String {
+    s.replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Generate a boundary that doesn't occur in any of the `parts`.
+fn boundary(parts: &[Part]) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    boundary_from(parts, nanos)
+}
+
+fn boundary_from(parts: &[Part], nanos: u128) -> String {
+    let mut i = 0u32;
+    loop {
+        let boundary = format!("form_{nanos:x}_{i}");
+        if !parts.iter().any(|p| {
+            p.body
+                .windows(boundary.len())
+                .any(|w| w == boundary.as_bytes())
+        }) {
+            return boundary;
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_with_boundary(multipart: Multipart) -> (String, String) {
+        let (body, content_type) = multipart.into_body().unwrap();
+        let boundary = content_type
+            .to_str()
+            .unwrap()
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap()
+            .to_owned();
+        (String::from_utf8(body.to_vec()).unwrap(), boundary)
+    }
+
+    #[test]
+    fn text_and_file() {
+        let mut multipart = Multipart::new();
+        multipart
+            .text("title", "Holiday")
+            .file("photo", "a \"b\".txt", "text/plain", "contents");
+        assert_eq!(multipart.len(), 2);
+
+        let (body, b) = body_with_boundary(multipart);
+        assert_eq!(
+            body,
+            format!(
+                "--{b}\r\n\
+                 Content-Disposition: form-data; name=\"title\"\r\n\
+                 \r\n\
+                 Holiday\r\n\
+                 --{b}\r\n\
+                 Content-Disposition: form-data; name=\"photo\"; filename=\"a %22b%22.txt\"\r\n\
+                 Content-Type: text/plain\r\n\
+                 \r\n\
+                 contents\r\n\
+                 --{b}--\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let multipart = Multipart::new();
+        assert!(multipart.is_empty());
+        let (body, b) = body_with_boundary(multipart);
+        assert_eq!(body, format!("--{b}--\r\n"));
+    }
+
+    #[test]
+    fn boundary_not_in_content() {
+        let part = |body: &'static str| Part {
+            name: "file".into(),
+            filename: None,
+            content_type: None,
+            body: Bytes::from_static(body.as_bytes()),
+        };
+        assert_eq!(boundary_from(&[part("--form_ff_1")], 0xff), "form_ff_0");
+        assert_eq!(
+            boundary_from(&[part("--form_ff_0"), part("form_ff_1--")], 0xff),
+            "form_ff_2"
+        );
+
+        let mut multipart = Multipart::new();
+        multipart.file(
+            "data",
+            "data.bin",
+            "application/octet-stream",
+            Bytes::from_static(b"\x00\xff--form_\r\n"),
+        );
+        let (body, content_type) = multipart.into_body().unwrap();
+        let boundary = content_type
+            .to_str()
+            .unwrap()
+            .rsplit('=')
+            .next()
+            .unwrap()
+            .to_owned();
+        let delimiter = format!("--{boundary}");
+        assert_eq!(
+            body.windows(delimiter.len())
+                .filter(|w| *w == delimiter.as_bytes())
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn build_request() {
+        let mut multipart = Multipart::new();
+        multipart.text("a", "b");
+        let req = multipart
+            .build_request(http::Request::builder().header(CONTENT_TYPE, "application/json"))
+            .unwrap();
+        assert!(req.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("multipart/form-data; boundary=form_"));
+        assert_eq!(req.headers().get_all(CONTENT_TYPE).iter().count(), 1);
+    }
+}
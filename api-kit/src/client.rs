@@ -0,0 +1,27 @@
+//! Client-side helpers for sending endpoint requests.
+
+mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod capabilities;
+mod headers;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+mod idempotency;
+mod locale;
+mod pagination;
+mod precondition;
+#[cfg(feature = "reqwest")]
+pub mod reqwest;
+
+#[cfg(feature = "stream")]
+pub use self::pagination::{paginate_stream, PageStream};
+pub use self::{
+    batch::{Batch, BatchHandle, BatchResponse},
+    capabilities::Capabilities,
+    headers::{RequestHeaders, WithHeaders},
+    idempotency::{IdempotencyKey, WithIdempotencyKey, IDEMPOTENCY_KEY},
+    locale::{Locale, WithLocale},
+    pagination::{link_query, next_cursor, next_link, paginate, Pages, Paginated},
+    precondition::{Precondition, WithPrecondition},
+};
@@ -0,0 +1,556 @@
+//! Batching several requests into a single `multipart/mixed` request.
+//!
+//! Each sub-request is serialized as an `application/http` part,
+//! as used by Google and `OData` batch APIs.
+//! The batched response is a `multipart/mixed` body of `application/http` responses,
+//! in the same order as the sub-requests.
+
+use std::{
+    marker::PhantomData,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use http::{
+    header::{CONTENT_TYPE, HOST},
+    HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri,
+};
+
+use crate::{
+    auth::Authenticator,
+    error::{DeserializeError, FromHttpResponseError, IntoHttpError},
+    metadata::media_type_matches,
+    Endpoint, IncomingResponse, OutgoingRequest,
+};
+
+/// A builder of batch requests.
+#[derive(Debug, Default)]
+pub struct Batch {
+    requests: Vec<http::Request<BytesMut>>,
+}
+
+/// A typed handle to a sub-request of a [`Batch`].
+///
+/// The handle is used to retrieve the sub-request's response from a [`BatchResponse`].
+#[derive(Debug)]
+pub struct BatchHandle<R> {
+    index: usize,
+    _response: PhantomData<fn() -> R>,
+}
+
+impl<R> BatchHandle<R> {
+    /// Returns the position of the sub-request in the batch.
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Batch {
+    /// Creates an empty batch.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            requests: Vec::new(),
+        }
+    }
+
+    /// Returns the number of sub-requests in the batch.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if the batch contains no sub-requests.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Build an outgoing request and add it to the batch.
+    ///
+    /// Returns a handle to retrieve its response from the [`BatchResponse`].
+    pub fn push<R, A>(
+        &mut self,
+        req: R,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<BatchHandle<R::IncomingResponse>, IntoHttpError>
+    where
+        R: OutgoingRequest,
+        A: Authenticator,
+    {
+        let req = req.try_into_http_request(base_url, auth, auth_data)?;
+        self.requests.push(req);
+        Ok(BatchHandle {
+            index: self.requests.len() - 1,
+            _response: PhantomData,
+        })
+    }
+
+    /// Convert the batch into a single `multipart/mixed` `POST` request to `uri`.
+    ///
+    /// The batch request itself is not authenticated;
+    /// use [`Authenticator::authenticate`] on it if the batch endpoint requires it.
+    pub fn into_http_request(self, uri: Uri) -> Result<http::Request<BytesMut>, IntoHttpError> {
+        let parts = self
+            .requests
+            .iter()
+            .map(serialize_request)
+            .collect::<Vec<_>>();
+        let boundary = boundary(&parts);
+
+        let mut body = BytesMut::new();
+        for (i, part) in parts.iter().enumerate() {
+            body.put_slice(b"--");
+            body.put_slice(boundary.as_bytes());
+            body.put_slice(b"\r\nContent-Type: application/http\r\nContent-ID: <");
+            body.put_slice(itoa::Buffer::new().format(i + 1).as_bytes());
+            body.put_slice(b">\r\n\r\n");
+            body.put_slice(part);
+            body.put_slice(b"\r\n");
+        }
+        body.put_slice(b"--");
+        body.put_slice(boundary.as_bytes());
+        body.put_slice(b"--\r\n");
+
+        let content_type = HeaderValue::from_str(&format!("multipart/mixed; boundary={boundary}"))?;
+        Ok(http::Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(CONTENT_TYPE, content_type)
+            .body(body)?)
+    }
+}
+
+/// Serialize a request in HTTP/1.1 message format.
+fn serialize_request(req: &http::Request<BytesMut>) -> BytesMut {
+    let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+
+    let mut buf = BytesMut::new();
+    buf.put_slice(req.method().as_str().as_bytes());
+    buf.put_u8(b' ');
+    buf.put_slice(path.as_bytes());
+    buf.put_slice(b" HTTP/1.1\r\n");
+    if let Some(authority) = req.uri().authority() {
+        if !req.headers().contains_key(HOST) {
+            buf.put_slice(b"host: ");
+            buf.put_slice(authority.as_str().as_bytes());
+            buf.put_slice(b"\r\n");
+        }
+    }
+    for (name, value) in req.headers() {
+        buf.put_slice(name.as_str().as_bytes());
+        buf.put_slice(b": ");
+        buf.put_slice(value.as_bytes());
+        buf.put_slice(b"\r\n");
+    }
+    buf.put_slice(b"\r\n");
+    buf.put_slice(req.body());
+    buf
+}
+
+/// Generate a boundary that doesn't occur in any of the `parts`.
+fn boundary(parts: &[BytesMut]) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let mut i = 0u32;
+    loop {
+        let boundary = format!("batch_{nanos:x}_{i}");
+        if !parts.iter().any(|p| contains(p, boundary.as_bytes())) {
+            return boundary;
+        }
+        i += 1;
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// The demultiplexed responses of a batch request.
+#[derive(Debug)]
+pub struct BatchResponse {
+    responses: Vec<Option<http::Response<Bytes>>>,
+}
+
+impl BatchResponse {
+    /// Split a `multipart/mixed` batch response into its sub-responses.
+    ///
+    /// Sub-responses must be in the same order as the sub-requests of the [`Batch`].
+    pub fn from_http_response(res: http::Response<Bytes>) -> Result<Self, DeserializeError> {
+        let boundary = res
+            .headers()
+            .get(CONTENT_TYPE)
+            .filter(|v| media_type_matches(v, "multipart/*"))
+            .and_then(|v| v.to_str().ok())
+            .and_then(multipart_boundary)
+            .ok_or(DeserializeError::InvalidMultipart)?
+            .to_owned();
+
+        let body = res.into_body();
+        let responses = split_multipart(&body, &boundary)?
+            .into_iter()
+            .map(|part| {
+                let (_, message) = split_head(&part).ok_or(DeserializeError::InvalidMultipart)?;
+                parse_response(&part.slice_ref(message)).map(Some)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { responses })
+    }
+
+    /// Returns the number of sub-responses in the batch.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Returns `true` if the batch contains no sub-responses.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Take the raw sub-response at `index`, if it hasn't been taken yet.
+    pub fn take_raw(&mut self, index: usize) -> Option<http::Response<Bytes>> {
+        self.responses.get_mut(index).and_then(Option::take)
+    }
+
+    /// Take and convert the sub-response of a sub-request.
+    ///
+    /// The sub-response is converted with [`IncomingResponse::try_from_http_response_by_status`].
+    /// Returns [`DeserializeError::MissingBatchPart`] if the batch response doesn't contain
+    /// the sub-response, or it was already taken.
+    // Handles are consumed, as each sub-response can only be taken once.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn take<R: IncomingResponse>(
+        &mut self,
+        handle: BatchHandle<R>,
+    ) -> Result<R, FromHttpResponseError<<R::OutgoingRequest as Endpoint>::Error>> {
+        let res = self
+            .take_raw(handle.index)
+            .ok_or(DeserializeError::MissingBatchPart(handle.index))?;
+        R::try_from_http_response_by_status(res)
+    }
+}
+
+/// Extract the `boundary` parameter of a content type.
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Split a multipart body into the bodies of its parts, including part headers.
+fn split_multipart(body: &Bytes, boundary: &str) -> Result<Vec<Bytes>, DeserializeError> {
+    let delimiter = format!("--{boundary}");
+    let delimiter = delimiter.as_bytes();
+
+    let mut parts = Vec::new();
+    let mut rest = &body[find(body, delimiter).ok_or(DeserializeError::InvalidMultipart)?..];
+    loop {
+        rest = &rest[delimiter.len()..];
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        let start = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or(DeserializeError::InvalidMultipart)?
+            + 1;
+        rest = &rest[start..];
+        let end = find(rest, delimiter).ok_or(DeserializeError::InvalidMultipart)?;
+        let part = strip_line_break(&rest[..end]);
+        parts.push(body.slice_ref(part));
+        rest = &rest[end..];
+    }
+}
+
+fn strip_line_break(b: &[u8]) -> &[u8] {
+    b.strip_suffix(b"\r\n")
+        .or_else(|| b.strip_suffix(b"\n"))
+        .unwrap_or(b)
+}
+
+/// Split a message at the first empty line into its head and body.
+fn split_head(message: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut start = 0;
+    for line in message.split_inclusive(|&b| b == b'\n') {
+        start += line.len();
+        if line == b"\r\n" || line == b"\n" {
+            return Some((&message[..start - line.len()], &message[start..]));
+        }
+    }
+    None
+}
+
+/// Parse an HTTP/1.1 response message.
+fn parse_response(message: &Bytes) -> Result<http::Response<Bytes>, DeserializeError> {
+    let (head, body) = split_head(message).unwrap_or((message, &[]));
+    let mut lines = head
+        .split(|&b| b == b'\n')
+        .map(|l| l.strip_suffix(b"\r").unwrap_or(l))
+        .filter(|l| !l.is_empty());
+
+    let status = lines
+        .next()
+        .and_then(|l| std::str::from_utf8(l).ok())
+        .and_then(|l| l.split(' ').nth(1))
+        .and_then(|s| StatusCode::from_bytes(s.as_bytes()).ok())
+        .ok_or(DeserializeError::InvalidMultipart)?;
+
+    let mut headers = HeaderMap::new();
+    for line in lines {
+        let colon = line
+            .iter()
+            .position(|&b| b == b':')
+            .ok_or(DeserializeError::InvalidMultipart)?;
+        let name = HeaderName::from_bytes(&line[..colon])
+            .map_err(|_| DeserializeError::InvalidMultipart)?;
+        let value = HeaderValue::from_bytes(line[colon + 1..].trim_ascii())
+            .map_err(|_| DeserializeError::InvalidMultipart)?;
+        headers.append(name, value);
+    }
+
+    let mut res = http::Response::new(message.slice_ref(body));
+    *res.status_mut() = status;
+    *res.headers_mut() = headers;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::AUTHORIZATION;
+
+    use super::*;
+    use crate::{auth::bearer::BearerAuth, metadata::Metadata, EndpointError};
+
+    #[derive(Debug)]
+    struct TestError(StatusCode);
+
+    impl EndpointError for TestError {
+        fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+        where
+            B: Default + BufMut,
+        {
+            Ok(http::Response::builder()
+                .status(self.0)
+                .body(B::default())?)
+        }
+
+        fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+            Self(response.status())
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct GetItem(u32);
+
+    #[derive(Debug)]
+    struct Item(Bytes);
+
+    impl Endpoint for GetItem {
+        type Error = TestError;
+
+        const METADATA: Metadata<'static> = Metadata {
+            method: Method::GET,
+            auth: &[&BearerAuth::new()],
+            path: "/items/{id}",
+            ..Metadata::DEFAULT
+        };
+    }
+
+    impl OutgoingRequest for GetItem {
+        type IncomingResponse = Item;
+
+        fn try_into_http_request<A>(
+            self,
+            base_url: &str,
+            auth: A,
+            auth_data: &A::AuthData,
+        ) -> Result<http::Request<BytesMut>, IntoHttpError>
+        where
+            A: Authenticator,
+        {
+            #[derive(serde::Serialize)]
+            struct Path {
+                id: u32,
+            }
+
+            let url = Self::METADATA.make_url(base_url, &Path { id: self.0 }, &())?;
+            let mut req = http::Request::builder()
+                .method(Self::METADATA.method)
+                .uri(url)
+                .body(BytesMut::new())?;
+            auth.authenticate(&mut req, auth_data)?;
+            Ok(req)
+        }
+    }
+
+    impl IncomingResponse for Item {
+        type OutgoingRequest = GetItem;
+
+        fn try_from_http_response(
+            res: http::Response<Bytes>,
+        ) -> Result<Self, FromHttpResponseError<TestError>> {
+            Ok(Self(res.into_body()))
+        }
+    }
+
+    #[test]
+    fn batch_request() {
+        let mut batch = Batch::new();
+        assert!(batch.is_empty());
+        let token = "token".to_owned();
+        batch
+            .push(GetItem(1), "https://example.com", BearerAuth::new(), &token)
+            .unwrap();
+        batch
+            .push(GetItem(2), "https://example.com", (), &())
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+
+        let req = batch
+            .into_http_request(Uri::from_static("https://example.com/batch"))
+            .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), "https://example.com/batch");
+
+        let content_type = req.headers()[CONTENT_TYPE].to_str().unwrap();
+        let boundary = multipart_boundary(content_type).unwrap();
+        assert!(content_type.starts_with("multipart/mixed; boundary=batch_"));
+
+        let expected = format!(
+            "--{boundary}\r\n\
+             Content-Type: application/http\r\n\
+             Content-ID: <1>\r\n\
+             \r\n\
+             GET /items/1 HTTP/1.1\r\n\
+             host: example.com\r\n\
+             {AUTHORIZATION}: Bearer token\r\n\
+             \r\n\
+             \r\n\
+             --{boundary}\r\n\
+             Content-Type: application/http\r\n\
+             Content-ID: <2>\r\n\
+             \r\n\
+             GET /items/2 HTTP/1.1\r\n\
+             host: example.com\r\n\
+             \r\n\
+             \r\n\
+             --{boundary}--\r\n"
+        );
+        assert_eq!(std::str::from_utf8(req.body()).unwrap(), expected);
+    }
+
+    #[test]
+    fn boundary_not_in_parts() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        // Candidate boundaries found in a part are skipped
+        let part = BytesMut::from(format!("batch_{nanos:x}_0").as_bytes());
+        let boundary = boundary(std::slice::from_ref(&part));
+        assert!(!contains(&part, boundary.as_bytes()));
+    }
+
+    fn batch_response(body: &'static str) -> http::Response<Bytes> {
+        http::Response::builder()
+            .header(CONTENT_TYPE, "multipart/mixed; boundary=\"batch_abc\"")
+            .body(Bytes::from_static(body.as_bytes()))
+            .unwrap()
+    }
+
+    #[test]
+    fn batch_response_demultiplex() {
+        let res = batch_response(
+            "preamble\r\n\
+             --batch_abc\r\n\
+             Content-Type: application/http\r\n\
+             Content-ID: <response-1>\r\n\
+             \r\n\
+             HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain\r\n\
+             \r\n\
+             first\r\n\
+             --batch_abc\n\
+             Content-Type: application/http\n\
+             \n\
+             HTTP/1.1 404 Not Found\n\
+             \n\
+             \n\
+             --batch_abc--\r\n",
+        );
+        let mut batch = BatchResponse::from_http_response(res).unwrap();
+        assert_eq!(batch.len(), 2);
+
+        let first = BatchHandle::<Item> {
+            index: 0,
+            _response: PhantomData,
+        };
+        let second = BatchHandle::<Item> {
+            index: 1,
+            _response: PhantomData,
+        };
+        let third = BatchHandle::<Item> {
+            index: 2,
+            _response: PhantomData,
+        };
+
+        let raw = batch.responses[0].as_ref().unwrap();
+        assert_eq!(raw.headers()[CONTENT_TYPE], "text/plain");
+
+        assert_eq!(batch.take(first).unwrap().0, "first");
+        assert!(matches!(
+            batch.take(second).unwrap_err(),
+            FromHttpResponseError::EndpointError(TestError(StatusCode::NOT_FOUND))
+        ));
+        assert!(matches!(
+            batch.take(third).unwrap_err(),
+            FromHttpResponseError::Deserialize(DeserializeError::MissingBatchPart(2))
+        ));
+        assert!(batch.take_raw(0).is_none());
+    }
+
+    #[test]
+    fn batch_response_invalid() {
+        let res = http::Response::new(Bytes::from_static(b"--batch_abc--"));
+        assert!(matches!(
+            BatchResponse::from_http_response(res).unwrap_err(),
+            DeserializeError::InvalidMultipart
+        ));
+
+        for body in [
+            "no delimiter",
+            "--batch_abc\r\nContent-Type: application/http\r\n\r\nHTTP/1.1 200 OK\r\n",
+            "--batch_abc\r\nno head\r\n--batch_abc--",
+            "--batch_abc\r\n\r\nHTTP/1.1 abc OK\r\n\r\n--batch_abc--",
+            "--batch_abc\r\n\r\nHTTP/1.1 200 OK\r\nbad header\r\n\r\n--batch_abc--",
+        ] {
+            assert!(matches!(
+                BatchResponse::from_http_response(batch_response(body)).unwrap_err(),
+                DeserializeError::InvalidMultipart
+            ));
+        }
+
+        let batch = BatchResponse::from_http_response(batch_response("--batch_abc--")).unwrap();
+        assert!(batch.is_empty());
+    }
+}
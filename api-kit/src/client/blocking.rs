@@ -0,0 +1,120 @@
+//! A minimal blocking client using [`ureq`].
+//!
+//! This is meant for scripts, CLI tools and tests that don't need an async runtime:
+//!
+//! ```ignore
+//! let agent = api_kit::client::blocking::agent();
+//! let user = api_kit::client::blocking::send(
+//!     &agent,
+//!     GetUser { id: 1 },
+//!     "https://api.example.com",
+//!     BearerAuth::new(),
+//!     &token,
+//! )?;
+//! ```
+//!
+//! `ureq` is used without default features, so TLS must be enabled with one of its
+//! features, e.g. `rustls`, to send requests to `https` URLs.
+
+use bytes::Bytes;
+
+use crate::{auth::Authenticator, error::SendError, Endpoint, IncomingResponse, OutgoingRequest};
+
+/// Creates a [`ureq::Agent`] for [`send`].
+///
+/// The agent doesn't treat error statuses as [`ureq::Error`]s,
+/// so error responses can be converted into endpoint errors.
+#[must_use]
+pub fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+/// Send `req` with `agent` and convert its response.
+///
+/// The request is built with [`OutgoingRequest::try_into_http_request`],
+/// and the response is converted with
+/// [`IncomingResponse::try_from_http_response_by_status`],
+/// so error responses are converted into the endpoint [`Error`](Endpoint::Error).
+/// The endpoint [`timeout`](crate::metadata::Metadata::timeout), if any,
+/// is set as the global timeout of the request.
+///
+/// Agents that treat error statuses as errors, such as [`ureq::agent`],
+/// return [`ureq::Error::StatusCode`] for error responses instead; see [`agent`].
+pub fn send<R, A>(
+    agent: &ureq::Agent,
+    req: R,
+    base_url: &str,
+    auth: A,
+    auth_data: &A::AuthData,
+) -> Result<R::IncomingResponse, SendError<<R as Endpoint>::Error>>
+where
+    R: OutgoingRequest,
+    A: Authenticator,
+{
+    let (parts, body) = req
+        .try_into_http_request(base_url, auth, auth_data)?
+        .into_parts();
+    let mut req = http::Request::from_parts(parts, &body[..]);
+    if let Some(timeout) = R::METADATA.timeout() {
+        req = agent
+            .configure_request(req)
+            .timeout_global(Some(timeout))
+            .build();
+    }
+    let res = agent.run(req)?;
+
+    let (parts, mut body) = res.into_parts();
+    let body = Bytes::from(body.with_config().read_to_vec()?);
+    let res = http::Response::from_parts(parts, body);
+
+    Ok(R::IncomingResponse::try_from_http_response_by_status(res)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::bearer::BearerAuth,
+        error::FromHttpResponseError,
+        tests::{
+            server::{serve_once, GetItem, Item},
+            TestError,
+        },
+    };
+
+    fn send(base_url: &str) -> Result<Item, SendError<TestError>> {
+        super::send(
+            &agent(),
+            GetItem,
+            base_url,
+            BearerAuth::new(),
+            &"token".to_owned(),
+        )
+    }
+
+    #[test]
+    fn send_round_trip() {
+        let (base_url, server) = serve_once("HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}");
+        assert_eq!(send(&base_url).unwrap(), Item(Bytes::from_static(b"{}")));
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /items/1 HTTP/1.1\r\n"));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("authorization: bearer token\r\n"));
+
+        let (base_url, _) = serve_once("HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n");
+        assert!(matches!(
+            send(&base_url).unwrap_err(),
+            SendError::Response(FromHttpResponseError::EndpointError(TestError))
+        ));
+
+        assert!(matches!(
+            send("not a url").unwrap_err(),
+            SendError::IntoHttp(_)
+        ));
+    }
+}
@@ -0,0 +1,88 @@
+//! Checking endpoint capability requirements before sending requests.
+
+use std::collections::BTreeSet;
+
+use crate::{error::IntoHttpError, metadata::Metadata, Endpoint};
+
+/// The set of capabilities available to a client, e.g. enabled account features.
+///
+/// Endpoints declare the capability they require in [`Metadata::requires_capability`].
+/// Checking requests against the available capabilities before sending them
+/// fails fast with [`IntoHttpError::CapabilityNotAvailable`],
+/// rather than with an error response from the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    capabilities: BTreeSet<String>,
+}
+
+impl Capabilities {
+    /// Creates an empty capability set.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            capabilities: BTreeSet::new(),
+        }
+    }
+
+    /// Adds a capability to the set.
+    pub fn insert(&mut self, capability: impl Into<String>) -> &mut Self {
+        self.capabilities.insert(capability.into());
+        self
+    }
+
+    /// Returns `true` if `capability` is available.
+    #[must_use]
+    pub fn contains(&self, capability: &str) -> bool {
+        self.capabilities.contains(capability)
+    }
+
+    /// Check that the capability required by `metadata`, if any, is available.
+    pub fn check_metadata(&self, metadata: &Metadata<'_>) -> Result<(), IntoHttpError> {
+        match metadata.requires_capability {
+            Some(capability) if !self.contains(capability) => {
+                Err(IntoHttpError::CapabilityNotAvailable(capability.to_owned()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the capability required by the endpoint `E`, if any, is available.
+    pub fn check<E: Endpoint>(&self) -> Result<(), IntoHttpError> {
+        self.check_metadata(&E::METADATA)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for Capabilities {
+    fn from_iter<T: IntoIterator<Item = S>>(iter: T) -> Self {
+        Self {
+            capabilities: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_metadata() {
+        let billing = Metadata {
+            requires_capability: Some("billing"),
+            ..Metadata::default()
+        };
+        let mut capabilities = Capabilities::new();
+        capabilities.check_metadata(&Metadata::default()).unwrap();
+        assert!(matches!(
+            capabilities.check_metadata(&billing).unwrap_err(),
+            IntoHttpError::CapabilityNotAvailable(capability) if capability == "billing"
+        ));
+
+        capabilities.insert("billing");
+        capabilities.check_metadata(&billing).unwrap();
+        assert_eq!(
+            capabilities,
+            Capabilities::from_iter(["billing", "billing"])
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use std::fmt;
+
+use bytes::BytesMut;
+use http::{HeaderMap, HeaderName, Request};
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::IntoHttpError,
+};
+
+/// Headers that [`WithHeaders`] sets on requests.
+///
+/// Each implementation decides whether it replaces headers the endpoint already set.
+pub trait RequestHeaders {
+    /// Set the headers on `headers`, before the request is authenticated.
+    fn set_headers(&self, headers: &mut HeaderMap);
+}
+
+/// Replaces all values of the headers with the same names.
+impl RequestHeaders for HeaderMap {
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        for name in self.keys() {
+            headers.remove(name);
+        }
+        for (name, value) in self {
+            headers.append(name, value.clone());
+        }
+    }
+}
+
+/// An [`Authenticator`] that sets caller-supplied headers before authenticating.
+///
+/// This wraps the authenticator passed to
+/// [`OutgoingRequest::try_into_http_request`](crate::OutgoingRequest::try_into_http_request),
+/// so call-time headers are set before requests are signed.
+/// Besides a [`HeaderMap`], the headers can be any [`RequestHeaders`],
+/// such as those of [`WithLocale`](super::WithLocale),
+/// [`WithIdempotencyKey`](super::WithIdempotencyKey) and
+/// [`WithPrecondition`](super::WithPrecondition).
+///
+/// Headers of a `HeaderMap` are applied in order of precedence:
+///
+/// 1. the endpoint [`Metadata::headers`](crate::metadata::Metadata::headers),
+/// 2. headers set from the fields of the request,
+/// 3. the headers of `WithHeaders`, which replace all values of earlier headers with
+///    the same name.
+///
+/// Authentication headers are set by the inner authenticator afterwards.
+#[derive(Debug, Clone)]
+pub struct WithHeaders<A, H = HeaderMap> {
+    headers: H,
+    inner: A,
+}
+
+impl<A, H> WithHeaders<A, H> {
+    /// Wraps the `inner` authenticator, setting `headers` on requests.
+    #[inline]
+    #[must_use]
+    pub const fn new(headers: H, inner: A) -> Self {
+        Self { headers, inner }
+    }
+
+    /// Returns the headers set on requests.
+    #[inline]
+    #[must_use]
+    pub const fn headers(&self) -> &H {
+        &self.headers
+    }
+}
+
+impl<A: AuthScheme, H: fmt::Debug> AuthScheme for WithHeaders<A, H> {
+    fn scheme(&self) -> &'static str {
+        self.inner.scheme()
+    }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        self.inner.auth_headers()
+    }
+}
+
+impl<A: Authenticator, H: RequestHeaders + fmt::Debug> Authenticator for WithHeaders<A, H> {
+    type AuthData = A::AuthData;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        data: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        self.headers.set_headers(req.headers_mut());
+        self.inner.authenticate(req, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{
+        header::{ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION, IF_MATCH},
+        HeaderValue,
+    };
+
+    use super::*;
+    use crate::{auth::bearer::BearerAuth, tests::TestRequest, OutgoingRequest};
+
+    #[test]
+    fn overrides_metadata_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("text/plain"));
+        headers.append(ACCEPT_LANGUAGE, HeaderValue::from_static("de"));
+        headers.append(ACCEPT_LANGUAGE, HeaderValue::from_static("en"));
+        let auth = WithHeaders::new(headers, BearerAuth::new());
+        assert_eq!(auth.scheme(), "bearer");
+        assert_eq!(auth.auth_headers(), [AUTHORIZATION]);
+
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        }
+        .try_into_http_request("https://example.com", auth, &"token".to_owned())
+        .unwrap();
+        // `application/json` from the metadata is replaced
+        let accept: Vec<_> = req.headers().get_all(ACCEPT).iter().collect();
+        assert_eq!(accept, ["text/plain"]);
+        let languages: Vec<_> = req.headers().get_all(ACCEPT_LANGUAGE).iter().collect();
+        assert_eq!(languages, ["de", "en"]);
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+    }
+
+    #[test]
+    fn overrides_request_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_MATCH, HeaderValue::from_static("\"v2\""));
+        let auth = WithHeaders::new(headers, ());
+
+        // As set by an endpoint from its fields
+        let mut req = Request::builder()
+            .header(IF_MATCH, "\"v1\"")
+            .header(ACCEPT, "application/json")
+            .body(BytesMut::new())
+            .unwrap();
+        auth.authenticate(&mut req, &()).unwrap();
+        assert_eq!(req.headers()[IF_MATCH], "\"v2\"");
+        assert_eq!(req.headers().get_all(IF_MATCH).iter().count(), 1);
+        assert_eq!(req.headers()[ACCEPT], "application/json");
+    }
+}
@@ -0,0 +1,98 @@
+//! Integration with [`hyper`] body types.
+//!
+//! These helpers convert between the buffered request and response types of this crate
+//! and the [`http_body::Body`] types used by `hyper`, without depending on an async runtime.
+
+use bytes::{Bytes, BytesMut};
+use http_body::Body;
+use http_body_util::{BodyExt, Full};
+
+/// Convert an outgoing request into a `hyper` request.
+///
+/// The body is frozen into a [`Full`] body without copying it.
+#[must_use]
+pub fn to_hyper_request(req: http::Request<BytesMut>) -> hyper::Request<Full<Bytes>> {
+    req.map(|body| Full::new(body.freeze()))
+}
+
+/// Convert a `hyper` response into a buffered response.
+///
+/// The body (e.g. a [`hyper::body::Incoming`]) is collected into [`Bytes`],
+/// so the response can be passed to [`IncomingResponse::try_from_http_response`].
+///
+/// [`IncomingResponse::try_from_http_response`]: crate::IncomingResponse::try_from_http_response
+pub async fn from_hyper_response<B>(
+    res: hyper::Response<B>,
+) -> Result<http::Response<Bytes>, B::Error>
+where
+    B: Body,
+{
+    let (parts, body) = res.into_parts();
+    let body = body.collect().await?.to_bytes();
+    Ok(http::Response::from_parts(parts, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use http::{header::CONTENT_TYPE, Method, StatusCode};
+    use http_body::Frame;
+
+    use super::*;
+    use crate::tests::now_or_never;
+
+    /// A body yielding its chunks in reverse order.
+    struct Chunks(Vec<Bytes>);
+
+    impl Body for Chunks {
+        type Data = Bytes;
+        type Error = std::convert::Infallible;
+
+        fn poll_frame(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+            Poll::Ready(self.0.pop().map(|chunk| Ok(Frame::data(chunk))))
+        }
+    }
+
+    #[test]
+    fn hyper_request() {
+        let body = BytesMut::from(&b"hello"[..]);
+        let ptr = body.as_ptr();
+        let req = http::Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/items")
+            .header(CONTENT_TYPE, "text/plain")
+            .body(body)
+            .unwrap();
+
+        let req = to_hyper_request(req);
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), "https://example.com/items");
+        assert_eq!(req.headers()[CONTENT_TYPE], "text/plain");
+
+        let body = now_or_never(req.into_body().collect()).unwrap().to_bytes();
+        assert_eq!(body, "hello");
+        assert_eq!(body.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn hyper_response() {
+        let body = Chunks(vec![
+            Bytes::from_static(b"world"),
+            Bytes::from_static(b"hello "),
+        ]);
+        let res = hyper::Response::builder()
+            .status(StatusCode::CREATED)
+            .header(CONTENT_TYPE, "text/plain")
+            .body(body)
+            .unwrap();
+
+        let res = now_or_never(from_hyper_response(res)).unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[CONTENT_TYPE], "text/plain");
+        assert_eq!(res.body(), "hello world");
+    }
+}
@@ -0,0 +1,118 @@
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use super::{RequestHeaders, WithHeaders};
+
+/// The `Idempotency-Key` header.
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// The key of a logical operation, sent in the `Idempotency-Key` header.
+///
+/// Servers use the key to recognize retries of a request they have already processed,
+/// so the key must remain the same across all retry attempts of an operation,
+/// and must not be reused for a different operation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(HeaderValue);
+
+impl IdempotencyKey {
+    /// Creates a key from a caller-provided value.
+    #[inline]
+    #[must_use]
+    pub const fn new(key: HeaderValue) -> Self {
+        Self(key)
+    }
+
+    /// Generates a random (version 4) UUID key.
+    #[cfg(feature = "uuid")]
+    #[must_use]
+    pub fn new_v4() -> Self {
+        let mut buf = uuid::Uuid::encode_buffer();
+        let key = uuid::Uuid::new_v4().hyphenated().encode_lower(&mut buf);
+        Self(HeaderValue::from_str(key).expect("UUIDs are valid header values"))
+    }
+
+    /// Returns the `Idempotency-Key` value.
+    #[inline]
+    #[must_use]
+    pub const fn as_header_value(&self) -> &HeaderValue {
+        &self.0
+    }
+}
+
+/// Replaces any `Idempotency-Key` set by the endpoint.
+impl RequestHeaders for IdempotencyKey {
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(IDEMPOTENCY_KEY, self.0.clone());
+    }
+}
+
+/// An [`Authenticator`](crate::auth::Authenticator) that sets the `Idempotency-Key` header
+/// before authenticating.
+///
+/// To retry an operation safely, build every attempt with clones of the same
+/// `WithIdempotencyKey`, so they all send the same key:
+///
+/// ```ignore
+/// let auth = WithIdempotencyKey::new(IdempotencyKey::new_v4(), BearerAuth::new());
+/// for _ in 0..3 {
+///     let req = CreatePayment { amount: 100 }
+///         .try_into_http_request(base_url, auth.clone(), &token)?;
+///     // ...
+/// }
+/// ```
+pub type WithIdempotencyKey<A> = WithHeaders<A, IdempotencyKey>;
+
+impl<A> WithIdempotencyKey<A> {
+    /// Returns the key set on requests.
+    #[inline]
+    #[must_use]
+    pub const fn key(&self) -> &IdempotencyKey {
+        self.headers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::AUTHORIZATION;
+
+    use super::*;
+    use crate::{
+        auth::{bearer::BearerAuth, AuthScheme},
+        tests::TestRequest,
+        OutgoingRequest,
+    };
+
+    #[test]
+    fn reuses_key() {
+        let auth = WithIdempotencyKey::new(
+            IdempotencyKey::new(HeaderValue::from_static("op-1")),
+            BearerAuth::new(),
+        );
+        assert_eq!(auth.scheme(), "bearer");
+        assert_eq!(auth.auth_headers(), [AUTHORIZATION]);
+
+        let attempt = || {
+            TestRequest {
+                id: 1,
+                accept: true,
+            }
+            .try_into_http_request("https://example.com", auth.clone(), &"token".to_owned())
+            .unwrap()
+        };
+        let (first, retry) = (attempt(), attempt());
+        assert_eq!(first.headers()[IDEMPOTENCY_KEY], "op-1");
+        assert_eq!(
+            retry.headers()[IDEMPOTENCY_KEY],
+            first.headers()[IDEMPOTENCY_KEY]
+        );
+        assert_eq!(retry.headers().get_all(IDEMPOTENCY_KEY).iter().count(), 1);
+        assert_eq!(retry.headers()[AUTHORIZATION], "Bearer token");
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn new_v4() {
+        let key = IdempotencyKey::new_v4();
+        assert_eq!(key.as_header_value().len(), 36);
+        assert_ne!(key, IdempotencyKey::new_v4());
+    }
+}
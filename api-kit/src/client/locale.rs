@@ -0,0 +1,96 @@
+use http::{header::ACCEPT_LANGUAGE, HeaderMap, HeaderValue};
+
+use super::{RequestHeaders, WithHeaders};
+
+/// A client-wide locale, sent in the `Accept-Language` header.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Locale(HeaderValue);
+
+impl Locale {
+    /// Creates a locale from an `Accept-Language` value, e.g. `de-CH, de;q=0.9`.
+    #[inline]
+    #[must_use]
+    pub const fn new(locale: HeaderValue) -> Self {
+        Self(locale)
+    }
+
+    /// Returns the `Accept-Language` value.
+    #[inline]
+    #[must_use]
+    pub const fn as_header_value(&self) -> &HeaderValue {
+        &self.0
+    }
+}
+
+/// Keeps an `Accept-Language` header the request already has.
+impl RequestHeaders for Locale {
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        headers
+            .entry(ACCEPT_LANGUAGE)
+            .or_insert_with(|| self.0.clone());
+    }
+}
+
+/// An [`Authenticator`](crate::auth::Authenticator) that sets a client-wide `Accept-Language`
+/// header before authenticating.
+///
+/// The locale applies to every request built with the same `WithLocale`.
+/// Unlike other [`WithHeaders`], requests that already have an `Accept-Language` header,
+/// e.g. from the endpoint [`Metadata::headers`](crate::metadata::Metadata::headers),
+/// keep their own.
+pub type WithLocale<A> = WithHeaders<A, Locale>;
+
+impl<A> WithLocale<A> {
+    /// Returns the `Accept-Language` value set on requests.
+    #[inline]
+    #[must_use]
+    pub const fn locale(&self) -> &HeaderValue {
+        self.headers().as_header_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use http::{header::AUTHORIZATION, Request};
+
+    use super::*;
+    use crate::{
+        auth::{bearer::BearerAuth, AuthScheme, Authenticator},
+        tests::TestRequest,
+        OutgoingRequest,
+    };
+
+    #[test]
+    fn sets_locale() {
+        let auth = WithLocale::new(
+            Locale::new(HeaderValue::from_static("de-CH, de;q=0.9")),
+            BearerAuth::new(),
+        );
+        assert_eq!(auth.locale(), "de-CH, de;q=0.9");
+        assert_eq!(auth.scheme(), "bearer");
+        assert_eq!(auth.auth_headers(), [AUTHORIZATION]);
+
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        }
+        .try_into_http_request("https://example.com", auth, &"token".to_owned())
+        .unwrap();
+        assert_eq!(req.headers()[ACCEPT_LANGUAGE], "de-CH, de;q=0.9");
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+    }
+
+    #[test]
+    fn keeps_endpoint_locale() {
+        let auth = WithLocale::new(Locale::new(HeaderValue::from_static("fr")), ());
+        // As set by an endpoint from its `Metadata::headers`
+        let mut req = Request::builder()
+            .header(ACCEPT_LANGUAGE, "en")
+            .body(BytesMut::new())
+            .unwrap();
+        auth.authenticate(&mut req, &()).unwrap();
+        assert_eq!(req.headers()[ACCEPT_LANGUAGE], "en");
+        assert_eq!(req.headers().get_all(ACCEPT_LANGUAGE).iter().count(), 1);
+    }
+}
@@ -0,0 +1,342 @@
+#[cfg(feature = "stream")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use http::header::LINK;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::DeserializeError, response::parse_link_header, IncomingResponse, OutgoingRequest,
+};
+
+/// A page of a paginated list endpoint.
+///
+/// [`IncomingResponse`] implementations don't see the request,
+/// so the next page is built from the request of this page,
+/// e.g. by cloning it with the cursor of this page (see [`next_cursor`]),
+/// or with the query of the `next` link of this page (see [`next_link`]).
+pub trait Paginated: IncomingResponse {
+    /// Returns the request for the page after this one,
+    /// or `None` if this is the last page.
+    ///
+    /// `request` is the request this page is a response to.
+    fn next_page(&self, request: &Self::OutgoingRequest) -> Option<Self::OutgoingRequest>;
+}
+
+/// Builds the next page request of a cursor-in-body paginated endpoint.
+///
+/// `cursor` is the cursor of the next page returned in the body of the current page.
+/// Returns `None` if it is missing or empty, as sent by many APIs for the last page.
+/// Otherwise, `request` is cloned and `set_cursor` sets the cursor on the clone.
+///
+/// ```ignore
+/// impl Paginated for UsersPage {
+///     fn next_page(&self, request: &ListUsers) -> Option<ListUsers> {
+///         next_cursor(request, self.next.as_deref(), |req, cursor| {
+///             req.cursor = Some(cursor.to_owned());
+///         })
+///     }
+/// }
+/// ```
+pub fn next_cursor<R: Clone>(
+    request: &R,
+    cursor: Option<&str>,
+    set_cursor: impl FnOnce(&mut R, &str),
+) -> Option<R> {
+    let cursor = cursor.filter(|cursor| !cursor.is_empty())?;
+    let mut request = request.clone();
+    set_cursor(&mut request, cursor);
+    Some(request)
+}
+
+/// Returns the target of the `rel="next"` link of a response,
+/// from its `Link` headers (see [`parse_link_header`]).
+///
+/// Malformed `Link` headers are ignored.
+/// The target is returned as sent, which may be a relative reference.
+/// [`IncomingResponse`] implementations should keep it in a field of the response,
+/// for [`Paginated::next_page`] to read its query with [`link_query`].
+#[must_use]
+pub fn next_link<T>(res: &http::Response<T>) -> Option<String> {
+    res.headers()
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| parse_link_header(value).ok())
+        .flatten()
+        .find(|link| link.has_rel("next"))
+        .map(|link| link.target)
+}
+
+/// Deserialize the query of a link target, e.g. `/users?page=2&per_page=50`.
+///
+/// A link target without a query is deserialized from an empty query.
+pub fn link_query<Q: DeserializeOwned>(target: &str) -> Result<Q, DeserializeError> {
+    let target = target.split_once('#').map_or(target, |(target, _)| target);
+    let query = target.split_once('?').map_or("", |(_, query)| query);
+    Ok(serde_urlencoded::from_str(query)?)
+}
+
+/// Iterate over the pages of a paginated endpoint, starting with `first`.
+///
+/// Each request is sent with `send`, e.g. a closure calling `blocking::send`,
+/// until [`Paginated::next_page`] returns `None` or `send` returns an error.
+/// The error is yielded as the last item.
+///
+/// ```ignore
+/// let agent = blocking::agent();
+/// for page in paginate(ListUsers::default(), |req| {
+///     blocking::send(&agent, req, base_url, BearerAuth::new(), &token)
+/// }) {
+///     for user in page?.users {
+///         // ...
+///     }
+/// }
+/// ```
+pub const fn paginate<R, F, E>(first: R, send: F) -> Pages<R, F>
+where
+    R: OutgoingRequest,
+    R::IncomingResponse: Paginated,
+    F: FnMut(R) -> Result<R::IncomingResponse, E>,
+{
+    Pages {
+        next: Some(first),
+        send,
+    }
+}
+
+/// An iterator over the pages of a paginated endpoint.
+///
+/// See [`paginate`].
+#[derive(Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Pages<R, F> {
+    next: Option<R>,
+    send: F,
+}
+
+impl<R, F, E> Iterator for Pages<R, F>
+where
+    R: OutgoingRequest,
+    R::IncomingResponse: Paginated,
+    F: FnMut(R) -> Result<R::IncomingResponse, E>,
+{
+    type Item = Result<R::IncomingResponse, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let request = self.next.take()?;
+        let page = (self.send)(request.clone());
+        if let Ok(page) = &page {
+            self.next = page.next_page(&request);
+        }
+        Some(page)
+    }
+}
+
+/// Stream the pages of a paginated endpoint, starting with `first`.
+///
+/// This is the asynchronous version of [`paginate`]:
+/// each request is sent with the future returned by `send`,
+/// e.g. a closure returning the future of `ReqwestExt::send`.
+#[cfg(feature = "stream")]
+pub const fn paginate_stream<R, F, Fut, E>(first: R, send: F) -> PageStream<R, F, Fut>
+where
+    R: OutgoingRequest,
+    R::IncomingResponse: Paginated,
+    F: FnMut(R) -> Fut,
+    Fut: Future<Output = Result<R::IncomingResponse, E>>,
+{
+    PageStream {
+        state: StreamState::Next(first),
+        send,
+    }
+}
+
+/// A stream of the pages of a paginated endpoint.
+///
+/// See [`paginate_stream`].
+#[cfg(feature = "stream")]
+#[must_use = "streams do nothing unless polled"]
+pub struct PageStream<R, F, Fut> {
+    state: StreamState<R, Fut>,
+    send: F,
+}
+
+#[cfg(feature = "stream")]
+enum StreamState<R, Fut> {
+    /// The next request to send.
+    Next(R),
+    /// A request being sent.
+    Sending(R, Pin<Box<Fut>>),
+    Done,
+}
+
+// The future is pinned in a `Box`, so the stream is never structurally pinned
+#[cfg(feature = "stream")]
+impl<R, F, Fut> Unpin for PageStream<R, F, Fut> {}
+
+#[cfg(feature = "stream")]
+impl<R, F, Fut, E> futures_core::Stream for PageStream<R, F, Fut>
+where
+    R: OutgoingRequest,
+    R::IncomingResponse: Paginated,
+    F: FnMut(R) -> Fut,
+    Fut: Future<Output = Result<R::IncomingResponse, E>>,
+{
+    type Item = Result<R::IncomingResponse, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match std::mem::replace(&mut this.state, StreamState::Done) {
+                StreamState::Next(request) => {
+                    let fut = Box::pin((this.send)(request.clone()));
+                    this.state = StreamState::Sending(request, fut);
+                }
+                StreamState::Sending(request, mut fut) => {
+                    let Poll::Ready(page) = fut.as_mut().poll(cx) else {
+                        this.state = StreamState::Sending(request, fut);
+                        return Poll::Pending;
+                    };
+                    if let Some(next) = page.as_ref().ok().and_then(|p| p.next_page(&request)) {
+                        this.state = StreamState::Next(next);
+                    }
+                    return Poll::Ready(Some(page));
+                }
+                StreamState::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::tests::{TestRequest, TestResponse};
+
+    // Pages 1 to 3
+    impl Paginated for TestResponse {
+        fn next_page(&self, request: &TestRequest) -> Option<TestRequest> {
+            (request.id < 3).then(|| TestRequest {
+                id: request.id + 1,
+                ..request.clone()
+            })
+        }
+    }
+
+    fn first() -> TestRequest {
+        TestRequest {
+            id: 1,
+            accept: true,
+        }
+    }
+
+    #[test]
+    fn pages() {
+        let mut sent = Vec::new();
+        let pages = paginate(first(), |req| {
+            sent.push(req.id);
+            Ok::<_, ()>(TestResponse)
+        });
+        assert_eq!(pages.count(), 3);
+        assert_eq!(sent, [1, 2, 3]);
+
+        let mut pages = paginate(first(), |req| {
+            if req.id == 2 {
+                Err("failed")
+            } else {
+                Ok(TestResponse)
+            }
+        });
+        assert!(pages.next().unwrap().is_ok());
+        assert_eq!(pages.next().unwrap().unwrap_err(), "failed");
+        assert!(pages.next().is_none());
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn page_stream() {
+        use std::task::Waker;
+
+        use futures_core::Stream;
+
+        let mut sent = Vec::new();
+        let mut stream = paginate_stream(first(), |req| {
+            sent.push(req.id);
+            std::future::ready(Ok::<_, ()>(TestResponse))
+        });
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut pages = 0;
+        while let Poll::Ready(Some(page)) = Pin::new(&mut stream).poll_next(&mut cx) {
+            assert!(page.is_ok());
+            pages += 1;
+        }
+        assert_eq!(pages, 3);
+        drop(stream);
+        assert_eq!(sent, [1, 2, 3]);
+    }
+
+    #[test]
+    fn cursor() {
+        let set_cursor = |req: &mut TestRequest, cursor: &str| req.id = cursor.parse().unwrap();
+        assert_eq!(next_cursor(&first(), Some("7"), set_cursor).unwrap().id, 7);
+        assert!(next_cursor(&first(), Some(""), set_cursor).is_none());
+        assert!(next_cursor(&first(), None, set_cursor).is_none());
+    }
+
+    #[test]
+    fn links() {
+        #[derive(Debug, PartialEq, Eq, Deserialize)]
+        struct Page {
+            page: u32,
+            per_page: Option<u32>,
+        }
+
+        let response = |links: &[&'static str]| {
+            let mut res = http::Response::builder();
+            for &link in links {
+                res = res.header(LINK, HeaderValue::from_static(link));
+            }
+            res.body(()).unwrap()
+        };
+
+        assert_eq!(
+            next_link(&response(&[
+                r#"<https://api.example.com/users?page=1>; rel="prev", <https://api.example.com/users?page=3>; rel="next""#
+            ]))
+            .as_deref(),
+            Some("https://api.example.com/users?page=3")
+        );
+        assert_eq!(
+            next_link(&response(&[
+                r#"</users?a=1,2>; title="a, b"; rel=first"#,
+                r#"</users?page=2>; REL="last next""#,
+            ]))
+            .as_deref(),
+            Some("/users?page=2")
+        );
+        assert_eq!(
+            next_link(&response(&[r#"</users?page=1>; rel="prev""#])),
+            None
+        );
+        assert_eq!(next_link(&response(&[])), None);
+        assert_eq!(
+            next_link(&response(&["not a link", "</users?page=2>; rel=next"])).as_deref(),
+            Some("/users?page=2")
+        );
+
+        assert_eq!(
+            link_query::<Page>("/users?page=2&per_page=50#top").unwrap(),
+            Page {
+                page: 2,
+                per_page: Some(50),
+            }
+        );
+        assert!(link_query::<Page>("/users").is_err());
+    }
+}
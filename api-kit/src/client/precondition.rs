@@ -0,0 +1,128 @@
+use http::{
+    header::{IF_MATCH, IF_NONE_MATCH},
+    HeaderMap, HeaderName,
+};
+
+use super::{RequestHeaders, WithHeaders};
+use crate::conditional::ETagMatch;
+
+/// An `If-Match` or `If-None-Match` precondition on entity tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Precondition {
+    header: HeaderName,
+    tags: ETagMatch,
+}
+
+impl Precondition {
+    /// Requires `If-Match` to match `tags`,
+    /// e.g. to only update a resource that wasn't modified since it was read.
+    #[inline]
+    #[must_use]
+    pub fn if_match(tags: impl Into<ETagMatch>) -> Self {
+        Self {
+            header: IF_MATCH,
+            tags: tags.into(),
+        }
+    }
+
+    /// Requires `If-None-Match` to match `tags`,
+    /// e.g. to only fetch a resource that was modified since it was cached.
+    #[inline]
+    #[must_use]
+    pub fn if_none_match(tags: impl Into<ETagMatch>) -> Self {
+        Self {
+            header: IF_NONE_MATCH,
+            tags: tags.into(),
+        }
+    }
+
+    /// Returns the entity tags of the precondition.
+    #[inline]
+    #[must_use]
+    pub const fn tags(&self) -> &ETagMatch {
+        &self.tags
+    }
+}
+
+/// Replaces a precondition set by the endpoint with the same header.
+impl RequestHeaders for Precondition {
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(self.header.clone(), self.tags.to_header_value());
+    }
+}
+
+/// An [`Authenticator`](crate::auth::Authenticator) that sets an `If-Match` or `If-None-Match`
+/// precondition before authenticating.
+///
+/// Responses to conditional `GET` requests can be converted with
+/// [`try_from_conditional_response`](crate::conditional::try_from_conditional_response).
+pub type WithPrecondition<A> = WithHeaders<A, Precondition>;
+
+impl<A> WithPrecondition<A> {
+    /// Wraps the `inner` authenticator, setting `If-Match` to `tags`.
+    ///
+    /// See [`Precondition::if_match`].
+    #[inline]
+    #[must_use]
+    pub fn if_match(tags: impl Into<ETagMatch>, inner: A) -> Self {
+        Self::new(Precondition::if_match(tags), inner)
+    }
+
+    /// Wraps the `inner` authenticator, setting `If-None-Match` to `tags`.
+    ///
+    /// See [`Precondition::if_none_match`].
+    #[inline]
+    #[must_use]
+    pub fn if_none_match(tags: impl Into<ETagMatch>, inner: A) -> Self {
+        Self::new(Precondition::if_none_match(tags), inner)
+    }
+
+    /// Returns the entity tags of the precondition.
+    #[inline]
+    #[must_use]
+    pub const fn tags(&self) -> &ETagMatch {
+        self.headers().tags()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use http::{header::AUTHORIZATION, Request};
+
+    use super::*;
+    use crate::{
+        auth::{bearer::BearerAuth, AuthScheme, Authenticator},
+        conditional::ETag,
+        tests::TestRequest,
+        OutgoingRequest,
+    };
+
+    fn request<A: Authenticator<AuthData = String>>(auth: A) -> Request<BytesMut> {
+        TestRequest {
+            id: 1,
+            accept: true,
+        }
+        .try_into_http_request("https://example.com", auth, &"token".to_owned())
+        .unwrap()
+    }
+
+    #[test]
+    fn sets_precondition() {
+        let etag = ETag::weak("v1").unwrap();
+        let auth = WithPrecondition::if_none_match(etag.clone(), BearerAuth::new());
+        assert_eq!(auth.scheme(), "bearer");
+        assert_eq!(auth.tags(), &ETagMatch::from(etag));
+
+        let req = request(auth);
+        assert_eq!(req.headers()[IF_NONE_MATCH], "W/\"v1\"");
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+
+        let req = request(WithPrecondition::if_match(
+            ETagMatch::Any,
+            BearerAuth::new(),
+        ));
+        assert_eq!(req.headers()[IF_MATCH], "*");
+        assert!(!req.headers().contains_key(IF_NONE_MATCH));
+    }
+}
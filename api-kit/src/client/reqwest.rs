@@ -0,0 +1,249 @@
+//! Integration with the [`reqwest`] HTTP client.
+
+use std::{future::Future, time::SystemTime};
+
+use http::header::RETRY_AFTER;
+
+use crate::{
+    auth::Authenticator,
+    error::{parse_retry_after, IntoHttpError, SendError},
+    Endpoint, IncomingResponse, OutgoingRequest,
+};
+
+/// Conversion of outgoing requests into [`reqwest::Request`]s.
+///
+/// This is implemented for all [`OutgoingRequest`]s,
+/// so requests can be sent with an existing [`reqwest::Client`] and its middleware.
+pub trait IntoReqwest: OutgoingRequest {
+    /// Convert into a [`reqwest::Request`].
+    ///
+    /// The request is built with [`OutgoingRequest::try_into_http_request`].
+    /// The body is moved into the [`reqwest::Body`] without copying it.
+    /// The endpoint [`timeout`](crate::metadata::Metadata::timeout), if any,
+    /// is set as the request timeout.
+    fn into_reqwest<A>(
+        self,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<reqwest::Request, IntoHttpError>
+    where
+        A: Authenticator;
+}
+
+impl<R: OutgoingRequest> IntoReqwest for R {
+    fn into_reqwest<A>(
+        self,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<reqwest::Request, IntoHttpError>
+    where
+        A: Authenticator,
+    {
+        let req = self.try_into_http_request(base_url, auth, auth_data)?;
+        let mut req = reqwest::Request::try_from(req.map(bytes::BytesMut::freeze))?;
+        if let Some(timeout) = Self::METADATA.timeout() {
+            *req.timeout_mut() = Some(timeout);
+        }
+        Ok(req)
+    }
+}
+
+/// Sending outgoing requests with a [`reqwest::Client`].
+pub trait ReqwestExt {
+    /// Send `req` and convert its response.
+    ///
+    /// The request is built with [`IntoReqwest::into_reqwest`],
+    /// and the response is converted with
+    /// [`IncomingResponse::try_from_http_response_by_status`],
+    /// so error responses are converted into the endpoint [`Error`](Endpoint::Error).
+    ///
+    /// Responses are retried as allowed by the endpoint
+    /// [`RETRY_POLICY`](Endpoint::RETRY_POLICY),
+    /// waiting for the `Retry-After` delay of the response, if any.
+    /// Responses asking for a delay longer than [`RetryPolicy::max_delay`](crate::metadata::RetryPolicy::max_delay)
+    /// are returned without retrying.
+    /// Retries resend the same request, which is authenticated only once.
+    fn send<R, A>(
+        &self,
+        req: R,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> impl Future<Output = Result<R::IncomingResponse, SendError<<R as Endpoint>::Error>>> + Send
+    where
+        R: OutgoingRequest,
+        A: Authenticator;
+}
+
+impl ReqwestExt for reqwest::Client {
+    fn send<R, A>(
+        &self,
+        req: R,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> impl Future<Output = Result<R::IncomingResponse, SendError<<R as Endpoint>::Error>>> + Send
+    where
+        R: OutgoingRequest,
+        A: Authenticator,
+    {
+        let req = req.into_reqwest(base_url, auth, auth_data);
+        async move {
+            let mut req = req?;
+            let mut attempt = 1;
+            let res = loop {
+                // Requests with streaming bodies can't be cloned, and are never retried
+                let retry = req.try_clone();
+                let res = self.execute(req).await?;
+                match retry {
+                    Some(retry)
+                        if R::RETRY_POLICY.should_retry(
+                            &R::METADATA.method,
+                            attempt,
+                            res.status(),
+                        ) =>
+                    {
+                        if let Some(delay) = res
+                            .headers()
+                            .get(RETRY_AFTER)
+                            .and_then(|value| parse_retry_after(value, SystemTime::now()))
+                        {
+                            if delay > R::RETRY_POLICY.max_delay {
+                                break res;
+                            }
+                            tokio::time::sleep(delay).await;
+                        }
+                        req = retry;
+                        attempt += 1;
+                    }
+                    _ => break res,
+                }
+            };
+
+            let mut builder = http::Response::builder()
+                .status(res.status())
+                .version(res.version());
+            if let Some(headers) = builder.headers_mut() {
+                headers.extend(res.headers().clone());
+            }
+            let body = res.bytes().await?;
+            let res = builder.body(body).map_err(IntoHttpError::from)?;
+
+            Ok(R::IncomingResponse::try_from_http_response_by_status(res)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use http::{header::AUTHORIZATION, Method, Version};
+
+    use super::*;
+    use crate::{
+        auth::bearer::BearerAuth,
+        error::FromHttpResponseError,
+        tests::{
+            server::{serve, serve_once, GetItem, Item},
+            TestError, TestRequest,
+        },
+    };
+
+    fn send(base_url: &str) -> Result<Item, SendError<TestError>> {
+        let client = reqwest::Client::new();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(client.send(GetItem, base_url, BearerAuth::new(), &"token".to_owned()))
+    }
+
+    #[test]
+    fn into_reqwest() {
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        };
+        let req = req
+            .into_reqwest(
+                "https://example.com",
+                BearerAuth::new(),
+                &"token".to_owned(),
+            )
+            .unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.version(), Version::HTTP_2);
+        assert_eq!(req.url().as_str(), "https://example.com/items/1");
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+        assert_eq!(req.headers()["accept"], "application/json");
+        assert_eq!(req.body().and_then(reqwest::Body::as_bytes), Some(&b""[..]));
+        assert_eq!(req.timeout(), None);
+
+        let req = GetItem
+            .into_reqwest(
+                "https://example.com",
+                BearerAuth::new(),
+                &"token".to_owned(),
+            )
+            .unwrap();
+        assert_eq!(req.timeout(), Some(&Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn send_round_trip() {
+        let (base_url, server) = serve_once("HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}");
+        assert_eq!(send(&base_url).unwrap(), Item(Bytes::from_static(b"{}")));
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /items/1 HTTP/1.1\r\n"));
+        assert!(request.contains("authorization: Bearer token\r\n"));
+
+        let (base_url, _) = serve_once("HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n");
+        assert!(matches!(
+            send(&base_url).unwrap_err(),
+            SendError::Response(FromHttpResponseError::EndpointError(TestError))
+        ));
+
+        assert!(matches!(
+            send("not a url").unwrap_err(),
+            SendError::IntoHttp(_)
+        ));
+    }
+
+    #[test]
+    fn send_retries() {
+        let (base_url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 0\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}",
+        ]);
+        assert_eq!(send(&base_url).unwrap(), Item(Bytes::from_static(b"{}")));
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("authorization: Bearer token\r\n"));
+
+        // Only `max_attempts` attempts are made
+        let (base_url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            "HTTP/1.1 503 Service Unavailable\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+        ]);
+        assert!(matches!(
+            send(&base_url).unwrap_err(),
+            SendError::Response(FromHttpResponseError::EndpointError(TestError))
+        ));
+        assert_eq!(server.join().unwrap().len(), 2);
+
+        // Delays longer than `max_delay` are not waited for
+        let (base_url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 3600\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+        ]);
+        assert!(matches!(
+            send(&base_url).unwrap_err(),
+            SendError::Response(FromHttpResponseError::EndpointError(TestError))
+        ));
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+}
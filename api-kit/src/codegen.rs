@@ -0,0 +1,333 @@
+//! Generating body types from JSON Schemas.
+//!
+//! [`structs_from_schema`] generates `serde` structs for object schemas,
+//! and is meant to be called from a build script:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let schema = serde_json::from_str(&std::fs::read_to_string("schemas/user.json")?)?;
+//! let source = api_kit::codegen::structs_from_schema("User", &schema)?;
+//! let out_dir = std::env::var("OUT_DIR")?;
+//! std::fs::write(format!("{out_dir}/user.rs"), source)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The generated file is then included with `include!(concat!(env!("OUT_DIR"), "/user.rs"));`.
+//! The generated structs derive `serde::Serialize` and `serde::Deserialize`,
+//! so the including crate must depend on `serde` with the `derive` feature.
+//!
+//! Object schemas with `string`, `integer`, `number`, `boolean`, nested `object`
+//! and `array` properties are supported.
+//! Properties not listed in `required` are generated as `Option`s.
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use serde_json::Value;
+
+use crate::error::SchemaError;
+
+/// Words that are used as raw identifiers, e.g. `r#type`.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Words that can't be raw identifiers, and are suffixed with `_` instead.
+const RESERVED: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Generate the Rust source of a struct named `name` for the object `schema`.
+///
+/// Nested object schemas are generated as additional structs,
+/// named after their parent struct and property, e.g. `UserAddress`.
+pub fn structs_from_schema(name: &str, schema: &Value) -> Result<String, SchemaError> {
+    let mut structs = Vec::new();
+    generate_struct(name, "#", schema, &mut structs)?;
+    Ok(structs.concat())
+}
+
+fn generate_struct(
+    name: &str,
+    path: &str,
+    schema: &Value,
+    structs: &mut Vec<String>,
+) -> Result<(), SchemaError> {
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        return Err(SchemaError::NotAnObject(path.to_owned()));
+    }
+
+    // Reserve the slot of this struct, so it comes before its nested structs
+    let index = structs.len();
+    structs.push(String::new());
+
+    let required: BTreeSet<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+
+    let mut def = String::new();
+    if index > 0 {
+        def.push('\n');
+    }
+    write_doc(&mut def, "", schema);
+    let _ = writeln!(
+        def,
+        "#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]\npub struct {name} {{"
+    );
+
+    let mut idents = BTreeSet::new();
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (property, property_schema) in properties {
+            let property_path = format!("{path}/properties/{property}");
+            let ident = field_ident(property);
+            if !idents.insert(ident.clone()) {
+                return Err(SchemaError::DuplicateField(property_path));
+            }
+
+            let ty = rust_type(
+                &format!("{name}{}", type_ident(property)),
+                &property_path,
+                property_schema,
+                structs,
+            )?;
+
+            write_doc(&mut def, "    ", property_schema);
+            let rename = ident.strip_prefix("r#").unwrap_or(&ident) != property;
+            if required.contains(property.as_str()) {
+                if rename {
+                    let _ = writeln!(def, "    #[serde(rename = {property:?})]");
+                }
+                let _ = writeln!(def, "    pub {ident}: {ty},");
+            } else {
+                let rename = if rename {
+                    format!("rename = {property:?}, ")
+                } else {
+                    String::new()
+                };
+                let _ = writeln!(
+                    def,
+                    "    #[serde({rename}default, skip_serializing_if = \"Option::is_none\")]"
+                );
+                let _ = writeln!(def, "    pub {ident}: Option<{ty}>,");
+            }
+        }
+    }
+    def.push_str("}\n");
+
+    structs[index] = def;
+    Ok(())
+}
+
+/// Returns the Rust type of a property, generating a struct named `name` for objects.
+fn rust_type(
+    name: &str,
+    path: &str,
+    schema: &Value,
+    structs: &mut Vec<String>,
+) -> Result<String, SchemaError> {
+    let ty = match schema.get("type").and_then(Value::as_str) {
+        Some("string") => "String".to_owned(),
+        Some("integer") => "i64".to_owned(),
+        Some("number") => "f64".to_owned(),
+        Some("boolean") => "bool".to_owned(),
+        Some("object") => {
+            generate_struct(name, path, schema, structs)?;
+            name.to_owned()
+        }
+        Some("array") => {
+            let items = schema
+                .get("items")
+                .ok_or_else(|| SchemaError::UnsupportedType {
+                    path: path.to_owned(),
+                    ty: "array without items".to_owned(),
+                })?;
+            let item = rust_type(
+                &format!("{name}Item"),
+                &format!("{path}/items"),
+                items,
+                structs,
+            )?;
+            format!("Vec<{item}>")
+        }
+        _ => {
+            return Err(SchemaError::UnsupportedType {
+                path: path.to_owned(),
+                ty: schema
+                    .get("type")
+                    .map_or_else(|| "none".to_owned(), Value::to_string),
+            })
+        }
+    };
+    Ok(ty)
+}
+
+/// Write the `title` and `description` of a schema as doc comments.
+fn write_doc(out: &mut String, indent: &str, schema: &Value) {
+    let docs = ["title", "description"]
+        .into_iter()
+        .filter_map(|key| schema.get(key).and_then(Value::as_str));
+    for (i, doc) in docs.enumerate() {
+        if i > 0 {
+            let _ = writeln!(out, "{indent}///");
+        }
+        for line in doc.lines() {
+            let _ = writeln!(out, "{indent}/// {}", line.trim_end());
+        }
+    }
+}
+
+/// Convert a property name into a `snake_case` field identifier.
+fn field_ident(property: &str) -> String {
+    let mut ident = String::new();
+    let mut prev_lower = false;
+    for c in property.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            ident.push(c);
+            prev_lower = true;
+        } else {
+            if !ident.is_empty() && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    ident.truncate(ident.trim_end_matches('_').len());
+
+    if ident.is_empty() {
+        ident.push_str("field");
+    } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if RESERVED.contains(&ident.as_str()) {
+        ident.push('_');
+    } else if KEYWORDS.contains(&ident.as_str()) {
+        ident.insert_str(0, "r#");
+    }
+    ident
+}
+
+/// Convert a property name into a `PascalCase` type name suffix.
+fn type_ident(property: &str) -> String {
+    let mut ident = String::new();
+    for part in property.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            ident.push(first.to_ascii_uppercase());
+            ident.extend(chars);
+        }
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert_str(0, "Field");
+    }
+    ident
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn object_schema() {
+        let schema = json!({
+            "title": "A user.",
+            "type": "object",
+            "required": ["id", "displayName", "address"],
+            "properties": {
+                "id": { "type": "integer", "description": "The user ID." },
+                "displayName": { "type": "string" },
+                "score": { "type": "number" },
+                "type": { "type": "string" },
+                "address": {
+                    "type": "object",
+                    "required": ["city"],
+                    "properties": {
+                        "city": { "type": "string" },
+                        "verified": { "type": "boolean" },
+                    },
+                },
+                "tags": { "type": "array", "items": { "type": "string" } },
+            },
+        });
+
+        assert_eq!(
+            structs_from_schema("User", &schema).unwrap(),
+            r#"/// A user.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct User {
+    pub address: UserAddress,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    /// The user ID.
+    pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserAddress {
+    pub city: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(field_ident("userId"), "user_id");
+        assert_eq!(field_ident("user-name"), "user_name");
+        assert_eq!(field_ident("HTTPStatus"), "httpstatus");
+        assert_eq!(field_ident("2fa"), "_2fa");
+        assert_eq!(field_ident("self"), "self_");
+        assert_eq!(field_ident("match"), "r#match");
+        assert_eq!(field_ident("-"), "field");
+        assert_eq!(type_ident("shipping_address"), "ShippingAddress");
+        assert_eq!(type_ident("1st"), "Field1st");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            structs_from_schema("User", &json!({ "type": "string" })).unwrap_err(),
+            SchemaError::NotAnObject(path) if path == "#"
+        ));
+        assert!(matches!(
+            structs_from_schema(
+                "User",
+                &json!({ "type": "object", "properties": { "id": { "type": ["string", "null"] } } })
+            )
+            .unwrap_err(),
+            SchemaError::UnsupportedType { path, .. } if path == "#/properties/id"
+        ));
+        assert!(matches!(
+            structs_from_schema(
+                "User",
+                &json!({
+                    "type": "object",
+                    "properties": { "userId": { "type": "string" }, "user_id": { "type": "string" } },
+                })
+            )
+            .unwrap_err(),
+            SchemaError::DuplicateField(_)
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+//! `gzip` and `deflate` body compression.
+//!
+//! Request bodies can be compressed with [`compress_request`] after they are serialized,
+//! and responses decompressed with [`decompress_response`]
+//! before they are given to [`IncomingResponse::try_from_http_response`].
+//! Requests built with a [`WithAcceptEncoding`] authenticator advertise the encodings
+//! that can be decompressed, so servers know to compress responses.
+//!
+//! [`IncomingResponse::try_from_http_response`]: crate::IncomingResponse::try_from_http_response
+
+use std::io::{Read, Write};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use flate2::{
+    read::{DeflateDecoder, GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use http::{
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH},
+    HeaderMap, HeaderValue, Request, Response,
+};
+
+use crate::{
+    client::{RequestHeaders, WithHeaders},
+    error::DeserializeError,
+};
+
+/// A content encoding.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// The `gzip` encoding.
+    Gzip,
+    /// The `deflate` encoding, zlib-wrapped as per RFC 9110.
+    Deflate,
+}
+
+impl Encoding {
+    /// Returns the `Content-Encoding` token of this encoding.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    /// Parses a `Content-Encoding` token, ignoring case.
+    ///
+    /// Returns `None` for unsupported encodings.
+    #[must_use]
+    pub const fn from_token(token: &str) -> Option<Self> {
+        if token.eq_ignore_ascii_case("gzip") || token.eq_ignore_ascii_case("x-gzip") {
+            Some(Self::Gzip)
+        } else if token.eq_ignore_ascii_case("deflate") {
+            Some(Self::Deflate)
+        } else {
+            None
+        }
+    }
+}
+
+/// The encodings responses are accepted in, sent in the `Accept-Encoding` header.
+///
+/// Defaults to `gzip, deflate`, all encodings [`decompress_response`] supports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AcceptEncoding(HeaderValue);
+
+impl AcceptEncoding {
+    /// Accepts `encodings`, in order of preference.
+    ///
+    /// No encodings accept only uncompressed (`identity`) responses.
+    #[must_use]
+    pub fn new(encodings: &[Encoding]) -> Self {
+        if encodings.is_empty() {
+            return Self(HeaderValue::from_static("identity"));
+        }
+        let value = encodings
+            .iter()
+            .map(|encoding| encoding.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self(HeaderValue::from_str(&value).expect("encoding tokens are valid header values"))
+    }
+
+    /// Returns the `Accept-Encoding` value.
+    #[inline]
+    #[must_use]
+    pub const fn as_header_value(&self) -> &HeaderValue {
+        &self.0
+    }
+}
+
+impl Default for AcceptEncoding {
+    fn default() -> Self {
+        Self(HeaderValue::from_static("gzip, deflate"))
+    }
+}
+
+/// Keeps an `Accept-Encoding` header the request already has.
+impl RequestHeaders for AcceptEncoding {
+    fn set_headers(&self, headers: &mut HeaderMap) {
+        headers
+            .entry(ACCEPT_ENCODING)
+            .or_insert_with(|| self.0.clone());
+    }
+}
+
+/// An [`Authenticator`](crate::auth::Authenticator) that sets the `Accept-Encoding` header
+/// before authenticating.
+///
+/// Use it with [`decompress_response`], whose encodings it advertises by default:
+///
+/// ```ignore
+/// let auth = WithAcceptEncoding::new(AcceptEncoding::default(), BearerAuth::new());
+/// let req = GetUser { id: 1 }.try_into_http_request(base_url, auth, &token)?;
+/// // ...
+/// let user = GetUserResponse::try_from_http_response(decompress_response(res)?)?;
+/// ```
+///
+/// Requests that already have an `Accept-Encoding` header,
+/// e.g. from the endpoint [`Metadata::headers`](crate::metadata::Metadata::headers),
+/// keep their own.
+pub type WithAcceptEncoding<A> = WithHeaders<A, AcceptEncoding>;
+
+/// Compress `body` with `encoding`.
+#[must_use]
+pub fn compress(body: &[u8], encoding: Encoding) -> BytesMut {
+    let writer = BytesMut::with_capacity(body.len() / 2).writer();
+    let writer = match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            encoder.write_all(body).and_then(|()| encoder.finish())
+        }
+        Encoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(writer, Compression::default());
+            encoder.write_all(body).and_then(|()| encoder.finish())
+        }
+    };
+    writer
+        .expect("writing to a `BytesMut` can't fail")
+        .into_inner()
+}
+
+/// The default maximum length of decompressed bodies, 64 MiB.
+///
+/// This protects against decompression bombs, small bodies expanding to gigabytes.
+pub const MAX_DECOMPRESSED_LEN: usize = 64 * 1024 * 1024;
+
+/// Decompress `body` encoded with `encoding`,
+/// to at most [`MAX_DECOMPRESSED_LEN`] bytes.
+///
+/// # Errors
+///
+/// Returns [`DeserializeError::Decompress`] if the body is malformed,
+/// and [`DeserializeError::DecompressedTooLarge`] if it decompresses to more bytes.
+pub fn decompress(body: &[u8], encoding: Encoding) -> Result<Bytes, DeserializeError> {
+    decompress_with_limit(body, encoding, MAX_DECOMPRESSED_LEN)
+}
+
+/// Decompress `body` encoded with `encoding`, to at most `max_len` bytes.
+///
+/// # Errors
+///
+/// See [`decompress`].
+pub fn decompress_with_limit(
+    body: &[u8],
+    encoding: Encoding,
+    max_len: usize,
+) -> Result<Bytes, DeserializeError> {
+    // Read one byte past the limit to detect longer bodies
+    let limit = u64::try_from(max_len).unwrap_or(u64::MAX).saturating_add(1);
+    let mut out = Vec::with_capacity(body.len().saturating_mul(2).min(max_len));
+    let res = match encoding {
+        Encoding::Gzip => GzDecoder::new(body).take(limit).read_to_end(&mut out),
+        Encoding::Deflate => ZlibDecoder::new(body)
+            .take(limit)
+            .read_to_end(&mut out)
+            .or_else(|_| {
+                // Some servers send raw deflate streams without the zlib wrapper
+                out.clear();
+                DeflateDecoder::new(body).take(limit).read_to_end(&mut out)
+            }),
+    };
+    res.map_err(DeserializeError::Decompress)?;
+    if out.len() > max_len {
+        return Err(DeserializeError::DecompressedTooLarge(max_len));
+    }
+    Ok(Bytes::from(out))
+}
+
+/// Compress the body of `req` with `encoding`.
+///
+/// The `Content-Encoding` header is set to the encoding,
+/// and any `Content-Length` header is removed as it no longer matches the body.
+#[must_use]
+pub fn compress_request(req: Request<BytesMut>, encoding: Encoding) -> Request<BytesMut> {
+    let (mut parts, body) = req.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    parts.headers.insert(
+        CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    Request::from_parts(parts, compress(&body, encoding))
+}
+
+/// Decompress the body of `res` according to its `Content-Encoding` header.
+///
+/// Encodings are removed in the reverse order they were applied, and `identity` is skipped.
+/// Responses without a `Content-Encoding` are returned unchanged.
+/// Otherwise the `Content-Encoding` and `Content-Length` headers are removed,
+/// so they describe the decompressed body.
+///
+/// The body is decompressed to at most [`MAX_DECOMPRESSED_LEN`] bytes.
+///
+/// # Errors
+///
+/// Returns [`DeserializeError::UnsupportedEncoding`] if an encoding isn't `gzip` or `deflate`,
+/// [`DeserializeError::Decompress`] if the body is malformed,
+/// and [`DeserializeError::DecompressedTooLarge`] if it decompresses to more bytes.
+pub fn decompress_response(res: Response<Bytes>) -> Result<Response<Bytes>, DeserializeError> {
+    decompress_response_with_limit(res, MAX_DECOMPRESSED_LEN)
+}
+
+/// Decompress the body of `res` like [`decompress_response`], to at most `max_len` bytes.
+///
+/// # Errors
+///
+/// See [`decompress_response`].
+pub fn decompress_response_with_limit(
+    res: Response<Bytes>,
+    max_len: usize,
+) -> Result<Response<Bytes>, DeserializeError> {
+    if !res.headers().contains_key(CONTENT_ENCODING) {
+        return Ok(res);
+    }
+
+    let mut encodings = Vec::new();
+    for value in res.headers().get_all(CONTENT_ENCODING) {
+        for token in value.to_str()?.split(',').map(str::trim) {
+            if token.is_empty() || token.eq_ignore_ascii_case("identity") {
+                continue;
+            }
+            let encoding = Encoding::from_token(token)
+                .ok_or_else(|| DeserializeError::UnsupportedEncoding(token.to_owned()))?;
+            encodings.push(encoding);
+        }
+    }
+
+    let (mut parts, mut body) = res.into_parts();
+    for encoding in encodings.into_iter().rev() {
+        body = decompress_with_limit(&body, encoding, max_len)?;
+    }
+    parts.headers.remove(CONTENT_ENCODING);
+    parts.headers.remove(CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::AUTHORIZATION;
+
+    use super::*;
+    use crate::{
+        auth::{bearer::BearerAuth, Authenticator},
+        tests::TestRequest,
+        OutgoingRequest,
+    };
+
+    const BODY: &[u8] = br#"{"items":[1,2,3,4,5,6,7,8,9,10],"name":"aaaaaaaaaaaaaaaaaaaaaaaa"}"#;
+
+    fn response(encoding: &str, body: impl Into<Bytes>) -> Response<Bytes> {
+        Response::builder()
+            .header(CONTENT_ENCODING, encoding)
+            .header(CONTENT_LENGTH, 3)
+            .body(body.into())
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for encoding in [Encoding::Gzip, Encoding::Deflate] {
+            let req = Request::builder()
+                .header(CONTENT_LENGTH, BODY.len())
+                .body(BytesMut::from(BODY))
+                .unwrap();
+            let req = compress_request(req, encoding);
+            assert_eq!(req.headers()[CONTENT_ENCODING], encoding.as_str());
+            assert!(!req.headers().contains_key(CONTENT_LENGTH));
+            assert_ne!(req.body().as_ref(), BODY);
+
+            let res =
+                decompress_response(response(encoding.as_str(), req.into_body().freeze())).unwrap();
+            assert_eq!(res.body().as_ref(), BODY);
+            assert!(!res.headers().contains_key(CONTENT_ENCODING));
+            assert!(!res.headers().contains_key(CONTENT_LENGTH));
+        }
+    }
+
+    #[test]
+    fn multiple_encodings() {
+        let body = compress(&compress(BODY, Encoding::Deflate), Encoding::Gzip);
+        let res = decompress_response(response("deflate, identity, GZIP", body.freeze())).unwrap();
+        assert_eq!(res.body().as_ref(), BODY);
+    }
+
+    #[test]
+    fn raw_deflate() {
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(BODY).unwrap();
+        let body = encoder.finish().unwrap();
+        assert_eq!(decompress(&body, Encoding::Deflate).unwrap(), BODY);
+    }
+
+    #[test]
+    fn uncompressed() {
+        let res = Response::builder()
+            .header(CONTENT_LENGTH, BODY.len())
+            .body(Bytes::from_static(BODY))
+            .unwrap();
+        let res = decompress_response(res).unwrap();
+        assert_eq!(res.body().as_ref(), BODY);
+        assert!(res.headers().contains_key(CONTENT_LENGTH));
+    }
+
+    #[test]
+    fn decompression_limit() {
+        // 16 MiB of zeros compress to a few kilobytes
+        let bomb = vec![0; 16 * 1024 * 1024];
+        for encoding in [Encoding::Gzip, Encoding::Deflate] {
+            let body = compress(&bomb, encoding).freeze();
+            assert!(body.len() < 64 * 1024);
+            assert!(matches!(
+                decompress_with_limit(&body, encoding, 1024 * 1024).unwrap_err(),
+                DeserializeError::DecompressedTooLarge(1_048_576)
+            ));
+            assert!(matches!(
+                decompress_response_with_limit(response(encoding.as_str(), body.clone()), 1024)
+                    .unwrap_err(),
+                DeserializeError::DecompressedTooLarge(1024)
+            ));
+            assert_eq!(decompress(&body, encoding).unwrap().len(), bomb.len());
+        }
+
+        // Bodies of exactly the limit are accepted
+        let body = compress(BODY, Encoding::Gzip);
+        assert_eq!(
+            decompress_with_limit(&body, Encoding::Gzip, BODY.len()).unwrap(),
+            BODY
+        );
+        assert!(decompress_with_limit(&body, Encoding::Gzip, BODY.len() - 1).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            decompress_response(response("br", "abc")).unwrap_err(),
+            DeserializeError::UnsupportedEncoding(e) if e == "br"
+        ));
+        assert!(matches!(
+            decompress_response(response("gzip", "abc")).unwrap_err(),
+            DeserializeError::Decompress(_)
+        ));
+    }
+
+    #[test]
+    fn accept_encoding() {
+        let auth = WithAcceptEncoding::new(AcceptEncoding::default(), BearerAuth::new());
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        }
+        .try_into_http_request("https://example.com", auth, &"token".to_owned())
+        .unwrap();
+        assert_eq!(req.headers()[ACCEPT_ENCODING], "gzip, deflate");
+        assert_eq!(req.headers()[AUTHORIZATION], "Bearer token");
+
+        assert_eq!(
+            AcceptEncoding::new(&[Encoding::Deflate]).as_header_value(),
+            "deflate"
+        );
+        assert_eq!(AcceptEncoding::new(&[]).as_header_value(), "identity");
+
+        // As set by an endpoint from its `Metadata::headers`
+        let auth = WithAcceptEncoding::new(AcceptEncoding::default(), ());
+        let mut req = Request::builder()
+            .header(ACCEPT_ENCODING, "identity")
+            .body(BytesMut::new())
+            .unwrap();
+        auth.authenticate(&mut req, &()).unwrap();
+        assert_eq!(req.headers()[ACCEPT_ENCODING], "identity");
+    }
+}
@@ -0,0 +1,304 @@
+//! Conditional requests with entity tags.
+//!
+//! See [RFC 9110 section 13](https://www.rfc-editor.org/rfc/rfc9110#section-13).
+//! Preconditions are set on outgoing requests with
+//! [`WithPrecondition`](crate::client::WithPrecondition).
+
+use std::{fmt, str::FromStr};
+
+use bytes::Bytes;
+use http::{header::ETAG, HeaderMap, HeaderValue, StatusCode};
+
+use crate::{
+    error::{DeserializeError, FromHttpResponseError},
+    Endpoint, IncomingResponse,
+};
+
+/// An entity tag, e.g. `"xyzzy"` or `W/"xyzzy"`.
+///
+/// The tag is kept as sent, including the quotes and weakness indicator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag(String);
+
+impl ETag {
+    /// Creates a strong entity tag from its opaque tag, without quotes.
+    ///
+    /// Returns `None` if `tag` contains characters not allowed in entity tags,
+    /// such as `"` or whitespace.
+    #[must_use]
+    pub fn strong(tag: &str) -> Option<Self> {
+        is_opaque_tag(tag).then(|| Self(format!("\"{tag}\"")))
+    }
+
+    /// Creates a weak entity tag from its opaque tag, without quotes.
+    ///
+    /// Returns `None` if `tag` contains characters not allowed in entity tags.
+    #[must_use]
+    pub fn weak(tag: &str) -> Option<Self> {
+        is_opaque_tag(tag).then(|| Self(format!("W/\"{tag}\"")))
+    }
+
+    /// Returns whether the entity tag is weak.
+    #[must_use]
+    pub fn is_weak(&self) -> bool {
+        self.0.starts_with("W/")
+    }
+
+    /// Returns the opaque tag, without quotes and weakness indicator.
+    #[must_use]
+    pub fn tag(&self) -> &str {
+        let tag = self.0.strip_prefix("W/").unwrap_or(&self.0);
+        &tag[1..tag.len() - 1]
+    }
+
+    /// Returns the entity tag as sent, e.g. `W/"xyzzy"`.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Strong comparison: both entity tags are strong and have the same opaque tag.
+    ///
+    /// This is used for `If-Match` preconditions.
+    #[must_use]
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.is_weak() && !other.is_weak() && self.tag() == other.tag()
+    }
+
+    /// Weak comparison: both entity tags have the same opaque tag, weak or not.
+    ///
+    /// This is used for `If-None-Match` preconditions.
+    #[must_use]
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag() == other.tag()
+    }
+
+    /// Converts the entity tag into a header value.
+    #[must_use]
+    pub fn to_header_value(&self) -> HeaderValue {
+        HeaderValue::from_str(&self.0).expect("entity tags are valid header values")
+    }
+}
+
+impl FromStr for ETag {
+    type Err = DeserializeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tag = s.strip_prefix("W/").unwrap_or(s);
+        let valid = tag
+            .strip_prefix('"')
+            .and_then(|tag| tag.strip_suffix('"'))
+            .is_some_and(is_opaque_tag);
+        if valid {
+            Ok(Self(s.to_owned()))
+        } else {
+            Err(DeserializeError::InvalidETag)
+        }
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Whether `tag` only contains entity tag characters (`etagc` in RFC 9110).
+///
+/// Non-ASCII characters are not accepted, so tags are always valid header values.
+fn is_opaque_tag(tag: &str) -> bool {
+    tag.bytes().all(|b| b == 0x21 || (0x23..=0x7E).contains(&b))
+}
+
+/// Returns the `ETag` header of a response, or `None` if it has none.
+///
+/// An invalid `ETag` header is a [`DeserializeError::InvalidETag`] error.
+pub fn etag(headers: &HeaderMap) -> Result<Option<ETag>, DeserializeError> {
+    headers
+        .get(ETAG)
+        .map(|value| value.to_str()?.parse())
+        .transpose()
+}
+
+/// The entity tags of an `If-Match` or `If-None-Match` precondition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ETagMatch {
+    /// The `*` wildcard, matching any current representation.
+    Any,
+    /// A list of entity tags.
+    Tags(Vec<ETag>),
+}
+
+impl ETagMatch {
+    /// Whether the precondition matches the `current` entity tag of the target resource,
+    /// using strong comparison as for `If-Match`.
+    ///
+    /// `current` is `None` if the resource has no current representation.
+    #[must_use]
+    pub fn matches_strong(&self, current: Option<&ETag>) -> bool {
+        self.matches(current, ETag::strong_eq)
+    }
+
+    /// Whether the precondition matches the `current` entity tag of the target resource,
+    /// using weak comparison as for `If-None-Match`.
+    #[must_use]
+    pub fn matches_weak(&self, current: Option<&ETag>) -> bool {
+        self.matches(current, ETag::weak_eq)
+    }
+
+    fn matches(&self, current: Option<&ETag>, eq: impl Fn(&ETag, &ETag) -> bool) -> bool {
+        current.is_some_and(|current| match self {
+            Self::Any => true,
+            Self::Tags(tags) => tags.iter().any(|tag| eq(tag, current)),
+        })
+    }
+
+    /// Converts the precondition into a header value,
+    /// e.g. `*` or `"xyzzy", W/"r2d2xxxx"`.
+    #[must_use]
+    pub fn to_header_value(&self) -> HeaderValue {
+        match self {
+            Self::Any => HeaderValue::from_static("*"),
+            Self::Tags(tags) => {
+                let tags: Vec<_> = tags.iter().map(ETag::as_str).collect();
+                HeaderValue::from_str(&tags.join(", "))
+                    .expect("entity tags are valid header values")
+            }
+        }
+    }
+}
+
+impl From<ETag> for ETagMatch {
+    fn from(tag: ETag) -> Self {
+        Self::Tags(vec![tag])
+    }
+}
+
+impl FromStr for ETagMatch {
+    type Err = DeserializeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim() == "*" {
+            return Ok(Self::Any);
+        }
+        s.split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Self::Tags)
+    }
+}
+
+/// A response to a conditional request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conditional<T> {
+    /// The precondition passed, and the response was converted.
+    Modified(T),
+    /// A `304 Not Modified` response, with its `ETag` header if it has one.
+    NotModified {
+        /// The entity tag of the current representation.
+        etag: Option<ETag>,
+    },
+}
+
+/// Convert the response to a conditional request.
+///
+/// `304 Not Modified` responses are [`Conditional::NotModified`],
+/// and other responses are converted with
+/// [`IncomingResponse::try_from_http_response_by_status`],
+/// rather than `304` responses being converted as endpoint errors.
+pub fn try_from_conditional_response<R: IncomingResponse>(
+    res: http::Response<Bytes>,
+) -> Result<Conditional<R>, FromHttpResponseError<<R::OutgoingRequest as Endpoint>::Error>> {
+    if res.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Conditional::NotModified {
+            etag: etag(res.headers())?,
+        });
+    }
+    Ok(Conditional::Modified(R::try_from_http_response_by_status(
+        res,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestResponse;
+
+    #[test]
+    fn etags() {
+        let strong: ETag = "\"xyzzy\"".parse().unwrap();
+        let weak: ETag = "W/\"xyzzy\"".parse().unwrap();
+        assert_eq!(ETag::strong("xyzzy").unwrap(), strong);
+        assert_eq!(ETag::weak("xyzzy").unwrap(), weak);
+        assert!(!strong.is_weak());
+        assert!(weak.is_weak());
+        assert_eq!(weak.tag(), "xyzzy");
+        assert_eq!(weak.to_string(), "W/\"xyzzy\"");
+        assert_eq!(weak.to_header_value(), "W/\"xyzzy\"");
+        assert_eq!("\"\"".parse::<ETag>().unwrap().tag(), "");
+
+        assert!(strong.strong_eq(&strong));
+        assert!(!strong.strong_eq(&weak));
+        assert!(!weak.strong_eq(&weak));
+        assert!(strong.weak_eq(&weak));
+        assert!(!strong.weak_eq(&ETag::strong("other").unwrap()));
+
+        for invalid in ["xyzzy", "\"xyzzy", "w/\"xyzzy\"", "\"a b\"", "\"a\"b\""] {
+            assert!(
+                matches!(invalid.parse::<ETag>(), Err(DeserializeError::InvalidETag)),
+                "{invalid}"
+            );
+        }
+        assert_eq!(ETag::strong("a\"b"), None);
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(etag(&headers).unwrap(), None);
+        headers.insert(ETAG, HeaderValue::from_static("W/\"xyzzy\""));
+        assert_eq!(etag(&headers).unwrap(), Some(weak));
+        headers.insert(ETAG, HeaderValue::from_static("xyzzy"));
+        assert!(etag(&headers).is_err());
+    }
+
+    #[test]
+    fn etag_match() {
+        let strong = ETag::strong("a").unwrap();
+        let weak = ETag::weak("a").unwrap();
+
+        assert!(ETagMatch::Any.matches_strong(Some(&weak)));
+        assert!(!ETagMatch::Any.matches_weak(None));
+        assert!(ETagMatch::from(strong.clone()).matches_strong(Some(&strong)));
+        assert!(!ETagMatch::from(strong.clone()).matches_strong(Some(&weak)));
+        assert!(ETagMatch::from(strong.clone()).matches_weak(Some(&weak)));
+
+        let tags: ETagMatch = "\"a\", W/\"b\"".parse().unwrap();
+        assert_eq!(
+            tags,
+            ETagMatch::Tags(vec![strong, ETag::weak("b").unwrap()])
+        );
+        assert_eq!(tags.to_header_value(), "\"a\", W/\"b\"");
+        assert_eq!(" * ".parse::<ETagMatch>().unwrap(), ETagMatch::Any);
+        assert_eq!(ETagMatch::Any.to_header_value(), "*");
+        assert!("\"a\", b".parse::<ETagMatch>().is_err());
+    }
+
+    #[test]
+    fn not_modified() {
+        let res = http::Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(ETAG, "\"v1\"")
+            .body(Bytes::new())
+            .unwrap();
+        assert!(matches!(
+            try_from_conditional_response::<TestResponse>(res).unwrap(),
+            Conditional::NotModified { etag: Some(etag) } if etag.tag() == "v1"
+        ));
+
+        let res = http::Response::new(Bytes::new());
+        assert!(matches!(
+            try_from_conditional_response::<TestResponse>(res).unwrap(),
+            Conditional::Modified(TestResponse)
+        ));
+    }
+}
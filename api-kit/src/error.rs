@@ -1,11 +1,40 @@
+#[cfg(feature = "json")]
+mod api_error;
+mod generic;
+#[cfg(feature = "json")]
+mod problem;
+mod retry_after;
+
+use std::time::Duration;
+
 use displaydoc::Display;
 use thiserror::Error;
 
+#[cfg(feature = "json")]
+pub use self::{api_error::ApiError, problem::ProblemDetails};
+pub use self::{
+    generic::GenericError,
+    retry_after::{parse_retry_after, retry_after},
+};
+use crate::EndpointError;
+
 #[derive(Debug, Display, Error)]
 #[non_exhaustive]
 pub enum IntoHttpError {
     /// Missing authorization.
     MissingAuth,
+    /// Capability `{0}` required by the endpoint is not available
+    CapabilityNotAvailable(String),
+    /// `{method}` endpoints take their arguments in the {expected}
+    ArgumentLocation {
+        /// The method of the endpoint.
+        method: http::Method,
+        /// Where the endpoint takes its arguments, `query` or `body`.
+        expected: &'static str,
+    },
+    /// Missing host in request URI
+    #[cfg(feature = "sigv4-auth")]
+    MissingHost,
     /// JSON serialization error: {0}
     #[cfg(feature = "json")]
     Json(#[from] serde_json::Error),
@@ -19,13 +48,19 @@ pub enum IntoHttpError {
     InvalidUri(#[from] http::uri::InvalidUri),
     /// HTTP construction failed: {0}
     Http(#[from] http::Error),
+    /// reqwest request construction failed: {0}
+    #[cfg(feature = "reqwest")]
+    Reqwest(#[from] reqwest::Error),
+    /// OAuth 2.0 token request failed: {0}
+    #[cfg(feature = "oauth2")]
+    OAuth2(#[from] OAuth2Error),
 }
 
 #[derive(Debug, Display, Error)]
 #[non_exhaustive]
 pub enum FromHttpRequestError {
     /// Deserialization error: {0}
-    Deserialize(DeserializeError),
+    Deserialize(#[source] DeserializeError),
     /// Method mismatch.
     MethodMismatch {
         /// Expected method.
@@ -33,6 +68,40 @@ pub enum FromHttpRequestError {
         /// Actual received method.
         actual: http::Method,
     },
+    /// Path does not match the endpoint path.
+    PathMismatch,
+    /// Request signature is invalid.
+    InvalidSignature,
+    /// Request timestamp is invalid.
+    InvalidTimestamp,
+    /// Request timestamp is outside the tolerance window.
+    Expired,
+    /// Request has already been received.
+    Replayed,
+}
+
+/// The maximum length of bodies kept in [`DeserializeError::JsonWithBody`] errors.
+#[cfg(feature = "json")]
+pub const MAX_ERROR_BODY_LEN: usize = 1024;
+
+#[cfg(feature = "json")]
+impl DeserializeError {
+    /// Creates a [`DeserializeError::JsonWithBody`] error for a `body` that failed to parse,
+    /// keeping at most [`MAX_ERROR_BODY_LEN`] bytes of it.
+    #[must_use]
+    pub fn json_with_body(source: serde_json::Error, body: &[u8]) -> Self {
+        Self::json_with_body_limit(source, body, MAX_ERROR_BODY_LEN)
+    }
+
+    /// Creates a [`DeserializeError::JsonWithBody`] error for a `body` that failed to parse,
+    /// keeping at most `max_len` bytes of it.
+    #[must_use]
+    pub fn json_with_body_limit(source: serde_json::Error, body: &[u8], max_len: usize) -> Self {
+        Self::JsonWithBody {
+            source,
+            body: bytes::Bytes::copy_from_slice(&body[..body.len().min(max_len)]),
+        }
+    }
 }
 
 impl<T> From<T> for FromHttpRequestError
@@ -48,9 +117,47 @@ where
 #[non_exhaustive]
 pub enum FromHttpResponseError<E> {
     #[error("deserialize error: {0}")]
-    Deserialize(DeserializeError),
+    Deserialize(#[source] DeserializeError),
     #[error("endpoint error: {0}")]
     EndpointError(E),
+    /// The endpoint returned a `429 Too Many Requests` error.
+    #[error("rate limited: {error}")]
+    RateLimited {
+        /// The endpoint error.
+        error: E,
+        /// How long to wait before retrying, from the `Retry-After` header.
+        retry_after: Option<Duration>,
+    },
+}
+
+impl<E: EndpointError> FromHttpResponseError<E> {
+    /// Convert an error response using [`EndpointError::try_from_http_response`].
+    ///
+    /// Returns [`FromHttpResponseError::EndpointError`] if the endpoint error was understood,
+    /// or [`FromHttpResponseError::Deserialize`] if the error body couldn't be parsed.
+    /// Understood `429 Too Many Requests` errors are returned as
+    /// [`FromHttpResponseError::RateLimited`], with the parsed [`retry_after`] duration.
+    pub fn from_error_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+        let rate_limited = (response.status() == http::StatusCode::TOO_MANY_REQUESTS)
+            .then(|| retry_after(&response));
+        match (E::try_from_http_response(response), rate_limited) {
+            (Ok(error), Some(retry_after)) => Self::RateLimited { error, retry_after },
+            (Ok(err), None) => Self::EndpointError(err),
+            (Err(err), _) => Self::Deserialize(err),
+        }
+    }
+
+    /// Returns how long to wait before retrying a rate limited request.
+    ///
+    /// Returns `None` unless this is a [`FromHttpResponseError::RateLimited`] error
+    /// whose response had a valid `Retry-After` header.
+    #[must_use]
+    pub const fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
 }
 
 impl<T, E> From<T> for FromHttpResponseError<E>
@@ -65,18 +172,69 @@ where
 #[derive(Debug, Display, Error)]
 #[non_exhaustive]
 pub enum DeserializeError {
-    /// Error parsing JSON
+    /// Error parsing JSON: {0}
     #[cfg(feature = "json")]
     Json(#[from] serde_json::Error),
+    /// Error parsing JSON: {source}, body: {body:?}
+    #[cfg(feature = "json")]
+    JsonWithBody {
+        /// The JSON error.
+        source: serde_json::Error,
+        /// The start of the body that failed to parse,
+        /// truncated to at most [`MAX_ERROR_BODY_LEN`] bytes.
+        body: bytes::Bytes,
+    },
     /// Error parsing query string: {0}
     Uri(#[from] serde_urlencoded::de::Error),
     /// Error converting header to string
     Header(#[from] http::header::ToStrError),
     /// Missing header: {0}
     MissingHeader(http::HeaderName),
+    /// Truncated body: expected {expected} bytes, got {actual}
+    TruncatedBody {
+        /// Length declared by the `Content-Length` header.
+        expected: usize,
+        /// Actual length of the body.
+        actual: usize,
+    },
+    /// Unexpected status: expected {expected}, got {actual}
+    UnexpectedStatus {
+        /// Status declared by the endpoint metadata.
+        expected: http::StatusCode,
+        /// Actual status of the response.
+        actual: http::StatusCode,
+    },
+    /// Unexpected content type: expected {expected}, got {actual:?}
+    UnexpectedContentType {
+        /// Media type expected by the response.
+        expected: &'static str,
+        /// Actual `Content-Type` header of the response.
+        actual: Option<http::HeaderValue>,
+    },
+    /// Invalid multipart body
+    InvalidMultipart,
+    /// Malformed `Link` header
+    InvalidLinkHeader,
+    /// Invalid entity tag
+    InvalidETag,
+    /// Missing batch response part: {0}
+    MissingBatchPart(usize),
+    /// Unsupported content encoding: {0}
+    #[cfg(feature = "compression")]
+    UnsupportedEncoding(String),
+    /// Error decompressing body: {0}
+    #[cfg(feature = "compression")]
+    Decompress(#[source] std::io::Error),
+    /// Decompressed body exceeds the limit of {0} bytes
+    #[cfg(feature = "compression")]
+    DecompressedTooLarge(usize),
+    /// Error reading body: {0}
+    #[cfg(feature = "stream")]
+    Body(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
 
 #[derive(Debug, Display, PartialEq, Eq, Error)]
+#[non_exhaustive]
 pub enum UrlError {
     /// Generic error message: {0}
     Message(String),
@@ -84,12 +242,142 @@ pub enum UrlError {
     TopLevel,
     /// Invalid endpoint
     InvalidEndpoint,
-    /// Value not supported
-    ValueNotSupported,
-    /// Key not found: {0}
-    KeyNotFound(&'static str),
+    /// {kind} values are not supported in path position for field `{field}`
+    ValueNotSupported {
+        /// The path field with the unsupported value,
+        /// or empty if the top-level value itself is not a struct.
+        ///
+        /// `ValueNotSupported` was a unit variant before it carried the field
+        /// and kind, so patterns matching it must now be `ValueNotSupported { .. }`.
+        field: &'static str,
+        /// The kind of serde value that was rejected, e.g. `sequence`.
+        kind: &'static str,
+    },
+    /// No path placeholder for field `{key}`, unfilled placeholders: {placeholders:?}
+    KeyNotFound {
+        /// The serialized field without a placeholder,
+        /// which is ignored unless a placeholder is left unfilled.
+        ///
+        /// `KeyNotFound` was a tuple variant holding only the key,
+        /// so patterns matching it must now be `KeyNotFound { key, .. }`.
+        key: &'static str,
+        /// The placeholders of the path that were not filled yet.
+        placeholders: Vec<String>,
+    },
+    /// Path field `{0}` has no placeholder
+    UnusedField(&'static str),
     /// Unfilled field: {0}
     UnfilledField(String),
+    /// Unfilled fields: {0:?}
+    UnfilledFields(Vec<String>),
+    /// Path field `{0}` is a `.` or `..` segment
+    DotSegment(&'static str),
+    /// No path segment {0} for matrix parameters
+    MatrixSegmentNotFound(usize),
+}
+
+#[cfg(feature = "oauth2")]
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum OAuth2Error {
+    /// Error sending token request: {0}
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// Token endpoint returned {status}
+    Status {
+        /// Status code of the token response.
+        status: http::StatusCode,
+        /// The `error` code of the token response, if any.
+        error: Option<String>,
+    },
+    /// Invalid token response: {0}
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(any(feature = "reqwest", feature = "blocking"))]
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum SendError<E> {
+    /// Error building request: {0}
+    IntoHttp(#[from] IntoHttpError),
+    /// Error sending request: {0}
+    #[cfg(feature = "reqwest")]
+    Reqwest(#[from] reqwest::Error),
+    /// Error sending request: {0}
+    #[cfg(feature = "blocking")]
+    Ureq(#[from] ureq::Error),
+    /// Error converting response: {0}
+    Response(#[from] FromHttpResponseError<E>),
+}
+
+#[cfg(feature = "codegen")]
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum SchemaError {
+    /// Schema at `{0}` is not an object schema
+    NotAnObject(String),
+    /// Unsupported type {ty} of schema at `{path}`
+    UnsupportedType {
+        /// JSON pointer of the schema.
+        path: String,
+        /// The unsupported type.
+        ty: String,
+    },
+    /// Property at `{0}` has the same field name as another property
+    DuplicateField(String),
+}
+
+#[cfg(feature = "har")]
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum HarError {
+    /// Error parsing HAR entry: {0}
+    Json(#[from] serde_json::Error),
+    /// Error decoding base64 body: {0}
+    Base64(#[from] base64::DecodeError),
+    /// Unsupported body encoding: {0}
+    UnsupportedEncoding(String),
+    /// Invalid request or response: {0}
+    Http(#[from] http::Error),
+    /// Error reading HAR file: {0}
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(feature = "har")]
+#[derive(Debug, Display, Error)]
+#[non_exhaustive]
+pub enum ConformanceError {
+    /// No recorded interaction named `{0}`
+    MissingInteraction(String),
+    /// Error building request: {0}
+    IntoHttp(#[from] IntoHttpError),
+    /// Request {part} differs from the recording: expected {expected}, got {actual}
+    RequestMismatch {
+        /// The differing part of the request, e.g. `uri` or `header`.
+        part: &'static str,
+        /// The recorded value.
+        expected: String,
+        /// The value of the built request.
+        actual: String,
+    },
+    /// Error converting recorded response: {0}
+    Deserialize(#[source] DeserializeError),
+}
+
+impl UrlError {
+    /// Returns the unfilled placeholders of an [`UrlError::UnfilledField`]
+    /// or [`UrlError::UnfilledFields`] error, or an empty slice for other errors.
+    ///
+    /// A single unfilled placeholder is an `UnfilledField` error,
+    /// and several are an `UnfilledFields` error,
+    /// listing them in order of appearance in the path.
+    #[must_use]
+    pub fn unfilled_fields(&self) -> &[String] {
+        match self {
+            Self::UnfilledField(field) => std::slice::from_ref(field),
+            Self::UnfilledFields(fields) => fields,
+            _ => &[],
+        }
+    }
 }
 
 impl serde::ser::Error for UrlError {
@@ -97,3 +385,64 @@ impl serde::ser::Error for UrlError {
         Self::Message(msg.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_source_chain() {
+        let json = serde_json::from_str::<u32>("[").unwrap_err();
+        let message = json.to_string();
+
+        let err = FromHttpResponseError::<ProblemDetails>::from(json);
+        let source = err.source().expect("deserialize error source");
+        assert!(matches!(
+            source.downcast_ref::<DeserializeError>(),
+            Some(DeserializeError::Json(_))
+        ));
+        let root = source.source().expect("json error source");
+        assert_eq!(
+            root.downcast_ref::<serde_json::Error>()
+                .map(ToString::to_string),
+            Some(message.clone())
+        );
+        assert_eq!(
+            err.to_string(),
+            format!("deserialize error: Error parsing JSON: {message}")
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_with_body() {
+        let body = format!("<html>{}</html>", "a".repeat(2000));
+        let json = serde_json::from_str::<u32>(&body).unwrap_err();
+        let err = DeserializeError::json_with_body(json, body.as_bytes());
+        let DeserializeError::JsonWithBody { body: kept, .. } = &err else {
+            panic!("expected JsonWithBody, got {err:?}");
+        };
+        assert_eq!(kept.len(), MAX_ERROR_BODY_LEN);
+        assert!(kept.starts_with(b"<html>aaa"));
+        assert!(err
+            .source()
+            .is_some_and(<dyn std::error::Error>::is::<serde_json::Error>));
+
+        let json = serde_json::from_str::<u32>("oops").unwrap_err();
+        let err = DeserializeError::json_with_body_limit(json, b"oops", 2);
+        assert!(err.to_string().ends_with(r#"body: b"oo""#));
+    }
+
+    #[test]
+    fn request_source_chain() {
+        let err = FromHttpRequestError::from(DeserializeError::InvalidMultipart);
+        assert!(err
+            .source()
+            .and_then(|e| e.downcast_ref::<DeserializeError>())
+            .is_some());
+        assert!(FromHttpRequestError::PathMismatch.source().is_none());
+    }
+}
@@ -0,0 +1,125 @@
+//! A generic endpoint error.
+
+use std::fmt;
+
+use bytes::BufMut;
+use http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
+use serde_json::Value;
+
+use crate::{
+    error::IntoHttpError,
+    response::{classify, StatusClass},
+    EndpointError,
+};
+
+/// A generic [`EndpointError`] holding the status and body of an error response.
+///
+/// Endpoints of APIs without a structured error format can use `type Error = ApiError;`
+/// instead of defining their own error type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The body of the response.
+    ///
+    /// Bodies that aren't valid JSON are kept as a string,
+    /// and empty bodies are [`Value::Null`].
+    pub body: Value,
+}
+
+impl ApiError {
+    /// Returns the class of the response status.
+    #[inline]
+    #[must_use]
+    pub const fn class(&self) -> StatusClass {
+        classify(self.status)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.body {
+            Value::Null => write!(f, "{}", self.status),
+            Value::String(body) => write!(f, "{}: {body}", self.status),
+            body => write!(f, "{}: {body}", self.status),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl EndpointError for ApiError {
+    fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+    where
+        B: Default + BufMut,
+    {
+        let mut body = B::default().writer();
+        if !self.body.is_null() {
+            serde_json::to_writer(&mut body, &self.body)?;
+        }
+
+        Ok(http::Response::builder()
+            .status(self.status)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(body.into_inner())?)
+    }
+
+    fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+        let body = response.body().as_ref();
+        let body = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(body)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()))
+        };
+        Self {
+            status: response.status(),
+            body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use serde_json::json;
+
+    use super::*;
+
+    fn response(status: u16, body: &'static str) -> http::Response<&'static str> {
+        http::Response::builder().status(status).body(body).unwrap()
+    }
+
+    #[test]
+    fn from_response() {
+        let err = ApiError::from_http_response(response(422, r#"{"message":"invalid"}"#));
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.body, json!({"message": "invalid"}));
+        assert_eq!(err.class(), StatusClass::ClientError);
+        assert_eq!(
+            err.to_string(),
+            r#"422 Unprocessable Entity: {"message":"invalid"}"#
+        );
+
+        let err = ApiError::from_http_response(response(502, "<html>Bad Gateway</html>"));
+        assert_eq!(err.body, "<html>Bad Gateway</html>");
+        assert_eq!(err.class(), StatusClass::ServerError);
+        assert_eq!(err.to_string(), "502 Bad Gateway: <html>Bad Gateway</html>");
+
+        let err = ApiError::from_http_response(response(404, ""));
+        assert_eq!(err.body, Value::Null);
+        assert_eq!(err.to_string(), "404 Not Found");
+    }
+
+    #[test]
+    fn into_response() {
+        let err = ApiError {
+            status: StatusCode::CONFLICT,
+            body: json!({"code": 7}),
+        };
+        let res: http::Response<BytesMut> = err.clone().try_into_http_response().unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(&res.body()[..], br#"{"code":7}"#);
+        assert_eq!(ApiError::from_http_response(res), err);
+    }
+}
@@ -0,0 +1,104 @@
+//! A raw endpoint error.
+
+use std::fmt;
+
+use bytes::{BufMut, Bytes};
+use http::StatusCode;
+
+use crate::{
+    error::IntoHttpError,
+    response::{classify, StatusClass},
+    EndpointError,
+};
+
+/// An [`EndpointError`] holding the status and raw body of an error response.
+///
+/// Endpoints without a structured error body can use `type Error = GenericError;`
+/// instead of defining their own error type.
+/// Unlike `ApiError`, the body is kept as sent,
+/// so this doesn't need the `json` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericError {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The body of the response.
+    pub body: Bytes,
+}
+
+impl GenericError {
+    /// Returns the class of the response status.
+    #[inline]
+    #[must_use]
+    pub const fn class(&self) -> StatusClass {
+        classify(self.status)
+    }
+}
+
+impl fmt::Display for GenericError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.body.is_empty() {
+            write!(f, "{}", self.status)
+        } else {
+            write!(
+                f,
+                "{}: {}",
+                self.status,
+                String::from_utf8_lossy(&self.body)
+            )
+        }
+    }
+}
+
+impl std::error::Error for GenericError {}
+
+impl EndpointError for GenericError {
+    fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+    where
+        B: Default + BufMut,
+    {
+        let mut body = B::default();
+        body.put(self.body);
+        Ok(http::Response::builder().status(self.status).body(body)?)
+    }
+
+    fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+        Self {
+            status: response.status(),
+            body: Bytes::copy_from_slice(response.body().as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let err = GenericError::from_http_response(
+            http::Response::builder()
+                .status(500)
+                .body("database unavailable")
+                .unwrap(),
+        );
+        assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(err.body, "database unavailable");
+        assert_eq!(err.class(), StatusClass::ServerError);
+        assert_eq!(
+            err.to_string(),
+            "500 Internal Server Error: database unavailable"
+        );
+
+        let res: http::Response<BytesMut> = err.clone().try_into_http_response().unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(&res.body()[..], b"database unavailable");
+        assert_eq!(GenericError::from_http_response(res), err);
+
+        let err = GenericError::from_http_response(
+            http::Response::builder().status(404).body("").unwrap(),
+        );
+        assert_eq!(err.to_string(), "404 Not Found");
+    }
+}
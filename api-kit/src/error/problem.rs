@@ -0,0 +1,182 @@
+//! RFC 9457 problem details.
+
+use std::fmt;
+
+use bytes::BufMut;
+use http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{DeserializeError, IntoHttpError},
+    EndpointError,
+};
+
+/// The media type of problem details.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem details for HTTP APIs.
+///
+/// An [`EndpointError`] for APIs returning `application/problem+json` error responses,
+/// as described in [RFC 9457](https://datatracker.ietf.org/doc/html/rfc9457)
+/// (formerly RFC 7807).
+/// Endpoints targeting such APIs can use `type Error = ProblemDetails;`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type.
+    ///
+    /// `None` is equivalent to `about:blank`.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub problem_type: Option<String>,
+    /// A short, human-readable summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The HTTP status code of the response.
+    ///
+    /// When converting from a response without a `status` member,
+    /// this is the status code of the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// A human-readable explanation specific to this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference identifying this occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension members of the problem.
+    #[serde(flatten)]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProblemDetails {
+    /// Returns the status code of the problem, if it is valid.
+    #[must_use]
+    pub fn status_code(&self) -> Option<StatusCode> {
+        self.status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+    }
+}
+
+impl fmt::Display for ProblemDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let title = self
+            .title
+            .as_deref()
+            .or_else(|| self.status_code().and_then(|s| s.canonical_reason()))
+            .unwrap_or("Unknown problem");
+        match &self.detail {
+            Some(detail) => write!(f, "{title}: {detail}"),
+            None => f.write_str(title),
+        }
+    }
+}
+
+impl std::error::Error for ProblemDetails {}
+
+impl EndpointError for ProblemDetails {
+    fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+    where
+        B: Default + BufMut,
+    {
+        let status = self
+            .status_code()
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        let mut body = B::default().writer();
+        serde_json::to_writer(&mut body, &self)?;
+
+        Ok(http::Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON))
+            .body(body.into_inner())?)
+    }
+
+    /// Converts the response, falling back to a problem with only the response status
+    /// if the body isn't valid problem details.
+    fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+        let status = response.status();
+        Self::try_from_http_response(response).unwrap_or_else(|_| Self {
+            status: Some(status.as_u16()),
+            ..Self::default()
+        })
+    }
+
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, DeserializeError> {
+        let mut problem: Self = serde_json::from_slice(response.body().as_ref())?;
+        problem
+            .status
+            .get_or_insert_with(|| response.status().as_u16());
+        Ok(problem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn from_response() {
+        let res = http::Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header(CONTENT_TYPE, PROBLEM_JSON)
+            .body(
+                json!({
+                    "type": "https://example.com/probs/out-of-credit",
+                    "title": "You do not have enough credit.",
+                    "detail": "Your current balance is 30, but that costs 50.",
+                    "instance": "/account/12345/msgs/abc",
+                    "balance": 30,
+                })
+                .to_string(),
+            )
+            .unwrap();
+
+        let problem = ProblemDetails::try_from_http_response(res).unwrap();
+        assert_eq!(
+            problem.problem_type.as_deref(),
+            Some("https://example.com/probs/out-of-credit")
+        );
+        assert_eq!(problem.status_code(), Some(StatusCode::FORBIDDEN));
+        assert_eq!(problem.instance.as_deref(), Some("/account/12345/msgs/abc"));
+        assert_eq!(problem.extensions["balance"], 30);
+        assert_eq!(
+            problem.to_string(),
+            "You do not have enough credit.: Your current balance is 30, but that costs 50."
+        );
+    }
+
+    #[test]
+    fn from_invalid_response() {
+        let res = http::Response::builder()
+            .status(StatusCode::BAD_GATEWAY)
+            .body("<html></html>")
+            .unwrap();
+        assert!(matches!(
+            ProblemDetails::try_from_http_response(res.clone()),
+            Err(DeserializeError::Json(_))
+        ));
+
+        let problem = ProblemDetails::from_http_response(res);
+        assert_eq!(problem.status, Some(502));
+        assert_eq!(problem.to_string(), "Bad Gateway");
+    }
+
+    #[test]
+    fn into_response() {
+        let problem = ProblemDetails {
+            title: Some("Not found".to_owned()),
+            status: Some(404),
+            ..ProblemDetails::default()
+        };
+        let res: http::Response<BytesMut> = problem.clone().try_into_http_response().unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()[CONTENT_TYPE], PROBLEM_JSON);
+        assert_eq!(&res.body()[..], br#"{"title":"Not found","status":404}"#);
+
+        assert_eq!(ProblemDetails::from_http_response(res), problem);
+    }
+}
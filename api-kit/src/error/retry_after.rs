@@ -0,0 +1,157 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::{header::RETRY_AFTER, HeaderValue};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Returns how long to wait before retrying, from the `Retry-After` header of `res`.
+///
+/// Returns `None` if the header is absent or malformed.
+/// See [`parse_retry_after`].
+#[must_use]
+pub fn retry_after<T>(res: &http::Response<T>) -> Option<Duration> {
+    parse_retry_after(res.headers().get(RETRY_AFTER)?, SystemTime::now())
+}
+
+/// Parse a `Retry-After` header value into the time to wait after `now`.
+///
+/// Both the delay in seconds (`120`) and the HTTP-date (`Sun, 06 Nov 1994 08:49:37 GMT`)
+/// forms are accepted, see [RFC 9110 section 10.2.3].
+/// Dates in the past result in a zero duration.
+///
+/// Returns `None` if the value is malformed.
+///
+/// [RFC 9110 section 10.2.3]: https://www.rfc-editor.org/rfc/rfc9110#section-10.2.3
+#[must_use]
+pub fn parse_retry_after(value: &HeaderValue, now: SystemTime) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse().ok().map(Duration::from_secs);
+    }
+
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Parse an IMF-fixdate, the RFC 1123 format of HTTP dates.
+fn parse_http_date(s: &str) -> Option<SystemTime> {
+    let mut parts = s.split(' ');
+    let (Some(weekday), Some(day), Some(month), Some(year), Some(time), Some("GMT"), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return None;
+    };
+    if weekday.len() != 4 || !weekday.ends_with(',') || day.len() != 2 || year.len() != 4 {
+        return None;
+    }
+
+    let day: u64 = parse_digits(day)?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let year: u64 = parse_digits(year)?;
+    let mut time = time
+        .split(':')
+        .map(|t| if t.len() == 2 { parse_digits(t) } else { None });
+    let (Some(Some(hour)), Some(Some(minute)), Some(Some(second)), None) =
+        (time.next(), time.next(), time.next(), time.next())
+    else {
+        return None;
+    };
+    if !(1..=31).contains(&day) || year < 1970 || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // Convert the civil date into days since the epoch,
+    // see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let y = year - u64::from(month <= 2);
+    let era = y / 400;
+    let yoe = y % 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+fn parse_digits(s: &str) -> Option<u64> {
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &'static str, now: u64) -> Option<Duration> {
+        parse_retry_after(
+            &HeaderValue::from_static(value),
+            UNIX_EPOCH + Duration::from_secs(now),
+        )
+    }
+
+    #[test]
+    fn seconds() {
+        assert_eq!(parse("120", 0), Some(Duration::from_mins(2)));
+        assert_eq!(parse(" 0 ", 0), Some(Duration::ZERO));
+        assert_eq!(parse("-1", 0), None);
+        assert_eq!(parse("1.5", 0), None);
+        assert_eq!(parse("", 0), None);
+    }
+
+    #[test]
+    fn http_date() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let date = 784_111_777;
+        assert_eq!(
+            parse("Sun, 06 Nov 1994 08:49:37 GMT", date - 30),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse("Sun, 06 Nov 1994 08:49:37 GMT", date + 30),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            parse("Thu, 29 Feb 2024 00:00:00 GMT", 1_709_164_800 - 1),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            parse("Thu, 01 Jan 1970 00:01:00 GMT", 0),
+            Some(Duration::from_mins(1))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        // RFC 850 and asctime dates are obsolete
+        assert_eq!(parse("Sunday, 06-Nov-94 08:49:37 GMT", 0), None);
+        assert_eq!(parse("Sun Nov  6 08:49:37 1994", 0), None);
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 UTC", 0), None);
+        assert_eq!(parse("Sun, 6 Nov 1994 08:49:37 GMT", 0), None);
+        assert_eq!(parse("Sun, 06 Foo 1994 08:49:37 GMT", 0), None);
+        assert_eq!(parse("Sun, 06 Nov 1994 24:49:37 GMT", 0), None);
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49 GMT", 0), None);
+        assert_eq!(parse("soon", 0), None);
+    }
+
+    #[test]
+    fn response_header() {
+        let res = http::Response::builder()
+            .header(RETRY_AFTER, "5")
+            .body(())
+            .unwrap();
+        assert_eq!(retry_after(&res), Some(Duration::from_secs(5)));
+        assert_eq!(retry_after(&http::Response::new(())), None);
+    }
+}
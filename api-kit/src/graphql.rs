@@ -0,0 +1,399 @@
+//! GraphQL over HTTP.
+//!
+//! GraphQL endpoints are `POST` requests to a single path with a JSON body
+//! containing the query, its variables, and an optional operation name.
+//! [`GraphQlRequest`] builds such requests, and [`GraphQlResponse`] parses the
+//! `{data, errors}` response body, routing GraphQL errors to [`GraphQlErrors`],
+//! an [`EndpointError`].
+//!
+//! See the [GraphQL over HTTP](https://graphql.github.io/graphql-over-http/draft/) specification.
+
+use std::fmt;
+
+use bytes::{BufMut, BytesMut};
+use http::{
+    header::{ACCEPT, CONTENT_TYPE},
+    HeaderValue, Method, StatusCode, Uri,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    auth::Authenticator,
+    error::{DeserializeError, FromHttpResponseError, IntoHttpError},
+    EndpointError,
+};
+
+/// The media types accepted for GraphQL responses, in order of preference.
+const ACCEPT_GRAPHQL: &str = "application/graphql-response+json, application/json;q=0.9";
+
+/// A GraphQL request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlRequest<V = serde_json::Value> {
+    /// The GraphQL document.
+    pub query: String,
+    /// The variables of the operation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<V>,
+    /// The name of the operation to execute, if the document contains several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+impl GraphQlRequest {
+    /// Creates a request for `query`, without variables.
+    #[must_use]
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            variables: None,
+            operation_name: None,
+        }
+    }
+}
+
+impl<V: Serialize> GraphQlRequest<V> {
+    /// Sets the variables of the operation.
+    #[must_use]
+    pub fn variables<W: Serialize>(self, variables: W) -> GraphQlRequest<W> {
+        GraphQlRequest {
+            query: self.query,
+            variables: Some(variables),
+            operation_name: self.operation_name,
+        }
+    }
+
+    /// Sets the name of the operation to execute.
+    #[must_use]
+    pub fn operation_name(mut self, name: impl Into<String>) -> Self {
+        self.operation_name = Some(name.into());
+        self
+    }
+
+    /// Convert into an HTTP `POST` request to `uri`, authenticated with `auth`.
+    ///
+    /// This is meant to be called from [`OutgoingRequest::try_into_http_request`],
+    /// with the URI built from the endpoint [`Metadata`].
+    ///
+    /// [`OutgoingRequest::try_into_http_request`]: crate::OutgoingRequest::try_into_http_request
+    /// [`Metadata`]: crate::metadata::Metadata
+    #[allow(clippy::needless_pass_by_value)] // Matches `OutgoingRequest::try_into_http_request`
+    pub fn try_into_http_request<A: Authenticator>(
+        self,
+        uri: Uri,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<http::Request<BytesMut>, IntoHttpError> {
+        let mut body = BytesMut::new().writer();
+        serde_json::to_writer(&mut body, &self)?;
+
+        let mut req = http::Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .header(ACCEPT, HeaderValue::from_static(ACCEPT_GRAPHQL))
+            .body(body.into_inner())?;
+        auth.authenticate(&mut req, auth_data)?;
+        Ok(req)
+    }
+}
+
+/// A GraphQL response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphQlResponse<T> {
+    /// The result of the operation, which may be partial if there are errors.
+    pub data: Option<T>,
+    /// The errors raised during the operation.
+    pub errors: Vec<GraphQlError>,
+    /// Extension members of the response.
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct RawResponse {
+    #[serde(default)]
+    data: serde_json::Value,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+    #[serde(default)]
+    extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl<T: DeserializeOwned> GraphQlResponse<T> {
+    /// Parses the body of a GraphQL response.
+    ///
+    /// If the response has errors, partial data that doesn't deserialize as `T` is discarded.
+    pub fn from_http_response<B: AsRef<[u8]>>(
+        res: &http::Response<B>,
+    ) -> Result<Self, DeserializeError> {
+        let raw: RawResponse = serde_json::from_slice(res.body().as_ref())?;
+        let data = match raw.data {
+            serde_json::Value::Null => None,
+            data => match T::deserialize(data) {
+                Ok(data) => Some(data),
+                Err(_) if !raw.errors.is_empty() => None,
+                Err(err) => return Err(err.into()),
+            },
+        };
+        Ok(Self {
+            data,
+            errors: raw.errors,
+            extensions: raw.extensions,
+        })
+    }
+
+    /// Returns the data of the response, or its errors.
+    ///
+    /// Responses with errors are an error, even if they contain partial data.
+    /// Use the fields of the response directly to handle partial data.
+    pub fn into_result(self) -> Result<T, FromHttpResponseError<GraphQlErrors>> {
+        if !self.errors.is_empty() {
+            return Err(FromHttpResponseError::EndpointError(GraphQlErrors(
+                self.errors,
+            )));
+        }
+        self.data.ok_or_else(|| {
+            FromHttpResponseError::Deserialize(DeserializeError::Json(
+                serde::de::Error::missing_field("data"),
+            ))
+        })
+    }
+
+    /// Converts an HTTP response into the data of the operation.
+    ///
+    /// Non-success responses are converted with [`FromHttpResponseError::from_error_response`],
+    /// and successful responses with GraphQL errors return those errors.
+    /// This is meant to be called from [`IncomingResponse::try_from_http_response`].
+    ///
+    /// [`IncomingResponse::try_from_http_response`]: crate::IncomingResponse::try_from_http_response
+    pub fn try_from_http_response<B: AsRef<[u8]>>(
+        res: http::Response<B>,
+    ) -> Result<T, FromHttpResponseError<GraphQlErrors>> {
+        if !res.status().is_success() {
+            return Err(FromHttpResponseError::from_error_response(res));
+        }
+        Self::from_http_response(&res)?.into_result()
+    }
+}
+
+/// An error raised during a GraphQL operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphQlError {
+    /// A description of the error.
+    pub message: String,
+    /// The locations in the document the error refers to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<GraphQlLocation>,
+    /// The path of the response field the error occurred in.
+    ///
+    /// Segments are field names (strings) or list indices (integers).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<serde_json::Value>,
+    /// Extension members of the error.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A location in a GraphQL document.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GraphQlLocation {
+    /// The line, starting at 1.
+    pub line: u32,
+    /// The column, starting at 1.
+    pub column: u32,
+}
+
+/// The errors of a GraphQL response, as an [`EndpointError`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GraphQlErrors(pub Vec<GraphQlError>);
+
+#[derive(Serialize, Deserialize)]
+struct ErrorsBody {
+    errors: Vec<GraphQlError>,
+}
+
+impl fmt::Display for GraphQlErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            f.write_str(&err.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GraphQlErrors {}
+
+impl EndpointError for GraphQlErrors {
+    fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+    where
+        B: Default + BufMut,
+    {
+        let mut body = B::default().writer();
+        serde_json::to_writer(&mut body, &ErrorsBody { errors: self.0 })?;
+
+        Ok(http::Response::builder()
+            .status(StatusCode::OK)
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/graphql-response+json"),
+            )
+            .body(body.into_inner())?)
+    }
+
+    /// Converts the response, falling back to a single error with the response status
+    /// if the body doesn't contain GraphQL errors.
+    fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+        let status = response.status();
+        Self::try_from_http_response(response).unwrap_or_else(|_| {
+            Self(vec![GraphQlError {
+                message: status.to_string(),
+                locations: Vec::new(),
+                path: Vec::new(),
+                extensions: serde_json::Map::new(),
+            }])
+        })
+    }
+
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, DeserializeError> {
+        let body: ErrorsBody = serde_json::from_slice(response.body().as_ref())?;
+        Ok(Self(body.errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::auth::bearer::BearerAuth;
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Hero {
+        name: String,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct HeroData {
+        hero: Hero,
+    }
+
+    fn response(status: StatusCode, body: &serde_json::Value) -> http::Response<Bytes> {
+        http::Response::builder()
+            .status(status)
+            .body(Bytes::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn request() {
+        let req = GraphQlRequest::new(
+            "query Hero($episode: Episode) { hero(episode: $episode) { name } }",
+        )
+        .variables(json!({ "episode": "JEDI" }))
+        .operation_name("Hero")
+        .try_into_http_request(
+            Uri::from_static("https://example.com/graphql"),
+            BearerAuth::new(),
+            &"token".to_owned(),
+        )
+        .unwrap();
+
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(req.headers()[ACCEPT], ACCEPT_GRAPHQL);
+        assert_eq!(req.headers()["authorization"], "Bearer token");
+        let body: serde_json::Value = serde_json::from_slice(req.body()).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "query": "query Hero($episode: Episode) { hero(episode: $episode) { name } }",
+                "variables": { "episode": "JEDI" },
+                "operationName": "Hero",
+            })
+        );
+
+        let req = GraphQlRequest::new("{ hero { name } }")
+            .try_into_http_request(Uri::from_static("https://example.com/graphql"), (), &())
+            .unwrap();
+        assert_eq!(&req.body()[..], br#"{"query":"{ hero { name } }"}"#);
+    }
+
+    #[test]
+    fn response_data() {
+        let res = response(
+            StatusCode::OK,
+            &json!({ "data": { "hero": { "name": "R2-D2" } } }),
+        );
+        assert_eq!(
+            GraphQlResponse::<HeroData>::try_from_http_response(res).unwrap(),
+            HeroData {
+                hero: Hero {
+                    name: "R2-D2".to_owned()
+                }
+            }
+        );
+
+        let res = response(StatusCode::OK, &json!({ "data": null }));
+        assert!(matches!(
+            GraphQlResponse::<HeroData>::try_from_http_response(res),
+            Err(FromHttpResponseError::Deserialize(DeserializeError::Json(
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn response_errors() {
+        let res = response(
+            StatusCode::OK,
+            &json!({
+                "data": { "hero": null },
+                "errors": [{
+                    "message": "Name for character with ID 1002 could not be fetched.",
+                    "locations": [{ "line": 6, "column": 7 }],
+                    "path": ["hero", "heroFriends", 1, "name"],
+                }],
+            }),
+        );
+        let Err(FromHttpResponseError::EndpointError(errors)) =
+            GraphQlResponse::<HeroData>::try_from_http_response(res)
+        else {
+            panic!("expected GraphQL errors");
+        };
+        assert_eq!(
+            errors.0[0].locations,
+            [GraphQlLocation { line: 6, column: 7 }]
+        );
+        assert_eq!(errors.0[0].path[2], 1);
+        assert_eq!(
+            errors.to_string(),
+            "Name for character with ID 1002 could not be fetched."
+        );
+
+        // Request errors with a non-success status
+        let res = response(
+            StatusCode::BAD_REQUEST,
+            &json!({ "errors": [{ "message": "Syntax error" }] }),
+        );
+        let Err(FromHttpResponseError::EndpointError(errors)) =
+            GraphQlResponse::<HeroData>::try_from_http_response(res)
+        else {
+            panic!("expected GraphQL errors");
+        };
+        assert_eq!(errors.to_string(), "Syntax error");
+
+        let res: http::Response<BytesMut> = errors.clone().try_into_http_response().unwrap();
+        assert_eq!(GraphQlErrors::from_http_response(res), errors);
+        assert_eq!(
+            GraphQlErrors::from_http_response(response(StatusCode::BAD_GATEWAY, &json!(null)))
+                .to_string(),
+            "502 Bad Gateway"
+        );
+    }
+}
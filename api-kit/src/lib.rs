@@ -1,18 +1,57 @@
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+use std::{borrow::Cow, future::Future};
+
+#[cfg(feature = "derive")]
+pub use api_kit_macros::{IncomingResponse, OutgoingRequest};
 use bytes::{BufMut, Bytes, BytesMut};
+// `criterion` is only used by the benchmarks
+#[cfg(test)]
+use criterion as _;
 pub use http;
+use http::header::CONTENT_TYPE;
+// The runtime and `tower` are only used by the client and server integration tests
+#[cfg(all(test, not(any(feature = "reqwest", feature = "axum"))))]
+use tokio as _;
+#[cfg(all(test, not(feature = "axum")))]
+use tower as _;
 
 use crate::{
-    auth::Authenticator,
+    auth::{AsyncAuthenticator, Authenticator},
     error::{FromHttpRequestError, FromHttpResponseError, IntoHttpError},
-    metadata::Metadata,
+    metadata::{media_type_matches, Metadata, PathMatching, RetryPolicy},
+    warning::{Warning, Warnings},
 };
 
 pub mod auth;
+pub mod body;
+pub mod client;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod conditional;
 pub mod error;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 pub mod metadata;
+pub mod response;
+pub mod rewrite;
+pub mod server;
+pub mod testing;
 mod url;
+pub mod warning;
+
+// The derive macros refer to this crate as `::api_kit`, including in its own tests
+#[cfg(all(test, feature = "derive"))]
+extern crate self as api_kit;
+
+/// Re-exports used by the derive macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use bytes;
+    pub use serde;
+}
 
 /// An API endpoint.
 ///
@@ -24,6 +63,11 @@ pub trait Endpoint: Sized {
 
     /// A metadata of this endpoint.
     const METADATA: Metadata<'static>;
+
+    /// When client integrations retry requests to this endpoint.
+    ///
+    /// Defaults to [`RetryPolicy::NONE`], never retrying.
+    const RETRY_POLICY: RetryPolicy<'static> = RetryPolicy::NONE;
 }
 
 /// An incoming request.
@@ -51,6 +95,31 @@ pub trait IncomingRequest: Endpoint {
         B: AsRef<[u8]>,
         I: IntoIterator<Item = &'a P>,
         P: AsRef<str> + 'a;
+
+    /// Convert an incoming HTTP request, matching it against the endpoint [`Metadata`].
+    ///
+    /// The request method must be the endpoint method,
+    /// and the request path must match the endpoint path (see [`PathMatching::Lenient`]).
+    /// The path arguments are extracted from the path and passed to
+    /// [`IncomingRequest::try_from_http_request`].
+    ///
+    /// [`PathMatching::Lenient`]: metadata::PathMatching::Lenient
+    fn from_request(req: http::Request<Bytes>) -> Result<Self, FromHttpRequestError> {
+        if req.method() != Self::METADATA.method {
+            return Err(FromHttpRequestError::MethodMismatch {
+                expected: Self::METADATA.method,
+                actual: req.method().clone(),
+            });
+        }
+
+        let path_args = Self::METADATA
+            .extract_path_args(req.uri().path(), PathMatching::Lenient)
+            .ok_or(FromHttpRequestError::PathMismatch)?
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect::<Vec<_>>();
+        Self::try_from_http_request(req, &path_args)
+    }
 }
 
 pub trait OutgoingResponse: Sized {
@@ -68,18 +137,208 @@ pub trait OutgoingRequest: Endpoint + Clone {
         self,
         base_url: &str,
         auth: A,
-        auth_data: A::AuthData,
+        auth_data: &A::AuthData,
     ) -> Result<http::Request<BytesMut>, IntoHttpError>
     where
         A: Authenticator;
+
+    /// Collect endpoint-specific warnings about this request.
+    ///
+    /// This is called by [`try_into_http_request_with_warnings`] before the request is built.
+    /// The default implementation adds no warnings.
+    ///
+    /// [`try_into_http_request_with_warnings`]: OutgoingRequest::try_into_http_request_with_warnings
+    fn warnings(&self, _warnings: &mut Warnings) {}
+
+    /// Convert into an HTTP request, collecting any non-fatal [`Warning`]s.
+    ///
+    /// In addition to the warnings from [`OutgoingRequest::warnings`], this checks that
+    /// the authenticator is accepted by the endpoint, that the built request contains
+    /// every header listed in the endpoint [`Metadata`], and that it has the endpoint
+    /// content type, if any.
+    fn try_into_http_request_with_warnings<A>(
+        self,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<(http::Request<BytesMut>, Warnings), IntoHttpError>
+    where
+        A: Authenticator,
+    {
+        let mut warnings = Warnings::new();
+        self.warnings(&mut warnings);
+
+        let scheme = auth.scheme();
+        if !scheme.is_empty() && !Self::METADATA.contains_auth(&auth) {
+            warnings.push(Warning::UnlistedAuthScheme(scheme));
+        }
+
+        let req = self.try_into_http_request(base_url, auth, auth_data)?;
+
+        for (name, _) in Self::METADATA.headers {
+            if !req.headers().contains_key(name) {
+                warnings.push(Warning::MissingHeader(name.clone()));
+            }
+        }
+
+        if let Some(expected) = Self::METADATA.content_type {
+            let actual = req.headers().get(CONTENT_TYPE);
+            let matches = expected
+                .to_str()
+                .is_ok_and(|expected| actual.is_some_and(|v| media_type_matches(v, expected)));
+            if !matches {
+                warnings.push(Warning::ContentTypeMismatch {
+                    expected,
+                    actual: actual.cloned(),
+                });
+            }
+        }
+
+        Ok((req, warnings))
+    }
+
+    /// Convert into an HTTP request, authenticating it with an [`AsyncAuthenticator`].
+    ///
+    /// The request is built without authentication using [`try_into_http_request`],
+    /// then authenticated by awaiting `auth`.
+    /// Implementations of [`try_into_http_request`] must therefore accept `()` as authenticator.
+    ///
+    /// [`try_into_http_request`]: OutgoingRequest::try_into_http_request
+    fn try_into_http_request_async<A>(
+        self,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> impl Future<Output = Result<http::Request<BytesMut>, IntoHttpError>> + Send
+    where
+        A: AsyncAuthenticator + Send + Sync,
+        A::AuthData: Sync,
+    {
+        let req = self.try_into_http_request(base_url, (), &());
+        async move {
+            let mut req = req?;
+            auth.authenticate(&mut req, auth_data).await?;
+            Ok(req)
+        }
+    }
 }
 
 pub trait IncomingResponse: Sized {
     type OutgoingRequest: OutgoingRequest;
 
+    /// Whether [`try_from_http_response_checked`] validates the `Content-Length` header.
+    ///
+    /// Endpoints whose responses legitimately declare a different length than their body,
+    /// such as `HEAD` endpoints, should set this to `false`.
+    ///
+    /// [`try_from_http_response_checked`]: IncomingResponse::try_from_http_response_checked
+    const CHECK_CONTENT_LENGTH: bool = true;
+
+    /// The media type responses must have, e.g. `application/json`.
+    ///
+    /// If set, [`try_from_http_response_checked`] rejects responses with a different
+    /// `Content-Type` with a [`DeserializeError::UnexpectedContentType`] error,
+    /// using [`response::check_content_type`].
+    ///
+    /// [`try_from_http_response_checked`]: IncomingResponse::try_from_http_response_checked
+    /// [`DeserializeError::UnexpectedContentType`]: error::DeserializeError::UnexpectedContentType
+    const EXPECTED_CONTENT_TYPE: Option<&'static str> = None;
+
     fn try_from_http_response(
         res: http::Response<Bytes>,
     ) -> Result<Self, FromHttpResponseError<<Self::OutgoingRequest as Endpoint>::Error>>;
+
+    /// Validate the response before converting it with [`try_from_http_response`].
+    ///
+    /// Unless [`CHECK_CONTENT_LENGTH`] is `false`, a body shorter than its `Content-Length`
+    /// header results in a [`DeserializeError::TruncatedBody`] error.
+    /// If [`EXPECTED_CONTENT_TYPE`] is set, the `Content-Type` of the response is checked.
+    ///
+    /// [`try_from_http_response`]: IncomingResponse::try_from_http_response
+    /// [`CHECK_CONTENT_LENGTH`]: IncomingResponse::CHECK_CONTENT_LENGTH
+    /// [`EXPECTED_CONTENT_TYPE`]: IncomingResponse::EXPECTED_CONTENT_TYPE
+    /// [`DeserializeError::TruncatedBody`]: error::DeserializeError::TruncatedBody
+    fn try_from_http_response_checked(
+        res: http::Response<Bytes>,
+    ) -> Result<Self, FromHttpResponseError<<Self::OutgoingRequest as Endpoint>::Error>> {
+        if Self::CHECK_CONTENT_LENGTH {
+            response::check_content_length(&res)?;
+        }
+        if let Some(expected) = Self::EXPECTED_CONTENT_TYPE {
+            response::check_content_type(&res, expected)?;
+        }
+        Self::try_from_http_response(res)
+    }
+
+    /// Convert an HTTP response with a streamed body.
+    ///
+    /// Endpoints returning large bodies, such as exports, can override this
+    /// to keep the body as a stream in the implementing type rather than buffering it.
+    ///
+    /// The default implementation buffers the body with [`response::collect_body`]
+    /// and converts it with [`try_from_http_response`].
+    ///
+    /// [`try_from_http_response`]: IncomingResponse::try_from_http_response
+    #[cfg(feature = "stream")]
+    fn try_from_http_response_stream<S, E>(
+        res: http::Response<S>,
+    ) -> impl Future<
+        Output = Result<Self, FromHttpResponseError<<Self::OutgoingRequest as Endpoint>::Error>>,
+    > + Send
+    where
+        S: futures_core::Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+    {
+        async move { Self::try_from_http_response(response::collect_body(res).await?) }
+    }
+
+    /// Returns `true` if responses with `status` are converted into the implementing type,
+    /// rather than into the endpoint's [`Endpoint::Error`].
+    ///
+    /// The default implementation accepts success (`2xx`) statuses.
+    /// Override this to deserialize structured bodies of specific error statuses,
+    /// such as `422 Unprocessable Entity` validation errors, into the implementing type.
+    #[must_use]
+    fn accepts_status(status: http::StatusCode) -> bool {
+        status.is_success()
+    }
+
+    /// Convert an HTTP response, choosing the deserialization target by its status.
+    ///
+    /// Responses accepted by [`IncomingResponse::accepts_status`] are converted with
+    /// [`IncomingResponse::try_from_http_response_checked`],
+    /// after checking success statuses against [`Metadata::success_status`].
+    /// Other responses are converted with [`EndpointError::try_from_http_response`].
+    fn try_from_http_response_by_status(
+        res: http::Response<Bytes>,
+    ) -> Result<Self, FromHttpResponseError<<Self::OutgoingRequest as Endpoint>::Error>> {
+        if Self::accepts_status(res.status()) {
+            <Self::OutgoingRequest as Endpoint>::METADATA.check_success_status(res.status())?;
+            Self::try_from_http_response_checked(res)
+        } else {
+            Err(FromHttpResponseError::from_error_response(res))
+        }
+    }
+}
+
+/// An incoming response that borrows from the response body.
+///
+/// This is the zero-copy counterpart of [`IncomingResponse`],
+/// allowing fields such as `&'de str` to borrow directly from the buffered body
+/// (e.g. using `serde`'s `#[serde(borrow)]`).
+///
+/// # Lifetimes
+///
+/// The implementing type is tied to the lifetime `'de` of the borrowed response.
+/// The response must therefore outlive the deserialized value,
+/// and can't be moved or dropped while the value is in use.
+/// Use [`IncomingResponse`] when the value must outlive the response.
+pub trait IncomingResponseRef<'de>: Sized {
+    type OutgoingRequest: OutgoingRequest;
+
+    fn try_from_http_response_ref(
+        res: &'de http::Response<Bytes>,
+    ) -> Result<Self, FromHttpResponseError<<Self::OutgoingRequest as Endpoint>::Error>>;
 }
 
 pub trait EndpointError: Sized + Send + 'static {
@@ -87,4 +346,1073 @@ pub trait EndpointError: Sized + Send + 'static {
     where
         B: Default + BufMut;
     fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self;
+
+    /// Try to convert an error response into the implementing type.
+    ///
+    /// Implementations that parse the error body should override this method to
+    /// return a [`DeserializeError`] when the body can't be understood,
+    /// so clients can distinguish errors the server sent from malformed responses.
+    ///
+    /// The default implementation never fails and calls [`EndpointError::from_http_response`].
+    ///
+    /// [`DeserializeError`]: error::DeserializeError
+    fn try_from_http_response<T: AsRef<[u8]>>(
+        response: http::Response<T>,
+    ) -> Result<Self, error::DeserializeError> {
+        Ok(Self::from_http_response(response))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        pin::pin,
+        sync::Mutex,
+        task::{Context, Poll, Waker},
+    };
+
+    use http::{header::ACCEPT, HeaderName, HeaderValue, Method};
+
+    use super::*;
+    use crate::auth::{bearer::BearerAuth, AuthScheme};
+
+    /// Poll a future that completes without waiting.
+    pub fn now_or_never<F: Future>(fut: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(fut).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct TestError;
+
+    impl EndpointError for TestError {
+        fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+        where
+            B: Default + BufMut,
+        {
+            Ok(http::Response::builder().status(500).body(B::default())?)
+        }
+
+        fn from_http_response<T: AsRef<[u8]>>(_response: http::Response<T>) -> Self {
+            Self
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct TestRequest {
+        pub id: u32,
+        pub accept: bool,
+    }
+
+    #[derive(Debug)]
+    pub struct TestResponse;
+
+    static HEADERS: [(HeaderName, HeaderValue); 1] =
+        [(ACCEPT, HeaderValue::from_static("application/json"))];
+
+    impl Endpoint for TestRequest {
+        type Error = TestError;
+
+        const METADATA: Metadata<'static> = Metadata {
+            method: Method::GET,
+            auth: &[&BearerAuth::new()],
+            path: "/items/{id}",
+            headers: &HEADERS,
+            version: Some(http::Version::HTTP_2),
+            ..Metadata::DEFAULT
+        };
+    }
+
+    impl OutgoingRequest for TestRequest {
+        type IncomingResponse = TestResponse;
+
+        fn try_into_http_request<A>(
+            self,
+            base_url: &str,
+            auth: A,
+            auth_data: &A::AuthData,
+        ) -> Result<http::Request<BytesMut>, IntoHttpError>
+        where
+            A: Authenticator,
+        {
+            #[derive(serde::Serialize)]
+            struct Path {
+                id: u32,
+            }
+
+            let url = Self::METADATA.make_url(base_url, &Path { id: self.id }, &())?;
+            let mut builder = Self::METADATA.request_builder(url);
+            if self.accept {
+                for (name, value) in Self::METADATA.headers {
+                    builder = builder.header(name, value);
+                }
+            }
+            let mut req = builder.body(BytesMut::new())?;
+            auth.authenticate(&mut req, auth_data)?;
+            Ok(req)
+        }
+
+        fn warnings(&self, warnings: &mut Warnings) {
+            if self.id == 0 {
+                warnings.push(Warning::Deprecated("item 0 is deprecated".into()));
+            }
+        }
+    }
+
+    impl IncomingResponse for TestResponse {
+        type OutgoingRequest = TestRequest;
+
+        fn try_from_http_response(
+            _res: http::Response<Bytes>,
+        ) -> Result<Self, FromHttpResponseError<TestError>> {
+            Ok(Self)
+        }
+    }
+
+    /// A local HTTP server and endpoint for sending real requests with the client integrations.
+    #[cfg(any(feature = "reqwest", feature = "blocking"))]
+    pub mod server {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            thread::JoinHandle,
+            time::Duration,
+        };
+
+        use bytes::{Bytes, BytesMut};
+        use http::Method;
+
+        use super::TestError;
+        use crate::{
+            auth::{bearer::BearerAuth, Authenticator},
+            error::{FromHttpResponseError, IntoHttpError},
+            metadata::{Metadata, RetryPolicy},
+            Endpoint, IncomingResponse, OutgoingRequest,
+        };
+
+        /// Serve a single `response` on a local port, returning the base URL
+        /// and a handle to the received request.
+        pub fn serve_once(response: &'static str) -> (String, JoinHandle<String>) {
+            let (base_url, handle) = serve(vec![response]);
+            (
+                base_url,
+                std::thread::spawn(move || handle.join().unwrap().remove(0)),
+            )
+        }
+
+        /// Serve `responses` on a local port, one per connection, returning the base URL
+        /// and a handle to the received requests.
+        pub fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base_url = format!("http://{}", listener.local_addr().unwrap());
+            let handle = std::thread::spawn(move || {
+                let mut requests = Vec::new();
+                for response in responses {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut request = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        let n = stream.read(&mut buf).unwrap();
+                        if n == 0 {
+                            break;
+                        }
+                        request.extend_from_slice(&buf[..n]);
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    requests.push(String::from_utf8(request).unwrap());
+                }
+                requests
+            });
+            (base_url, handle)
+        }
+
+        /// `GET /items/1`, responding with the raw body.
+        ///
+        /// Unavailable responses are retried once, and requests time out after 30 seconds.
+        #[derive(Debug, Clone)]
+        pub struct GetItem;
+
+        #[derive(Debug, PartialEq, Eq)]
+        pub struct Item(pub Bytes);
+
+        impl Endpoint for GetItem {
+            type Error = TestError;
+
+            const METADATA: Metadata<'static> = Metadata {
+                method: Method::GET,
+                auth: &[&BearerAuth::new()],
+                path: "/items/1",
+                timeout: Some(Duration::from_secs(30)),
+                ..Metadata::DEFAULT
+            };
+            const RETRY_POLICY: RetryPolicy<'static> = RetryPolicy {
+                max_attempts: 2,
+                retry_statuses: &[http::StatusCode::SERVICE_UNAVAILABLE],
+                idempotent: false,
+                max_delay: Duration::from_mins(1),
+            };
+        }
+
+        impl OutgoingRequest for GetItem {
+            type IncomingResponse = Item;
+
+            fn try_into_http_request<A>(
+                self,
+                base_url: &str,
+                auth: A,
+                auth_data: &A::AuthData,
+            ) -> Result<http::Request<BytesMut>, IntoHttpError>
+            where
+                A: Authenticator,
+            {
+                let url = Self::METADATA.make_url(base_url, &(), &())?;
+                let mut req = Self::METADATA.request_builder(url).body(BytesMut::new())?;
+                auth.authenticate(&mut req, auth_data)?;
+                Ok(req)
+            }
+        }
+
+        impl IncomingResponse for Item {
+            type OutgoingRequest = GetItem;
+
+            fn try_from_http_response(
+                res: http::Response<Bytes>,
+            ) -> Result<Self, FromHttpResponseError<TestError>> {
+                Ok(Self(res.into_body()))
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn incoming_response_ref() {
+        #[derive(serde::Deserialize)]
+        struct BorrowedResponse<'a> {
+            name: &'a str,
+        }
+
+        impl<'de> IncomingResponseRef<'de> for BorrowedResponse<'de> {
+            type OutgoingRequest = TestRequest;
+
+            fn try_from_http_response_ref(
+                res: &'de http::Response<Bytes>,
+            ) -> Result<Self, FromHttpResponseError<TestError>> {
+                Ok(serde_json::from_slice(res.body())?)
+            }
+        }
+
+        let res = http::Response::new(Bytes::from_static(br#"{"name":"item"}"#));
+        let parsed = BorrowedResponse::try_from_http_response_ref(&res).unwrap();
+        assert_eq!(parsed.name, "item");
+        assert_eq!(parsed.name.as_ptr(), res.body()[9..].as_ptr());
+    }
+
+    #[test]
+    fn incoming_response_checked() {
+        let mut res = http::Response::new(Bytes::from_static(b"short"));
+        res.headers_mut().insert(
+            http::header::CONTENT_LENGTH,
+            HeaderValue::from_static("100"),
+        );
+        assert!(matches!(
+            TestResponse::try_from_http_response_checked(res).unwrap_err(),
+            FromHttpResponseError::Deserialize(error::DeserializeError::TruncatedBody { .. })
+        ));
+
+        let res = http::Response::new(Bytes::from_static(b"short"));
+        TestResponse::try_from_http_response_checked(res).unwrap();
+    }
+
+    #[test]
+    fn endpoint_error_fallible() {
+        #[derive(Debug)]
+        struct CodeError(u32);
+
+        impl EndpointError for CodeError {
+            fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+            where
+                B: Default + BufMut,
+            {
+                Ok(http::Response::builder().status(400).body(B::default())?)
+            }
+
+            fn from_http_response<T: AsRef<[u8]>>(response: http::Response<T>) -> Self {
+                Self::try_from_http_response(response).unwrap_or(Self(0))
+            }
+
+            fn try_from_http_response<T: AsRef<[u8]>>(
+                response: http::Response<T>,
+            ) -> Result<Self, error::DeserializeError> {
+                #[derive(serde::Deserialize)]
+                struct Body {
+                    code: u32,
+                }
+                let body: Body = serde_urlencoded::from_bytes(response.body().as_ref())?;
+                Ok(Self(body.code))
+            }
+        }
+
+        let res = http::Response::new("code=42");
+        let err = FromHttpResponseError::<CodeError>::from_error_response(res);
+        assert!(matches!(
+            err,
+            FromHttpResponseError::EndpointError(CodeError(42))
+        ));
+
+        let res = http::Response::new("garbage");
+        let err = FromHttpResponseError::<CodeError>::from_error_response(res);
+        assert!(matches!(err, FromHttpResponseError::Deserialize(_)));
+
+        // The default implementation is infallible
+        let res = http::Response::new("garbage");
+        let err = FromHttpResponseError::<TestError>::from_error_response(res);
+        assert!(matches!(
+            err,
+            FromHttpResponseError::EndpointError(TestError)
+        ));
+        assert_eq!(err.retry_after(), None);
+
+        let res = http::Response::builder()
+            .status(http::StatusCode::TOO_MANY_REQUESTS)
+            .header(http::header::RETRY_AFTER, "30")
+            .body("code=7")
+            .unwrap();
+        let err = FromHttpResponseError::<CodeError>::from_error_response(res);
+        assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(30)));
+        assert!(matches!(
+            err,
+            FromHttpResponseError::RateLimited {
+                error: CodeError(7),
+                ..
+            }
+        ));
+
+        let res = http::Response::builder()
+            .status(http::StatusCode::TOO_MANY_REQUESTS)
+            .header(http::header::RETRY_AFTER, "later")
+            .body("code=7")
+            .unwrap();
+        let err = FromHttpResponseError::<CodeError>::from_error_response(res);
+        assert!(matches!(
+            err,
+            FromHttpResponseError::RateLimited {
+                retry_after: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn incoming_response_by_status() {
+        #[derive(Debug, PartialEq, Eq)]
+        enum ValidatedResponse {
+            Ok,
+            Invalid(Bytes),
+        }
+
+        impl IncomingResponse for ValidatedResponse {
+            type OutgoingRequest = TestRequest;
+
+            fn try_from_http_response(
+                res: http::Response<Bytes>,
+            ) -> Result<Self, FromHttpResponseError<TestError>> {
+                if res.status() == http::StatusCode::UNPROCESSABLE_ENTITY {
+                    Ok(Self::Invalid(res.into_body()))
+                } else {
+                    Ok(Self::Ok)
+                }
+            }
+
+            fn accepts_status(status: http::StatusCode) -> bool {
+                status.is_success() || status == http::StatusCode::UNPROCESSABLE_ENTITY
+            }
+        }
+
+        let response = |status: u16| {
+            http::Response::builder()
+                .status(status)
+                .body(Bytes::from_static(b"field: required"))
+                .unwrap()
+        };
+
+        assert_eq!(
+            ValidatedResponse::try_from_http_response_by_status(response(200)).unwrap(),
+            ValidatedResponse::Ok
+        );
+        assert_eq!(
+            ValidatedResponse::try_from_http_response_by_status(response(422)).unwrap(),
+            ValidatedResponse::Invalid(Bytes::from_static(b"field: required"))
+        );
+        assert!(matches!(
+            ValidatedResponse::try_from_http_response_by_status(response(400)).unwrap_err(),
+            FromHttpResponseError::EndpointError(TestError)
+        ));
+        assert!(matches!(
+            TestResponse::try_from_http_response_by_status(response(422)).unwrap_err(),
+            FromHttpResponseError::EndpointError(TestError)
+        ));
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn incoming_response_stream() {
+        use std::collections::VecDeque;
+
+        struct Chunks(VecDeque<Result<Bytes, &'static str>>);
+
+        impl futures_core::Stream for Chunks {
+            type Item = Result<Bytes, &'static str>;
+
+            fn poll_next(
+                mut self: std::pin::Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<Option<Self::Item>> {
+                Poll::Ready(self.0.pop_front())
+            }
+        }
+
+        #[derive(Debug, PartialEq, Eq)]
+        struct Export(Bytes);
+
+        impl IncomingResponse for Export {
+            type OutgoingRequest = TestRequest;
+
+            fn try_from_http_response(
+                res: http::Response<Bytes>,
+            ) -> Result<Self, FromHttpResponseError<TestError>> {
+                Ok(Self(res.into_body()))
+            }
+        }
+
+        let response =
+            |chunks: Vec<Result<Bytes, &'static str>>| http::Response::new(Chunks(chunks.into()));
+
+        let res = response(vec![
+            Ok(Bytes::from_static(b"id,name\n")),
+            Ok(Bytes::from_static(b"1,a\n")),
+        ]);
+        assert_eq!(
+            now_or_never(Export::try_from_http_response_stream(res)).unwrap(),
+            Export(Bytes::from_static(b"id,name\n1,a\n"))
+        );
+
+        let res = response(vec![Ok(Bytes::from_static(b"id")), Err("reset")]);
+        assert!(matches!(
+            now_or_never(Export::try_from_http_response_stream(res)).unwrap_err(),
+            FromHttpResponseError::Deserialize(error::DeserializeError::Body(err))
+                if err.to_string() == "reset"
+        ));
+    }
+
+    #[test]
+    fn incoming_response_success_status() {
+        #[derive(Debug, Clone)]
+        struct CreateRequest;
+
+        #[derive(Debug)]
+        struct CreateResponse;
+
+        impl Endpoint for CreateRequest {
+            type Error = TestError;
+
+            const METADATA: Metadata<'static> = Metadata {
+                method: Method::POST,
+                path: "/items",
+                success_status: Some(http::StatusCode::CREATED),
+                ..Metadata::DEFAULT
+            };
+        }
+
+        impl OutgoingRequest for CreateRequest {
+            type IncomingResponse = CreateResponse;
+
+            fn try_into_http_request<A>(
+                self,
+                base_url: &str,
+                auth: A,
+                auth_data: &A::AuthData,
+            ) -> Result<http::Request<BytesMut>, IntoHttpError>
+            where
+                A: Authenticator,
+            {
+                let url = Self::METADATA.make_body_url(base_url, &())?;
+                let mut req = Self::METADATA.request_builder(url).body(BytesMut::new())?;
+                auth.authenticate(&mut req, auth_data)?;
+                Ok(req)
+            }
+        }
+
+        impl IncomingResponse for CreateResponse {
+            type OutgoingRequest = CreateRequest;
+
+            fn try_from_http_response(
+                _res: http::Response<Bytes>,
+            ) -> Result<Self, FromHttpResponseError<TestError>> {
+                Ok(Self)
+            }
+        }
+
+        let response = |status: u16| {
+            http::Response::builder()
+                .status(status)
+                .body(Bytes::new())
+                .unwrap()
+        };
+
+        CreateResponse::try_from_http_response_by_status(response(201)).unwrap();
+        assert!(matches!(
+            CreateResponse::try_from_http_response_by_status(response(200)).unwrap_err(),
+            FromHttpResponseError::Deserialize(error::DeserializeError::UnexpectedStatus {
+                expected: http::StatusCode::CREATED,
+                actual: http::StatusCode::OK,
+            })
+        ));
+        assert!(matches!(
+            CreateResponse::try_from_http_response_by_status(response(500)).unwrap_err(),
+            FromHttpResponseError::EndpointError(TestError)
+        ));
+
+        // Any success status is accepted by default
+        TestResponse::try_from_http_response_by_status(response(202)).unwrap();
+
+        let req = CreateRequest
+            .try_into_http_request("https://example.com", (), &())
+            .unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), "https://example.com/items");
+    }
+
+    /// A token provider fetching its token on first use.
+    #[derive(Debug, Default)]
+    struct TokenProvider {
+        token: Mutex<Option<String>>,
+    }
+
+    impl AuthScheme for TokenProvider {
+        fn scheme(&self) -> &'static str {
+            "bearer"
+        }
+    }
+
+    impl AsyncAuthenticator for TokenProvider {
+        type AuthData = String;
+
+        async fn authenticate(
+            &self,
+            req: &mut http::Request<BytesMut>,
+            client_id: &Self::AuthData,
+        ) -> Result<(), IntoHttpError> {
+            // Simulated token endpoint
+            let fetched = std::future::ready(format!("token-for-{client_id}")).await;
+            let token = self.token.lock().unwrap().get_or_insert(fetched).clone();
+            req.headers_mut().insert(
+                http::header::AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {token}"))?,
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn async_authenticator() {
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        };
+        let http_req = now_or_never(req.clone().try_into_http_request_async(
+            "https://example.com",
+            TokenProvider::default(),
+            &"client".to_owned(),
+        ))
+        .unwrap();
+        assert_eq!(http_req.uri(), "https://example.com/items/1");
+        assert_eq!(
+            http_req.headers()[http::header::AUTHORIZATION],
+            "Bearer token-for-client"
+        );
+
+        // Synchronous authenticators work as well
+        let http_req = now_or_never(req.try_into_http_request_async(
+            "https://example.com",
+            BearerAuth::new(),
+            &"token".to_owned(),
+        ))
+        .unwrap();
+        assert_eq!(
+            http_req.headers()[http::header::AUTHORIZATION],
+            "Bearer token"
+        );
+    }
+
+    #[test]
+    fn warnings_none() {
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        };
+        let (req, warnings) = req
+            .try_into_http_request_with_warnings(
+                "https://example.com",
+                BearerAuth::new(),
+                &"token".to_owned(),
+            )
+            .unwrap();
+        assert_eq!(req.uri(), "https://example.com/items/1");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn warnings_collected() {
+        let req = TestRequest {
+            id: 0,
+            accept: false,
+        };
+        let (_, warnings) = req
+            .try_into_http_request_with_warnings("https://example.com", (), &())
+            .unwrap();
+        assert_eq!(
+            warnings.into_vec(),
+            vec![
+                Warning::Deprecated("item 0 is deprecated".into()),
+                Warning::MissingHeader(ACCEPT),
+            ]
+        );
+    }
+
+    #[test]
+    fn warnings_content_type() {
+        #[derive(Debug, Clone)]
+        struct Upload(Option<&'static str>);
+
+        #[derive(Debug)]
+        struct UploadResponse;
+
+        impl Endpoint for Upload {
+            type Error = TestError;
+
+            const METADATA: Metadata<'static> = Metadata {
+                method: Method::POST,
+                path: "/upload",
+                content_type: Some(HeaderValue::from_static("application/json")),
+                ..Metadata::DEFAULT
+            };
+        }
+
+        impl OutgoingRequest for Upload {
+            type IncomingResponse = UploadResponse;
+
+            fn try_into_http_request<A>(
+                self,
+                base_url: &str,
+                auth: A,
+                auth_data: &A::AuthData,
+            ) -> Result<http::Request<BytesMut>, IntoHttpError>
+            where
+                A: Authenticator,
+            {
+                let mut builder = http::Request::builder()
+                    .method(Self::METADATA.method)
+                    .uri(Self::METADATA.make_body_url(base_url, &())?);
+                if let Some(content_type) = self.0 {
+                    builder = builder.header(CONTENT_TYPE, content_type);
+                }
+                let mut req = builder.body(BytesMut::new())?;
+                auth.authenticate(&mut req, auth_data)?;
+                Ok(req)
+            }
+        }
+
+        impl IncomingResponse for UploadResponse {
+            type OutgoingRequest = Upload;
+
+            fn try_from_http_response(
+                _res: http::Response<Bytes>,
+            ) -> Result<Self, FromHttpResponseError<TestError>> {
+                Ok(Self)
+            }
+        }
+
+        let warnings = |content_type| {
+            Upload(content_type)
+                .try_into_http_request_with_warnings("https://example.com", (), &())
+                .unwrap()
+                .1
+                .into_vec()
+        };
+        assert!(warnings(Some("application/json; charset=utf-8")).is_empty());
+        assert_eq!(
+            warnings(Some("text/plain")),
+            vec![Warning::ContentTypeMismatch {
+                expected: HeaderValue::from_static("application/json"),
+                actual: Some(HeaderValue::from_static("text/plain")),
+            }]
+        );
+        assert_eq!(
+            warnings(None),
+            vec![Warning::ContentTypeMismatch {
+                expected: HeaderValue::from_static("application/json"),
+                actual: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn warnings_unlisted_auth() {
+        #[derive(Debug)]
+        struct OtherAuth;
+
+        impl AuthScheme for OtherAuth {
+            fn scheme(&self) -> &'static str {
+                "other"
+            }
+        }
+
+        impl Authenticator for OtherAuth {
+            type AuthData = ();
+
+            fn authenticate(
+                &self,
+                req: &mut http::Request<BytesMut>,
+                _data: &Self::AuthData,
+            ) -> Result<(), IntoHttpError> {
+                req.headers_mut().insert(
+                    HeaderName::from_static("x-other"),
+                    HeaderValue::from_static("1"),
+                );
+                Ok(())
+            }
+        }
+
+        let req = TestRequest {
+            id: 1,
+            accept: true,
+        };
+        let (req, warnings) = req
+            .try_into_http_request_with_warnings("https://example.com", OtherAuth, &())
+            .unwrap();
+        assert!(req.headers().contains_key("x-other"));
+        assert_eq!(
+            warnings.into_vec(),
+            vec![Warning::UnlistedAuthScheme("other")]
+        );
+    }
+
+    #[test]
+    fn incoming_request_from_request() {
+        #[derive(Debug, PartialEq, Eq)]
+        struct PutItem {
+            id: String,
+            body: Bytes,
+        }
+
+        struct PutItemResponse;
+
+        impl Endpoint for PutItem {
+            type Error = TestError;
+
+            const METADATA: Metadata<'static> = Metadata {
+                method: Method::PUT,
+                path: "/items/{id}",
+                ..Metadata::DEFAULT
+            };
+        }
+
+        impl IncomingRequest for PutItem {
+            type OutgoingResponse = PutItemResponse;
+
+            fn try_from_http_request<'a, B, I, P>(
+                req: http::Request<B>,
+                path_args: I,
+            ) -> Result<Self, FromHttpRequestError>
+            where
+                B: AsRef<[u8]>,
+                I: IntoIterator<Item = &'a P>,
+                P: AsRef<str> + 'a,
+            {
+                let id = path_args.into_iter().next().unwrap().as_ref().to_owned();
+                Ok(Self {
+                    id,
+                    body: Bytes::copy_from_slice(req.body().as_ref()),
+                })
+            }
+        }
+
+        impl OutgoingResponse for PutItemResponse {
+            type IncomingRequest = PutItem;
+
+            fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+            where
+                B: Default + BufMut,
+            {
+                Ok(http::Response::new(B::default()))
+            }
+        }
+
+        let request = |method, uri| {
+            http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(Bytes::from_static(b"data"))
+                .unwrap()
+        };
+
+        assert_eq!(
+            PutItem::from_request(request(Method::PUT, "https://example.com/items/a%20b/"))
+                .unwrap(),
+            PutItem {
+                id: "a b".into(),
+                body: Bytes::from_static(b"data"),
+            }
+        );
+        assert!(matches!(
+            PutItem::from_request(request(Method::GET, "/items/1")).unwrap_err(),
+            FromHttpRequestError::MethodMismatch {
+                expected: Method::PUT,
+                actual: Method::GET,
+            }
+        ));
+        assert!(matches!(
+            PutItem::from_request(request(Method::PUT, "/users/1")).unwrap_err(),
+            FromHttpRequestError::PathMismatch
+        ));
+    }
+
+    #[cfg(all(feature = "derive", feature = "json"))]
+    mod derive {
+        use std::marker::PhantomData;
+
+        use serde::{Deserialize, Serialize};
+
+        use super::*;
+        use crate::{body::FormFormat, error::DeserializeError, metadata::QueryStyle};
+
+        /// Get a user by ID.
+        ///
+        /// Only the first line is the summary.
+        #[derive(Clone, OutgoingRequest)]
+        #[api_kit(method = GET, path = "/users/{id}", auth = BearerAuth::new(), response = User)]
+        struct GetUser {
+            id: u64,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            fields: Option<&'static str>,
+        }
+
+        #[derive(Debug, PartialEq, Eq, Deserialize, IncomingResponse)]
+        #[api_kit(request = GetUser)]
+        struct User {
+            id: u64,
+            name: String,
+        }
+
+        #[derive(Debug, Deserialize, IncomingResponse)]
+        #[api_kit(request = CreateUser<T>, format = FormFormat)]
+        struct CreatedUser<T: Serialize + Clone> {
+            id: u64,
+            #[serde(skip)]
+            request: PhantomData<T>,
+        }
+
+        #[derive(Clone, OutgoingRequest)]
+        #[api_kit(
+            method = POST,
+            path = "/orgs/{org}/users",
+            response = CreatedUser<T>,
+            error = TestError,
+            format = FormFormat,
+            query_style = Bracketed,
+        )]
+        struct CreateUser<T: Serialize + Clone> {
+            org: &'static str,
+            #[serde(rename = "userName")]
+            name: T,
+            #[api_kit(query)]
+            tags: Vec<&'static str>,
+        }
+
+        #[derive(Clone, OutgoingRequest)]
+        #[api_kit(method = PUT, path = "/users/{id}", response = UpdatedUser)]
+        struct UpdateUser {
+            id: u64,
+            name: &'static str,
+            #[api_kit(header = "Idempotency-Key")]
+            key: u64,
+            #[api_kit(header = "If-Match")]
+            if_match: Option<&'static str>,
+        }
+
+        #[derive(Debug, IncomingResponse)]
+        #[api_kit(request = UpdateUser)]
+        #[serde(rename_all = "camelCase")]
+        struct UpdatedUser {
+            user_name: String,
+            #[api_kit(header = "ETag")]
+            etag: HeaderValue,
+            #[api_kit(header = "Location")]
+            location: Option<HeaderValue>,
+        }
+
+        #[test]
+        fn outgoing_request() {
+            assert_eq!(GetUser::METADATA.method, Method::GET);
+            assert_eq!(GetUser::METADATA.path, "/users/{id}");
+            assert_eq!(GetUser::METADATA.summary, Some("Get a user by ID."));
+
+            let req = GetUser {
+                id: 1,
+                fields: None,
+            }
+            .try_into_http_request(
+                "https://example.com",
+                BearerAuth::new(),
+                &"token".to_owned(),
+            )
+            .unwrap();
+            assert_eq!(req.uri(), "https://example.com/users/1");
+            assert_eq!(req.headers()["authorization"], "Bearer token");
+            assert_eq!(req.headers()[ACCEPT], "application/json");
+            assert!(req.body().is_empty());
+
+            let req = GetUser {
+                id: 1,
+                fields: Some("name"),
+            }
+            .try_into_http_request(
+                "https://example.com",
+                BearerAuth::new(),
+                &"token".to_owned(),
+            )
+            .unwrap();
+            assert_eq!(req.uri(), "https://example.com/users/1?fields=name");
+
+            assert_eq!(
+                CreateUser::<()>::METADATA.query_style,
+                QueryStyle::Bracketed
+            );
+            let req = CreateUser {
+                org: "acme",
+                name: "a b",
+                tags: vec!["x", "y"],
+            }
+            .try_into_http_request("https://example.com", (), &())
+            .unwrap();
+            assert_eq!(req.method(), Method::POST);
+            assert_eq!(
+                req.uri(),
+                "https://example.com/orgs/acme/users?tags=x&tags=y"
+            );
+            assert_eq!(
+                req.headers()[CONTENT_TYPE],
+                "application/x-www-form-urlencoded"
+            );
+            assert_eq!(req.headers()[ACCEPT], "application/x-www-form-urlencoded");
+            assert_eq!(&req.body()[..], b"userName=a+b");
+        }
+
+        #[test]
+        fn content_negotiation() {
+            let mut headers = http::HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static("application/problem+json"));
+            let req = GetUser {
+                id: 1,
+                fields: None,
+            }
+            .try_into_http_request(
+                "https://example.com",
+                crate::client::WithHeaders::new(headers, BearerAuth::new()),
+                &"token".to_owned(),
+            )
+            .unwrap();
+            assert_eq!(req.headers()[ACCEPT], "application/problem+json");
+
+            assert_eq!(User::EXPECTED_CONTENT_TYPE, Some("application/json"));
+            let res = http::Response::builder()
+                .header(CONTENT_TYPE, "text/html")
+                .body(Bytes::from_static(b"<html>"))
+                .unwrap();
+            assert!(matches!(
+                User::try_from_http_response_by_status(res).unwrap_err(),
+                FromHttpResponseError::Deserialize(DeserializeError::UnexpectedContentType {
+                    expected: "application/json",
+                    ..
+                })
+            ));
+        }
+
+        #[test]
+        fn header_fields() {
+            let update = |if_match| UpdateUser {
+                id: 1,
+                name: "a",
+                key: 42,
+                if_match,
+            };
+            let req = update(None)
+                .try_into_http_request("https://example.com", (), &())
+                .unwrap();
+            assert_eq!(req.uri(), "https://example.com/users/1");
+            assert_eq!(req.headers()["idempotency-key"], "42");
+            assert!(!req.headers().contains_key("if-match"));
+            assert_eq!(&req.body()[..], br#"{"name":"a"}"#);
+
+            let req = update(Some("\"v1\""))
+                .try_into_http_request("https://example.com", (), &())
+                .unwrap();
+            assert_eq!(req.headers()["if-match"], "\"v1\"");
+
+            let response = |etag: Option<&'static str>| {
+                let mut res = http::Response::builder().header(CONTENT_TYPE, "application/json");
+                if let Some(etag) = etag {
+                    res = res.header("etag", etag);
+                }
+                res.body(Bytes::from_static(br#"{"userName":"a"}"#))
+                    .unwrap()
+            };
+            let res = UpdatedUser::try_from_http_response(response(Some("\"v2\""))).unwrap();
+            assert_eq!(res.user_name, "a");
+            assert_eq!(res.etag, "\"v2\"");
+            assert_eq!(res.location, None);
+
+            assert!(matches!(
+                UpdatedUser::try_from_http_response(response(None)).unwrap_err(),
+                FromHttpResponseError::Deserialize(DeserializeError::MissingHeader(name))
+                    if name == "etag"
+            ));
+        }
+
+        #[test]
+        fn incoming_response() {
+            let res = http::Response::builder()
+                .header(CONTENT_TYPE, "application/json")
+                .body(Bytes::from_static(br#"{"id":1,"name":"a"}"#))
+                .unwrap();
+            assert_eq!(
+                User::try_from_http_response(res).unwrap(),
+                User {
+                    id: 1,
+                    name: "a".into(),
+                }
+            );
+
+            let res = http::Response::builder()
+                .header(CONTENT_TYPE, "text/html")
+                .body(Bytes::from_static(b"<html>"))
+                .unwrap();
+            assert!(matches!(
+                User::try_from_http_response(res).unwrap_err(),
+                FromHttpResponseError::Deserialize(_)
+            ));
+
+            let res = http::Response::builder()
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Bytes::from_static(b"id=2"))
+                .unwrap();
+            assert_eq!(
+                CreatedUser::<&str>::try_from_http_response(res).unwrap().id,
+                2
+            );
+        }
+    }
 }
@@ -1,34 +1,774 @@
-use http::{HeaderName, HeaderValue, Uri};
+use std::{borrow::Cow, fmt, time::Duration};
+
+use http::{header::CONTENT_TYPE, request, HeaderName, HeaderValue, StatusCode, Uri, Version};
 use serde::Serialize;
 
-use crate::{auth::AuthScheme, error::IntoHttpError, url::construct_url};
+use crate::{
+    auth::AuthScheme,
+    error::{DeserializeError, IntoHttpError},
+    url::{construct_matrix_url, construct_url, match_endpoint},
+};
+
+/// How strictly request paths are matched against an endpoint path.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub enum PathMatching {
+    /// A trailing slash on the request or endpoint path is ignored,
+    /// so `/users/` and `/users` match the same endpoint.
+    #[default]
+    Lenient,
+    /// The request path must match the endpoint path exactly, including trailing slashes.
+    Strict,
+}
+
+/// How path arguments without a placeholder in the endpoint path are handled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub enum UnusedPathArgs {
+    /// Extra path arguments are ignored, e.g. when endpoints share a struct of arguments.
+    ///
+    /// A misnamed argument, like `user_id` for the placeholder `{id}`, still fails
+    /// with [`UrlError::KeyNotFound`](crate::error::UrlError::KeyNotFound),
+    /// as its placeholder is left unfilled.
+    #[default]
+    Ignore,
+    /// Every path argument must have a placeholder,
+    /// otherwise [`UrlError::UnusedField`](crate::error::UrlError::UnusedField) is returned.
+    Reject,
+}
+
+/// How the query string of requests to an endpoint is serialized.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub enum QueryStyle {
+    /// Flat `key=value` pairs, serialized with `serde_urlencoded`.
+    ///
+    /// Sequences and nested structs are not supported.
+    #[default]
+    UrlEncoded,
+    /// Sequences are serialized as repeated keys, e.g. `ids=1&ids=2`,
+    /// and nested structs and maps with bracketed keys, e.g. `filter[status]=active`,
+    /// whose brackets are percent-encoded.
+    ///
+    /// Sequences of sequences, structs or maps are not supported.
+    Bracketed,
+}
 
-#[derive(Debug, Clone, Default)]
+/// When client integrations retry requests to an endpoint,
+/// see [`Endpoint::RETRY_POLICY`](crate::Endpoint::RETRY_POLICY).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RetryPolicy<'a> {
+    /// The maximum number of attempts, including the first one.
+    ///
+    /// `0` and `1` mean requests are never retried.
+    pub max_attempts: u32,
+    /// The response statuses that are retried, e.g. `429`, `502` and `503`.
+    pub retry_statuses: &'a [StatusCode],
+    /// Whether requests can be repeated safely although the method isn't idempotent,
+    /// e.g. for `POST` endpoints with an `Idempotency-Key` header.
+    ///
+    /// Requests with idempotent methods, such as `GET`, `PUT` and `DELETE`,
+    /// can always be retried.
+    pub idempotent: bool,
+    /// The longest `Retry-After` delay that is waited for before retrying.
+    ///
+    /// Responses asking for a longer delay are returned instead of being retried.
+    /// [`RetryPolicy::NONE`] allows one minute.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy<'_> {
+    /// Never retry requests.
+    pub const NONE: RetryPolicy<'static> = RetryPolicy {
+        max_attempts: 1,
+        retry_statuses: &[],
+        idempotent: false,
+        max_delay: Duration::from_mins(1),
+    };
+
+    /// Returns `true` if a request with `method` that received `status` on its
+    /// `attempt`th attempt, starting at `1`, should be retried.
+    #[must_use]
+    pub fn should_retry(&self, method: &http::Method, attempt: u32, status: StatusCode) -> bool {
+        attempt < self.max_attempts
+            && self.retry_statuses.contains(&status)
+            && (self.idempotent || method.is_idempotent())
+    }
+}
+
+impl Default for RetryPolicy<'_> {
+    fn default() -> Self {
+        RetryPolicy::NONE
+    }
+}
+
+/// Returns `true` if the media type of a `Content-Type` header value is `expected`.
+///
+/// Only the type and subtype are compared, case-insensitively;
+/// parameters such as `charset` are ignored.
+/// A `*` subtype in `expected` (e.g. `multipart/*`) matches any subtype.
+#[must_use]
+pub fn media_type_matches(header_value: &HeaderValue, expected: &str) -> bool {
+    let Ok(value) = header_value.to_str() else {
+        return false;
+    };
+    let media_type = value.split(';').next().unwrap_or_default().trim();
+    let (Some((ty, subtype)), Some((expected_ty, expected_subtype))) =
+        (media_type.split_once('/'), expected.split_once('/'))
+    else {
+        return false;
+    };
+
+    ty.trim().eq_ignore_ascii_case(expected_ty)
+        && (expected_subtype == "*" || subtype.trim().eq_ignore_ascii_case(expected_subtype))
+}
+
+/// Metadata describing an endpoint.
+///
+/// Fields that most endpoints leave at their defaults can be filled in from
+/// [`Metadata::DEFAULT`], which also works in constants:
+///
+/// ```
+/// # use api_kit::metadata::Metadata;
+/// const METADATA: Metadata<'static> = Metadata {
+///     method: http::Method::POST,
+///     path: "/users",
+///     ..Metadata::DEFAULT
+/// };
+/// ```
+///
+/// New fields are added to this struct over time, e.g. `success_status`,
+/// so literals listing every field break; use `..Metadata::DEFAULT` to keep them compiling.
+#[derive(Clone)]
 pub struct Metadata<'a> {
     pub method: http::Method,
     pub auth: &'a [&'a dyn AuthScheme],
     pub path: &'a str,
+    /// How the query string of requests is serialized.
+    pub query_style: QueryStyle,
+    /// How path arguments without a placeholder in the [`path`](Metadata::path) are handled.
+    pub unused_path_args: UnusedPathArgs,
+    /// Constant headers of requests.
+    ///
+    /// Per-request headers can be set with [`WithHeaders`](crate::client::WithHeaders),
+    /// which overrides these.
     pub headers: &'a [(HeaderName, HeaderValue)],
+    /// The status code a successful response must have.
+    ///
+    /// `None` means any success (`2xx`) status is accepted.
+    pub success_status: Option<StatusCode>,
+    /// The HTTP version requests to the endpoint must use.
+    ///
+    /// `None` uses the default version of the HTTP library.
+    pub version: Option<Version>,
+    /// How long requests to the endpoint may take, e.g. `Some(Duration::from_secs(300))`
+    /// for a long export.
+    ///
+    /// This is a hint for client integrations, such as the `reqwest` and `blocking` clients,
+    /// which apply it to each request. `None` uses the default timeout of the client.
+    pub timeout: Option<Duration>,
+    /// The content type of request bodies, e.g. `application/json`.
+    ///
+    /// `None` means the endpoint doesn't set a content type.
+    pub content_type: Option<HeaderValue>,
+    /// The capability, e.g. an account feature, the endpoint is available with.
+    ///
+    /// `None` means the endpoint is always available.
+    /// See [`Capabilities`](crate::client::Capabilities).
+    pub requires_capability: Option<&'a str>,
+    /// A short human-readable summary of the endpoint, e.g. for generated documentation.
+    ///
+    /// This is not used when making requests.
+    pub summary: Option<&'a str>,
+}
+
+/// Auth schemes are rendered by their [`AuthScheme::scheme`] names.
+impl fmt::Debug for Metadata<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Schemes<'a>(&'a [&'a dyn AuthScheme]);
+
+        impl fmt::Debug for Schemes<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list()
+                    .entries(self.0.iter().map(|auth| auth.scheme()))
+                    .finish()
+            }
+        }
+
+        f.debug_struct("Metadata")
+            .field("method", &self.method)
+            .field("auth", &Schemes(self.auth))
+            .field("path", &self.path)
+            .field("query_style", &self.query_style)
+            .field("unused_path_args", &self.unused_path_args)
+            .field("headers", &self.headers)
+            .field("success_status", &self.success_status)
+            .field("version", &self.version)
+            .field("timeout", &self.timeout)
+            .field("content_type", &self.content_type)
+            .field("requires_capability", &self.requires_capability)
+            .field("summary", &self.summary)
+            .finish()
+    }
+}
+
+impl Default for Metadata<'_> {
+    fn default() -> Self {
+        Metadata::DEFAULT
+    }
 }
 
 impl Metadata<'_> {
+    /// A `GET` endpoint at the root path without authentication,
+    /// with every optional field unset.
+    pub const DEFAULT: Metadata<'static> = Metadata {
+        method: http::Method::GET,
+        auth: &[],
+        path: "",
+        query_style: QueryStyle::UrlEncoded,
+        unused_path_args: UnusedPathArgs::Ignore,
+        headers: &[],
+        success_status: None,
+        version: None,
+        timeout: None,
+        content_type: None,
+        requires_capability: None,
+        summary: None,
+    };
+
     pub fn make_url(
         &self,
         base_url: &str,
         path_args: &impl Serialize,
         query_string: &impl Serialize,
     ) -> Result<Uri, IntoHttpError> {
-        let base_url = base_url.strip_suffix('/').unwrap_or(base_url);
+        let base_url = base_url.trim_end_matches('/');
         Ok(Uri::try_from(construct_url(
             base_url,
             self.path,
             path_args,
             query_string,
+            self.query_style,
+            self.unused_path_args,
+        )?)?)
+    }
+
+    /// Make the URL of the endpoint with `matrix_params` attached to a path `segment`,
+    /// e.g. `/users;version=2/posts` for the segment `0` of `/users/posts`.
+    ///
+    /// The matrix parameters are serialized after the path argument of the segment, if any,
+    /// and before the query string.
+    /// See [`Metadata::make_url`] for the other parameters.
+    pub fn make_matrix_url(
+        &self,
+        base_url: &str,
+        path_args: &impl Serialize,
+        segment: usize,
+        matrix_params: &impl Serialize,
+        query_string: &impl Serialize,
+    ) -> Result<Uri, IntoHttpError> {
+        let base_url = base_url.trim_end_matches('/');
+        Ok(Uri::try_from(construct_matrix_url(
+            base_url,
+            self.path,
+            path_args,
+            segment,
+            matrix_params,
+            query_string,
+            self.query_style,
+            self.unused_path_args,
         )?)?)
     }
 
+    /// Make the URL of the endpoint mounted under a path `prefix`, e.g. `/api` of a gateway.
+    ///
+    /// The `prefix` is inserted between the `base_url` and the endpoint path
+    /// with exactly one slash on each side, so the base URL `https://h` or `https://h/`
+    /// with the prefix `/api`, `api` or `/api/` and the path `/v1/x` yield `https://h/api/v1/x`.
+    pub fn make_prefixed_url(
+        &self,
+        base_url: &str,
+        prefix: &str,
+        path_args: &impl Serialize,
+        query_string: &impl Serialize,
+    ) -> Result<Uri, IntoHttpError> {
+        let prefix = prefix.trim_matches('/');
+        if prefix.is_empty() {
+            return self.make_url(base_url, path_args, query_string);
+        }
+        let base_url = format!("{}/{prefix}", base_url.trim_end_matches('/'));
+        self.make_url(&base_url, path_args, query_string)
+    }
+
+    /// Start building a request to the endpoint at `uri`.
+    ///
+    /// The builder has the method and, if set, the [HTTP version] and [content type]
+    /// of the endpoint.
+    ///
+    /// [HTTP version]: Metadata::version
+    /// [content type]: Metadata::content_type
+    pub fn request_builder(&self, uri: Uri) -> request::Builder {
+        let mut builder = http::Request::builder()
+            .method(self.method.clone())
+            .uri(uri);
+        if let Some(version) = self.version {
+            builder = builder.version(version);
+        }
+        if let Some(content_type) = &self.content_type {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+        builder
+    }
+
+    /// Returns `true` if requests to this endpoint carry their arguments in the body.
+    ///
+    /// `POST`, `PUT` and `PATCH` endpoints carry a body,
+    /// while all other methods take their arguments in the query string.
+    #[must_use]
+    pub const fn expects_body(&self) -> bool {
+        matches!(
+            self.method,
+            http::Method::POST | http::Method::PUT | http::Method::PATCH
+        )
+    }
+
+    /// Returns the [timeout] client integrations apply to requests to this endpoint.
+    ///
+    /// [timeout]: Metadata::timeout
+    #[inline]
+    #[must_use]
+    pub const fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Make the URL of an endpoint that takes its arguments in the query string.
+    ///
+    /// This is for endpoints where [`Metadata::expects_body`] is `false`,
+    /// such as `GET` and `DELETE` endpoints.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IntoHttpError::ArgumentLocation`] if the endpoint expects a body.
+    pub fn make_query_url(
+        &self,
+        base_url: &str,
+        path_args: &impl Serialize,
+        query_string: &impl Serialize,
+    ) -> Result<Uri, IntoHttpError> {
+        if self.expects_body() {
+            return Err(IntoHttpError::ArgumentLocation {
+                method: self.method.clone(),
+                expected: "body",
+            });
+        }
+        self.make_url(base_url, path_args, query_string)
+    }
+
+    /// Make the URL of an endpoint that takes its arguments in the body.
+    ///
+    /// This is for endpoints where [`Metadata::expects_body`] is `true`,
+    /// such as `POST` and `PUT` endpoints.
+    /// Only the path arguments are serialized; no query string is added.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IntoHttpError::ArgumentLocation`] if the endpoint doesn't expect a body.
+    pub fn make_body_url(
+        &self,
+        base_url: &str,
+        path_args: &impl Serialize,
+    ) -> Result<Uri, IntoHttpError> {
+        if !self.expects_body() {
+            return Err(IntoHttpError::ArgumentLocation {
+                method: self.method.clone(),
+                expected: "query",
+            });
+        }
+        self.make_url(base_url, path_args, &())
+    }
+
+    /// Match an incoming request path against the endpoint path.
+    ///
+    /// Returns the percent-decoded path arguments in the order they appear in the endpoint path,
+    /// suitable for [`IncomingRequest::try_from_http_request`],
+    /// or `None` if the path doesn't match.
+    /// Optional parameters like `{tab?}` that are omitted from `path` are not returned.
+    ///
+    /// [`IncomingRequest::try_from_http_request`]: crate::IncomingRequest::try_from_http_request
+    #[must_use]
+    pub fn extract_path_args<'p>(
+        &self,
+        path: &'p str,
+        matching: PathMatching,
+    ) -> Option<Vec<Cow<'p, str>>> {
+        match_endpoint(self.path, path, matching == PathMatching::Strict)
+    }
+
+    /// Check that a success (`2xx`) `status` is the endpoint's expected [success status].
+    ///
+    /// Non-success statuses are not checked.
+    ///
+    /// [success status]: Metadata::success_status
+    pub fn check_success_status(&self, status: StatusCode) -> Result<(), DeserializeError> {
+        match self.success_status {
+            Some(expected) if status.is_success() && status != expected => {
+                Err(DeserializeError::UnexpectedStatus {
+                    expected,
+                    actual: status,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `true` if the endpoint accepts the authentication `scheme`.
+    ///
+    /// Scheme identifiers are compared case-insensitively, see [`canonical_scheme`](crate::auth::canonical_scheme).
     pub fn contains_auth(&self, scheme: &impl AuthScheme) -> bool {
         let scheme_str = scheme.scheme();
-        self.auth.iter().any(|auth| auth.scheme() == scheme_str)
+        self.auth
+            .iter()
+            .any(|auth| auth.scheme().eq_ignore_ascii_case(scheme_str))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+
+    use super::*;
+    use crate::auth::bearer::BearerAuth;
+
+    #[derive(Serialize)]
+    struct Path {
+        id: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Query {
+        page: u32,
+    }
+
+    #[test]
+    fn debug() {
+        let meta = Metadata {
+            method: Method::POST,
+            auth: &[&BearerAuth::new(), &()],
+            path: "/users",
+            ..Metadata::default()
+        };
+        assert_eq!(
+            format!("{meta:?}"),
+            r#"Metadata { method: POST, auth: ["bearer", ""], path: "/users", query_style: UrlEncoded, unused_path_args: Ignore, headers: [], success_status: None, version: None, timeout: None, content_type: None, requires_capability: None, summary: None }"#
+        );
+    }
+
+    #[test]
+    fn make_url_unused_path_args() {
+        #[derive(Serialize)]
+        struct Args {
+            id: u32,
+            name: &'static str,
+        }
+
+        let meta = Metadata {
+            path: "/users/{id}",
+            ..Metadata::DEFAULT
+        };
+        let args = Args { id: 1, name: "a" };
+        let url = meta.make_url("https://example.com", &args, &()).unwrap();
+        assert_eq!(url, "https://example.com/users/1");
+
+        let meta = Metadata {
+            unused_path_args: UnusedPathArgs::Reject,
+            ..meta
+        };
+        assert!(matches!(
+            meta.make_url("https://example.com", &args, &())
+                .unwrap_err(),
+            IntoHttpError::Url(crate::error::UrlError::UnusedField("name"))
+        ));
+    }
+
+    #[test]
+    fn make_url_root() {
+        for path in ["", "/"] {
+            let meta = Metadata {
+                path,
+                ..Metadata::default()
+            };
+            for base_url in ["https://example.com", "https://example.com/"] {
+                let url = meta.make_url(base_url, &(), &()).unwrap();
+                assert_eq!(url, "https://example.com/");
+                assert_eq!(
+                    meta.extract_path_args(url.path(), PathMatching::Strict),
+                    (path == "/").then(Vec::new)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn media_type_matches() {
+        let matches =
+            |value, expected| super::media_type_matches(&HeaderValue::from_static(value), expected);
+        assert!(matches("application/json", "application/json"));
+        assert!(matches(
+            "application/json; charset=utf-8",
+            "application/json"
+        ));
+        assert!(matches(
+            "Application/JSON ;charset=\"UTF-8\"",
+            "application/json"
+        ));
+        assert!(matches(
+            "multipart/form-data; boundary=----abc",
+            "multipart/form-data"
+        ));
+        assert!(matches("multipart/mixed; boundary=batch", "multipart/*"));
+        assert!(!matches("application/problem+json", "application/json"));
+        assert!(!matches("text/json", "application/json"));
+        assert!(!matches("application", "application/json"));
+        assert!(!matches("", "application/json"));
+    }
+
+    #[test]
+    fn expects_body() {
+        let meta = |method| Metadata {
+            method,
+            ..Metadata::default()
+        };
+        assert!(meta(Method::POST).expects_body());
+        assert!(meta(Method::PUT).expects_body());
+        assert!(meta(Method::PATCH).expects_body());
+        assert!(!meta(Method::GET).expects_body());
+        assert!(!meta(Method::DELETE).expects_body());
+        assert!(!meta(Method::HEAD).expects_body());
+    }
+
+    #[test]
+    fn extract_path_args() {
+        let meta = Metadata {
+            path: "/users/{id}",
+            ..Metadata::default()
+        };
+        for path in ["/users/1", "/users/1/"] {
+            assert_eq!(
+                meta.extract_path_args(path, PathMatching::default()),
+                Some(vec![Cow::Borrowed("1")])
+            );
+        }
+        assert_eq!(
+            meta.extract_path_args("/users/1", PathMatching::Strict),
+            Some(vec![Cow::Borrowed("1")])
+        );
+        assert_eq!(
+            meta.extract_path_args("/users/1/", PathMatching::Strict),
+            None
+        );
+    }
+
+    #[test]
+    fn request_builder() {
+        let meta = Metadata {
+            method: Method::PUT,
+            ..Metadata::default()
+        };
+        let req = meta
+            .request_builder(Uri::from_static("https://example.com/"))
+            .body(())
+            .unwrap();
+        assert_eq!(req.method(), Method::PUT);
+        assert_eq!(req.uri(), "https://example.com/");
+        assert_eq!(req.version(), Version::default());
+
+        assert!(!req.headers().contains_key(CONTENT_TYPE));
+
+        let meta = Metadata {
+            version: Some(Version::HTTP_2),
+            content_type: Some(HeaderValue::from_static("application/json")),
+            ..meta
+        };
+        let req = meta
+            .request_builder(Uri::from_static("https://example.com/"))
+            .body(())
+            .unwrap();
+        assert_eq!(req.version(), Version::HTTP_2);
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+    }
+
+    #[test]
+    fn check_success_status() {
+        let any = Metadata::default();
+        any.check_success_status(StatusCode::OK).unwrap();
+        any.check_success_status(StatusCode::CREATED).unwrap();
+
+        let created = Metadata {
+            success_status: Some(StatusCode::CREATED),
+            ..Metadata::default()
+        };
+        created.check_success_status(StatusCode::CREATED).unwrap();
+        created.check_success_status(StatusCode::NOT_FOUND).unwrap();
+        assert!(matches!(
+            created.check_success_status(StatusCode::OK).unwrap_err(),
+            DeserializeError::UnexpectedStatus {
+                expected: StatusCode::CREATED,
+                actual: StatusCode::OK,
+            }
+        ));
+    }
+
+    #[test]
+    fn make_query_and_body_url() {
+        let get = Metadata {
+            method: Method::GET,
+            path: "/items/{id}",
+            ..Metadata::default()
+        };
+        let url = get
+            .make_query_url("https://example.com/", &Path { id: 1 }, &Query { page: 2 })
+            .unwrap();
+        assert_eq!(url, "https://example.com/items/1?page=2");
+
+        let post = Metadata {
+            method: Method::POST,
+            path: "/items/{id}",
+            ..Metadata::default()
+        };
+        let url = post
+            .make_body_url("https://example.com", &Path { id: 1 })
+            .unwrap();
+        assert_eq!(url, "https://example.com/items/1");
+
+        // Arguments in the wrong place are rejected
+        let err = post
+            .make_query_url("https://example.com", &Path { id: 1 }, &Query { page: 2 })
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            IntoHttpError::ArgumentLocation {
+                method: Method::POST,
+                expected: "body"
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "`POST` endpoints take their arguments in the body"
+        );
+        assert!(matches!(
+            get.make_body_url("https://example.com", &Path { id: 1 }),
+            Err(IntoHttpError::ArgumentLocation {
+                method: Method::GET,
+                expected: "query"
+            })
+        ));
+    }
+
+    #[test]
+    fn query_styles() {
+        #[derive(Serialize)]
+        struct Search {
+            ids: Vec<String>,
+            after: Option<u32>,
+        }
+
+        let search = Search {
+            ids: vec!["1".to_owned(), "2".to_owned()],
+            after: None,
+        };
+        let meta = Metadata {
+            path: "/items",
+            ..Metadata::default()
+        };
+        assert!(matches!(
+            meta.make_url("https://example.com", &(), &search),
+            Err(IntoHttpError::Query(_))
+        ));
+
+        let meta = Metadata {
+            query_style: QueryStyle::Bracketed,
+            ..meta
+        };
+        let url = meta.make_url("https://example.com", &(), &search).unwrap();
+        assert_eq!(url, "https://example.com/items?ids=1&ids=2");
+    }
+
+    #[test]
+    fn make_prefixed_url() {
+        let meta = Metadata {
+            path: "/v1/items/{id}",
+            ..Metadata::default()
+        };
+        for (base_url, prefix) in [
+            ("https://h", "/api"),
+            ("https://h/", "/api"),
+            ("https://h//", "api/"),
+            ("https://h/api", ""),
+            ("https://h/api/", "/"),
+        ] {
+            let url = meta
+                .make_prefixed_url(base_url, prefix, &Path { id: 1 }, &Query { page: 2 })
+                .unwrap();
+            assert_eq!(
+                url, "https://h/api/v1/items/1?page=2",
+                "{base_url} {prefix}"
+            );
+        }
+        let url = meta
+            .make_prefixed_url("https://h/gw", "/api/", &Path { id: 1 }, &())
+            .unwrap();
+        assert_eq!(url, "https://h/gw/api/v1/items/1");
+
+        // Extra trailing slashes of the base URL don't double the slash
+        let url = meta.make_url("https://h//", &Path { id: 1 }, &()).unwrap();
+        assert_eq!(url, "https://h/v1/items/1");
+    }
+
+    #[test]
+    fn make_matrix_url() {
+        #[derive(Serialize)]
+        struct Matrix {
+            version: u32,
+        }
+
+        let meta = Metadata {
+            path: "/v1/items/{id}",
+            query_style: QueryStyle::Bracketed,
+            ..Metadata::default()
+        };
+        let url = meta
+            .make_matrix_url(
+                "https://h/",
+                &Path { id: 1 },
+                1,
+                &Matrix { version: 2 },
+                &Query { page: 2 },
+            )
+            .unwrap();
+        assert_eq!(url, "https://h/v1/items;version=2/1?page=2");
+    }
+
+    #[test]
+    fn retry_policy() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            retry_statuses: &[
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ],
+            idempotent: false,
+            max_delay: Duration::from_mins(1),
+        };
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        assert!(policy.should_retry(&http::Method::GET, 1, unavailable));
+        assert!(policy.should_retry(&http::Method::PUT, 2, unavailable));
+        assert!(!policy.should_retry(&http::Method::GET, 3, unavailable));
+        assert!(!policy.should_retry(&http::Method::GET, 1, StatusCode::BAD_GATEWAY));
+        assert!(!policy.should_retry(&http::Method::POST, 1, unavailable));
+        assert!(RetryPolicy {
+            idempotent: true,
+            ..policy
+        }
+        .should_retry(&http::Method::POST, 1, unavailable));
+
+        assert_eq!(RetryPolicy::default(), RetryPolicy::NONE);
+        assert!(!RetryPolicy::NONE.should_retry(&http::Method::GET, 1, unavailable));
     }
 }
@@ -0,0 +1,601 @@
+//! Helpers for inspecting HTTP responses.
+
+use http::{
+    header::{CONTENT_LENGTH, CONTENT_TYPE, LOCATION},
+    HeaderValue, StatusCode, Uri,
+};
+
+use crate::{error::DeserializeError, metadata::media_type_matches};
+
+/// The class of a status code, given by its first digit.
+///
+/// See [RFC 9110 section 15](https://www.rfc-editor.org/rfc/rfc9110#section-15).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum StatusClass {
+    /// `1xx` statuses.
+    Informational,
+    /// `2xx` statuses.
+    Success,
+    /// `3xx` statuses.
+    Redirection,
+    /// `4xx` statuses.
+    ClientError,
+    /// `5xx` statuses.
+    ServerError,
+}
+
+/// Returns the class of `status`.
+///
+/// Nonstandard statuses of `600` and above are classified as server errors.
+#[must_use]
+pub const fn classify(status: StatusCode) -> StatusClass {
+    match status.as_u16() {
+        100..200 => StatusClass::Informational,
+        200..300 => StatusClass::Success,
+        300..400 => StatusClass::Redirection,
+        400..500 => StatusClass::ClientError,
+        _ => StatusClass::ServerError,
+    }
+}
+
+/// Check that the response body is at least as long as its `Content-Length` header.
+///
+/// Responses without a valid `Content-Length` header (e.g. chunked responses) are accepted,
+/// as are responses whose status forbids a body (`1xx`, `204 No Content`, `304 Not Modified`).
+///
+/// Responses to `HEAD` requests declare the length of the body they would have returned,
+/// so this check must not be used for them.
+pub fn check_content_length<T: AsRef<[u8]>>(
+    res: &http::Response<T>,
+) -> Result<(), DeserializeError> {
+    let status = res.status();
+    if status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+    {
+        return Ok(());
+    }
+
+    let Some(expected) = res
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+    else {
+        return Ok(());
+    };
+
+    let actual = res.body().as_ref().len();
+    if actual < expected {
+        return Err(DeserializeError::TruncatedBody { expected, actual });
+    }
+
+    Ok(())
+}
+
+/// Check that the media type of the response is `expected`, e.g. `application/json`.
+///
+/// Parameters of the `Content-Type` header, such as `charset`, are ignored
+/// (see [`media_type_matches`]).
+/// Responses with an empty body are accepted regardless of their content type.
+///
+/// This turns e.g. HTML error pages returned with a `200 OK` status into a
+/// [`DeserializeError::UnexpectedContentType`] error, rather than a parse error.
+pub fn check_content_type<T: AsRef<[u8]>>(
+    res: &http::Response<T>,
+    expected: &'static str,
+) -> Result<(), DeserializeError> {
+    if res.body().as_ref().is_empty() {
+        return Ok(());
+    }
+
+    let actual = res.headers().get(CONTENT_TYPE);
+    if actual.is_some_and(|actual| media_type_matches(actual, expected)) {
+        Ok(())
+    } else {
+        Err(DeserializeError::UnexpectedContentType {
+            expected,
+            actual: actual.cloned(),
+        })
+    }
+}
+
+/// Buffer a streamed response body into [`Bytes`](bytes::Bytes).
+///
+/// This is the default implementation of
+/// [`IncomingResponse::try_from_http_response_stream`](crate::IncomingResponse::try_from_http_response_stream).
+/// Errors of the stream are returned as [`DeserializeError::Body`].
+#[cfg(feature = "stream")]
+pub async fn collect_body<S, E>(
+    res: http::Response<S>,
+) -> Result<http::Response<bytes::Bytes>, DeserializeError>
+where
+    S: futures_core::Stream<Item = Result<bytes::Bytes, E>>,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let (parts, stream) = res.into_parts();
+    let mut stream = std::pin::pin!(stream);
+    let mut body = bytes::BytesMut::new();
+    while let Some(chunk) = std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        body.extend_from_slice(&chunk.map_err(|err| DeserializeError::Body(err.into()))?);
+    }
+    Ok(http::Response::from_parts(parts, body.freeze()))
+}
+
+/// Returns the URI of the resource created by a `201 Created` response.
+///
+/// The URI is read from the `Location` header.
+/// Relative references are resolved against `request_uri`, the URI of the request
+/// (see [RFC 3986 section 5.2](https://datatracker.ietf.org/doc/html/rfc3986#section-5.2)),
+/// and fragments are discarded.
+///
+/// Returns `None` if the response isn't `201 Created`, or has no valid `Location` header.
+///
+/// [`IncomingResponse`](crate::IncomingResponse) implementations don't see the request,
+/// so creation endpoints should keep the raw `Location` header in a field of the response
+/// and have callers resolve it with the URI the request was sent to.
+#[must_use]
+pub fn created_location<T>(res: &http::Response<T>, request_uri: &Uri) -> Option<Uri> {
+    if res.status() != StatusCode::CREATED {
+        return None;
+    }
+    let location = res.headers().get(LOCATION)?.to_str().ok()?;
+    resolve_reference(request_uri, location)
+}
+
+/// Resolve a URI reference against a base URI.
+fn resolve_reference(base: &Uri, reference: &str) -> Option<Uri> {
+    let reference = reference.split_once('#').map_or(reference, |(r, _)| r);
+
+    // Absolute URI
+    if reference
+        .split_once(':')
+        .is_some_and(|(scheme, _)| is_scheme(scheme))
+    {
+        return reference.parse().ok();
+    }
+
+    let scheme = base.scheme_str()?;
+    let authority = base.authority()?.as_str();
+
+    if let Some(rest) = reference.strip_prefix("//") {
+        // Network-path reference
+        let slash = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(slash);
+        let (path, query) = split_query(path);
+        return join(scheme, authority, &remove_dot_segments(path), query)
+            .parse()
+            .ok();
+    }
+
+    let resolved = if reference.is_empty() {
+        join(scheme, authority, base.path(), base.query())
+    } else if let Some(query) = reference.strip_prefix('?') {
+        join(scheme, authority, base.path(), Some(query))
+    } else {
+        let (path, query) = split_query(reference);
+        let path = if path.starts_with('/') {
+            remove_dot_segments(path)
+        } else {
+            // Merge with the directory of the base path
+            let dir = base.path().rfind('/').map_or("/", |i| &base.path()[..=i]);
+            remove_dot_segments(&format!("{dir}{path}"))
+        };
+        join(scheme, authority, &path, query)
+    };
+
+    resolved.parse().ok()
+}
+
+fn is_scheme(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn split_query(s: &str) -> (&str, Option<&str>) {
+    s.split_once('?').map_or((s, None), |(p, q)| (p, Some(q)))
+}
+
+fn join(scheme: &str, authority: &str, path: &str, query: Option<&str>) -> String {
+    let path = if path.is_empty() { "/" } else { path };
+    let mut uri = format!("{scheme}://{authority}{path}");
+    if let Some(query) = query {
+        uri.push('?');
+        uri.push_str(query);
+    }
+    uri
+}
+
+/// Remove `.` and `..` segments from a path (RFC 3986 section 5.2.4).
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.split('/').peekable();
+    // Skip the empty segment before the leading slash
+    if path.starts_with('/') {
+        parts.next();
+    }
+    while let Some(part) = parts.next() {
+        let last = parts.peek().is_none();
+        match part {
+            "." => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." => {
+                segments.pop();
+                if last {
+                    segments.push("");
+                }
+            }
+            part => segments.push(part),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// A link of a `Link` header (see [RFC 8288](https://datatracker.ietf.org/doc/html/rfc8288)).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Link {
+    /// The link target, as sent. It may be a relative reference.
+    pub target: String,
+    /// The relation types of the `rel` parameter, lowercased.
+    pub rel: Vec<String>,
+    /// The other parameters, with lowercased names and unquoted values,
+    /// in the order they were sent.
+    pub params: Vec<(String, String)>,
+}
+
+impl Link {
+    /// Returns whether the link has the relation type `rel`, e.g. `next`.
+    #[must_use]
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rel.iter().any(|r| r.eq_ignore_ascii_case(rel))
+    }
+
+    /// Returns the value of the first parameter named `name`, e.g. `title`.
+    #[must_use]
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse the comma-separated links of a `Link` header value.
+///
+/// Only the first `rel` parameter of a link is used, as required by RFC 8288.
+/// Empty list elements are skipped.
+/// Links with a missing `<` or `>`, invalid parameters, or unterminated quoted strings
+/// are a [`DeserializeError::InvalidLinkHeader`] error.
+pub fn parse_link_header(value: &HeaderValue) -> Result<Vec<Link>, DeserializeError> {
+    let mut rest = value.to_str()?;
+    let mut links = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c| c == ',' || is_ows(c));
+        if rest.is_empty() {
+            return Ok(links);
+        }
+
+        let (target, after) = rest
+            .strip_prefix('<')
+            .and_then(|rest| rest.split_once('>'))
+            .ok_or(DeserializeError::InvalidLinkHeader)?;
+        rest = after;
+        let mut link = Link {
+            target: target.to_owned(),
+            ..Link::default()
+        };
+        let mut has_rel = false;
+        while let Some(after) = rest.trim_start_matches(is_ows).strip_prefix(';') {
+            let (name, value, after) = parse_link_param(after.trim_start_matches(is_ows))
+                .ok_or(DeserializeError::InvalidLinkHeader)?;
+            rest = after;
+            if name != "rel" {
+                link.params.push((name, value));
+            } else if !has_rel {
+                has_rel = true;
+                link.rel = value
+                    .split_ascii_whitespace()
+                    .map(str::to_ascii_lowercase)
+                    .collect();
+            }
+        }
+
+        rest = rest.trim_start_matches(is_ows);
+        if !(rest.is_empty() || rest.starts_with(',')) {
+            return Err(DeserializeError::InvalidLinkHeader);
+        }
+        links.push(link);
+    }
+}
+
+/// Parse a `name[=value]` link parameter, returning the lowercased name,
+/// the unquoted value, and the rest of the header value.
+fn parse_link_param(s: &str) -> Option<(String, String, &str)> {
+    let name_len = s.find(|c| !is_tchar(c)).unwrap_or(s.len());
+    if name_len == 0 {
+        return None;
+    }
+    let (name, rest) = s.split_at(name_len);
+    let name = name.to_ascii_lowercase();
+
+    let Some(rest) = rest.trim_start_matches(is_ows).strip_prefix('=') else {
+        return Some((name, String::new(), rest));
+    };
+    let rest = rest.trim_start_matches(is_ows);
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((name, value, &quoted[i + 1..])),
+                '\\' => value.push(chars.next()?.1),
+                c => value.push(c),
+            }
+        }
+        // Unterminated quoted string
+        None
+    } else {
+        let value_len = rest.find(|c| !is_tchar(c)).unwrap_or(rest.len());
+        if value_len == 0 {
+            return None;
+        }
+        let (value, rest) = rest.split_at(value_len);
+        Some((name, value.to_owned(), rest))
+    }
+}
+
+/// Optional whitespace (RFC 9110 section 5.6.3).
+const fn is_ows(c: char) -> bool {
+    matches!(c, ' ' | '\t')
+}
+
+/// Token characters (RFC 9110 section 5.6.2).
+const fn is_tchar(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(
+            c,
+            '!' | '#'
+                | '$'
+                | '%'
+                | '&'
+                | '\''
+                | '*'
+                | '+'
+                | '-'
+                | '.'
+                | '^'
+                | '_'
+                | '`'
+                | '|'
+                | '~'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_class() {
+        let class = |status| classify(StatusCode::from_u16(status).unwrap());
+        assert_eq!(class(101), StatusClass::Informational);
+        assert_eq!(class(204), StatusClass::Success);
+        assert_eq!(class(399), StatusClass::Redirection);
+        assert_eq!(class(400), StatusClass::ClientError);
+        assert_eq!(class(503), StatusClass::ServerError);
+        assert_eq!(class(999), StatusClass::ServerError);
+    }
+
+    fn response(
+        status: u16,
+        content_length: Option<&'static str>,
+        body: &'static str,
+    ) -> http::Response<&'static str> {
+        let mut res = http::Response::new(body);
+        *res.status_mut() = StatusCode::from_u16(status).unwrap();
+        if let Some(len) = content_length {
+            res.headers_mut()
+                .insert(CONTENT_LENGTH, HeaderValue::from_static(len));
+        }
+        res
+    }
+
+    #[test]
+    fn content_length_matches() {
+        check_content_length(&response(200, Some("5"), "hello")).unwrap();
+        check_content_length(&response(200, None, "hello")).unwrap();
+        check_content_length(&response(200, Some("invalid"), "hello")).unwrap();
+    }
+
+    #[test]
+    fn content_length_truncated() {
+        let err = check_content_length(&response(200, Some("10"), "hello")).unwrap_err();
+        assert!(matches!(
+            err,
+            DeserializeError::TruncatedBody {
+                expected: 10,
+                actual: 5
+            }
+        ));
+    }
+
+    #[test]
+    fn content_length_bodyless_status() {
+        check_content_length(&response(204, Some("10"), "")).unwrap();
+        check_content_length(&response(304, Some("10"), "")).unwrap();
+    }
+
+    #[test]
+    fn content_type() {
+        let json = |content_type: Option<&'static str>, body| {
+            let mut res = http::Response::new(body);
+            if let Some(content_type) = content_type {
+                res.headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+            }
+            check_content_type(&res, "application/json")
+        };
+
+        json(Some("application/json"), "{}").unwrap();
+        json(Some("application/json; charset=utf-8"), "{}").unwrap();
+        json(None, "").unwrap();
+        assert!(matches!(
+            json(Some("text/html"), "<html></html>").unwrap_err(),
+            DeserializeError::UnexpectedContentType {
+                expected: "application/json",
+                actual: Some(actual),
+            } if actual == "text/html"
+        ));
+        assert!(matches!(
+            json(None, "{}").unwrap_err(),
+            DeserializeError::UnexpectedContentType { actual: None, .. }
+        ));
+    }
+
+    fn created(location: &'static str) -> http::Response<()> {
+        let mut res = http::Response::new(());
+        *res.status_mut() = StatusCode::CREATED;
+        res.headers_mut()
+            .insert(LOCATION, HeaderValue::from_static(location));
+        res
+    }
+
+    #[test]
+    fn created_location_absolute() {
+        let base = Uri::from_static("https://example.com/api/users");
+        assert_eq!(
+            created_location(&created("https://other.com/users/1"), &base).unwrap(),
+            "https://other.com/users/1"
+        );
+        assert_eq!(
+            created_location(&created("/api/users/1"), &base).unwrap(),
+            "https://example.com/api/users/1"
+        );
+        assert_eq!(
+            created_location(&created("//cdn.example.com/a/./b?x=1#frag"), &base).unwrap(),
+            "https://cdn.example.com/a/b?x=1"
+        );
+    }
+
+    #[test]
+    fn created_location_relative() {
+        let base = Uri::from_static("https://example.com/api/users?page=1");
+        assert_eq!(
+            created_location(&created("users/1"), &base).unwrap(),
+            "https://example.com/api/users/1"
+        );
+        assert_eq!(
+            created_location(&created("../groups/2"), &base).unwrap(),
+            "https://example.com/groups/2"
+        );
+        assert_eq!(
+            created_location(&created("../../../x"), &base).unwrap(),
+            "https://example.com/x"
+        );
+        assert_eq!(
+            created_location(&created("./"), &base).unwrap(),
+            "https://example.com/api/"
+        );
+        assert_eq!(
+            created_location(&created("?page=2"), &base).unwrap(),
+            "https://example.com/api/users?page=2"
+        );
+        assert_eq!(
+            created_location(&created("#top"), &base).unwrap(),
+            "https://example.com/api/users?page=1"
+        );
+    }
+
+    #[test]
+    fn created_location_missing() {
+        let base = Uri::from_static("https://example.com/users");
+
+        let mut res = created("/users/1");
+        *res.status_mut() = StatusCode::OK;
+        assert_eq!(created_location(&res, &base), None);
+
+        let mut res = created("/users/1");
+        res.headers_mut().remove(LOCATION);
+        assert_eq!(created_location(&res, &base), None);
+
+        // Relative references need an absolute base
+        assert_eq!(
+            created_location(&created("/users/1"), &Uri::from_static("/users")),
+            None
+        );
+    }
+
+    #[test]
+    fn link_header() {
+        let links = parse_link_header(&HeaderValue::from_static(
+            "<https://api.github.com/repositories/1300192/issues?page=2>; rel=\"prev\", \
+             <https://api.github.com/repositories/1300192/issues?page=4>; rel=\"next\", \
+             <https://api.github.com/repositories/1300192/issues?page=515>; rel=\"last\", \
+             <https://api.github.com/repositories/1300192/issues?page=1>; rel=\"first\"",
+        ))
+        .unwrap();
+        let rels: Vec<_> = links.iter().map(|link| link.rel.join(" ")).collect();
+        assert_eq!(rels, ["prev", "next", "last", "first"]);
+        assert_eq!(
+            links
+                .iter()
+                .find(|link| link.has_rel("NEXT"))
+                .unwrap()
+                .target,
+            "https://api.github.com/repositories/1300192/issues?page=4"
+        );
+
+        let links = parse_link_header(&HeaderValue::from_static(
+            r#"</a,b>;title="x, \"y\"";REL="Next Last" ; rel=prev;crossorigin, ,</c>"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            links,
+            [
+                Link {
+                    target: "/a,b".into(),
+                    rel: vec!["next".into(), "last".into()],
+                    params: vec![
+                        ("title".into(), r#"x, "y""#.into()),
+                        ("crossorigin".into(), String::new()),
+                    ],
+                },
+                Link {
+                    target: "/c".into(),
+                    ..Link::default()
+                },
+            ]
+        );
+        assert_eq!(links[0].param("Title"), Some(r#"x, "y""#));
+        assert_eq!(links[1].param("title"), None);
+        assert_eq!(
+            parse_link_header(&HeaderValue::from_static("")).unwrap(),
+            []
+        );
+    }
+
+    #[test]
+    fn link_header_malformed() {
+        for value in [
+            "https://example.com; rel=next",
+            "<https://example.com; rel=next",
+            "<https://example.com>; rel=\"next",
+            "<https://example.com>; rel=",
+            "<https://example.com>; =next",
+            "<https://example.com> rel=next",
+            "<https://example.com>; rel=next <https://example.org>",
+        ] {
+            assert!(
+                matches!(
+                    parse_link_header(&HeaderValue::from_static(value)),
+                    Err(DeserializeError::InvalidLinkHeader)
+                ),
+                "{value}"
+            );
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Rewriting the URI of outgoing requests.
+//!
+//! A [`UriRewriter`] receives the URI constructed from the endpoint [`Metadata`]
+//! and returns a modified one, e.g. to route tenants to different hosts
+//! or to add a path prefix at runtime.
+//!
+//! Rewriters are applied by wrapping an [`Authenticator`] in a [`Rewrite`].
+//! The URI is rewritten right before the inner authenticator runs,
+//! so signing authenticators see the final URI.
+//!
+//! [`Metadata`]: crate::metadata::Metadata
+
+use std::fmt;
+
+use bytes::BytesMut;
+use http::{HeaderName, Request, Uri};
+
+use crate::{
+    auth::{AuthScheme, Authenticator},
+    error::IntoHttpError,
+};
+
+/// Rewrites the URI of an outgoing request.
+pub trait UriRewriter {
+    /// Rewrite the `uri` of an outgoing request.
+    fn rewrite(&self, uri: Uri) -> Result<Uri, IntoHttpError>;
+}
+
+impl<F> UriRewriter for F
+where
+    F: Fn(Uri) -> Result<Uri, IntoHttpError>,
+{
+    fn rewrite(&self, uri: Uri) -> Result<Uri, IntoHttpError> {
+        self(uri)
+    }
+}
+
+/// An [`Authenticator`] that rewrites the request URI before authenticating.
+///
+/// The scheme and authentication data are those of the inner authenticator.
+pub struct Rewrite<R, A> {
+    rewriter: R,
+    inner: A,
+}
+
+impl<R, A> Rewrite<R, A> {
+    /// Wraps the `inner` authenticator, rewriting URIs with `rewriter` before authentication.
+    #[inline]
+    #[must_use]
+    pub const fn new(rewriter: R, inner: A) -> Self {
+        Self { rewriter, inner }
+    }
+}
+
+impl<R, A: fmt::Debug> fmt::Debug for Rewrite<R, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rewrite")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R, A: AuthScheme> AuthScheme for Rewrite<R, A> {
+    fn scheme(&self) -> &'static str {
+        self.inner.scheme()
+    }
+
+    fn auth_headers(&self) -> &'static [HeaderName] {
+        self.inner.auth_headers()
+    }
+}
+
+impl<R: UriRewriter, A: Authenticator> Authenticator for Rewrite<R, A> {
+    type AuthData = A::AuthData;
+
+    fn authenticate(
+        &self,
+        req: &mut Request<BytesMut>,
+        data: &Self::AuthData,
+    ) -> Result<(), IntoHttpError> {
+        let uri = std::mem::take(req.uri_mut());
+        *req.uri_mut() = self.rewriter.rewrite(uri)?;
+        self.inner.authenticate(req, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header::HOST, uri::Authority, HeaderValue};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct HostFromUri;
+
+    impl AuthScheme for HostFromUri {
+        fn scheme(&self) -> &'static str {
+            "host"
+        }
+    }
+
+    impl Authenticator for HostFromUri {
+        type AuthData = ();
+
+        fn authenticate(
+            &self,
+            req: &mut Request<BytesMut>,
+            _data: &Self::AuthData,
+        ) -> Result<(), IntoHttpError> {
+            let host = req.uri().host().unwrap_or_default().to_owned();
+            req.headers_mut()
+                .insert(HOST, HeaderValue::from_str(&host)?);
+            Ok(())
+        }
+    }
+
+    fn tenant_host(uri: Uri) -> Result<Uri, IntoHttpError> {
+        let mut parts = uri.into_parts();
+        parts.authority = Some(Authority::from_static("tenant.example.com"));
+        Ok(Uri::from_parts(parts).map_err(http::Error::from)?)
+    }
+
+    #[test]
+    fn rewrite_before_auth() {
+        let auth = Rewrite::new(tenant_host, HostFromUri);
+        assert_eq!(auth.scheme(), "host");
+
+        let mut req = Request::builder()
+            .uri("https://example.com/users/1?page=2")
+            .body(BytesMut::new())
+            .unwrap();
+        auth.authenticate(&mut req, &()).unwrap();
+
+        assert_eq!(req.uri(), "https://tenant.example.com/users/1?page=2");
+        assert_eq!(req.headers()[HOST], "tenant.example.com");
+    }
+
+    #[test]
+    fn auth_headers() {
+        use http::header::AUTHORIZATION;
+
+        use crate::auth::bearer::BearerAuth;
+
+        assert!(Rewrite::new(tenant_host, HostFromUri)
+            .auth_headers()
+            .is_empty());
+        assert_eq!(
+            Rewrite::new(tenant_host, BearerAuth::new()).auth_headers(),
+            [AUTHORIZATION]
+        );
+    }
+
+    #[test]
+    fn rewrite_error() {
+        let auth = Rewrite::new(|_| Err(IntoHttpError::MissingAuth), ());
+        let mut req = Request::new(BytesMut::new());
+        assert!(matches!(
+            auth.authenticate(&mut req, &()).unwrap_err(),
+            IntoHttpError::MissingAuth
+        ));
+    }
+}
@@ -0,0 +1,4 @@
+//! Server-side helpers for handling endpoint requests.
+
+#[cfg(feature = "axum")]
+pub mod axum;
@@ -0,0 +1,249 @@
+//! Integration with the [`axum`] web framework.
+//!
+//! [`Endpoint`] extracts [`IncomingRequest`]s in handlers,
+//! and wraps [`OutgoingResponse`]s so handlers can return them:
+//!
+//! ```ignore
+//! async fn put_item(Endpoint(req): Endpoint<PutItem>) -> Endpoint<PutItemResponse> {
+//!     Endpoint(PutItemResponse)
+//! }
+//!
+//! let app = Router::new().route("/items/{id}", put(put_item));
+//! ```
+
+use axum::{
+    body::Body,
+    extract::{
+        rejection::{BytesRejection, RawPathParamsRejection},
+        FromRequest, FromRequestParts, RawPathParams, Request,
+    },
+    response::{IntoResponse, Response},
+};
+use bytes::{Bytes, BytesMut};
+use http::StatusCode;
+
+use crate::{error::FromHttpRequestError, IncomingRequest, OutgoingResponse};
+
+/// An extractor of [`IncomingRequest`]s, and a response of [`OutgoingResponse`]s.
+///
+/// When extracting, the path arguments are taken from the route parameters, in order,
+/// and the request is converted with [`IncomingRequest::try_from_http_request`].
+/// The route parameters must therefore be in the same order as in the endpoint path.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Endpoint<T>(pub T);
+
+/// Rejection of the [`Endpoint`] extractor.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Rejection {
+    /// The request couldn't be converted.
+    Request(FromHttpRequestError),
+    /// The route parameters couldn't be extracted.
+    Path(RawPathParamsRejection),
+    /// The body couldn't be read.
+    Body(BytesRejection),
+}
+
+impl From<FromHttpRequestError> for Rejection {
+    fn from(err: FromHttpRequestError) -> Self {
+        Self::Request(err)
+    }
+}
+
+impl From<RawPathParamsRejection> for Rejection {
+    fn from(err: RawPathParamsRejection) -> Self {
+        Self::Path(err)
+    }
+}
+
+impl From<BytesRejection> for Rejection {
+    fn from(err: BytesRejection) -> Self {
+        Self::Body(err)
+    }
+}
+
+/// Conversion errors are responded to with their message and
+/// `400 Bad Request` for malformed requests, `404 Not Found` for path mismatches,
+/// `405 Method Not Allowed` for method mismatches,
+/// and `401 Unauthorized` for signature and timestamp errors.
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Request(err) => {
+                let status = match err {
+                    FromHttpRequestError::Deserialize(_) => StatusCode::BAD_REQUEST,
+                    FromHttpRequestError::MethodMismatch { .. } => StatusCode::METHOD_NOT_ALLOWED,
+                    FromHttpRequestError::PathMismatch => StatusCode::NOT_FOUND,
+                    FromHttpRequestError::InvalidSignature
+                    | FromHttpRequestError::InvalidTimestamp
+                    | FromHttpRequestError::Expired
+                    | FromHttpRequestError::Replayed => StatusCode::UNAUTHORIZED,
+                };
+                (status, err.to_string()).into_response()
+            }
+            Self::Path(err) => err.into_response(),
+            Self::Body(err) => err.into_response(),
+        }
+    }
+}
+
+impl<T, S> FromRequest<S> for Endpoint<T>
+where
+    T: IncomingRequest,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+        let path_args: Vec<String> = RawPathParams::from_request_parts(&mut parts, state)
+            .await?
+            .iter()
+            .map(|(_, value)| value.to_owned())
+            .collect();
+
+        let mut head = http::Request::new(());
+        *head.method_mut() = parts.method.clone();
+        *head.uri_mut() = parts.uri.clone();
+        *head.version_mut() = parts.version;
+        *head.headers_mut() = parts.headers.clone();
+        let body = Bytes::from_request(Request::from_parts(parts, body), state).await?;
+        let req = head.map(|()| body);
+
+        Ok(Self(T::try_from_http_request(req, &path_args)?))
+    }
+}
+
+/// Responses that fail to convert are responded to with `500 Internal Server Error`.
+impl<T: OutgoingResponse> IntoResponse for Endpoint<T> {
+    fn into_response(self) -> Response {
+        match self.0.try_into_http_response::<BytesMut>() {
+            Ok(res) => res.map(|body| Body::from(body.freeze())),
+            Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::put, Router};
+    use bytes::BufMut;
+    use http::{header::CONTENT_TYPE, Method};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        error::{DeserializeError, IntoHttpError},
+        metadata::Metadata,
+        tests::TestError,
+    };
+
+    #[derive(Debug)]
+    struct PutItem {
+        id: u32,
+        name: String,
+    }
+
+    struct PutItemResponse(String);
+
+    impl crate::Endpoint for PutItem {
+        type Error = TestError;
+
+        const METADATA: Metadata<'static> = Metadata {
+            method: Method::PUT,
+            path: "/items/{id}",
+            ..Metadata::DEFAULT
+        };
+    }
+
+    impl IncomingRequest for PutItem {
+        type OutgoingResponse = PutItemResponse;
+
+        fn try_from_http_request<'a, B, I, P>(
+            req: http::Request<B>,
+            path_args: I,
+        ) -> Result<Self, FromHttpRequestError>
+        where
+            B: AsRef<[u8]>,
+            I: IntoIterator<Item = &'a P>,
+            P: AsRef<str> + 'a,
+        {
+            if req.method() != Method::PUT {
+                return Err(FromHttpRequestError::MethodMismatch {
+                    expected: Method::PUT,
+                    actual: req.method().clone(),
+                });
+            }
+            let id = path_args
+                .into_iter()
+                .next()
+                .and_then(|id| id.as_ref().parse().ok())
+                .ok_or(FromHttpRequestError::PathMismatch)?;
+            let name = std::str::from_utf8(req.body().as_ref())
+                .map_err(|_| DeserializeError::InvalidMultipart)?
+                .to_owned();
+            Ok(Self { id, name })
+        }
+    }
+
+    impl OutgoingResponse for PutItemResponse {
+        type IncomingRequest = PutItem;
+
+        fn try_into_http_response<B>(self) -> Result<http::Response<B>, IntoHttpError>
+        where
+            B: Default + BufMut,
+        {
+            let mut body = B::default();
+            body.put_slice(self.0.as_bytes());
+            Ok(http::Response::builder()
+                .status(StatusCode::CREATED)
+                .header(CONTENT_TYPE, "text/plain")
+                .body(body)?)
+        }
+    }
+
+    async fn put_item(Endpoint(req): Endpoint<PutItem>) -> Endpoint<PutItemResponse> {
+        Endpoint(PutItemResponse(format!("{}={}", req.id, req.name)))
+    }
+
+    fn call(method: Method, uri: &str, body: &'static [u8]) -> (StatusCode, Bytes) {
+        let app = Router::new().route("/items/{id}", put(put_item).post(put_item));
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::from(body))
+            .unwrap();
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let res = app.oneshot(req).await.unwrap();
+                let status = res.status();
+                (
+                    status,
+                    axum::body::to_bytes(res.into_body(), usize::MAX)
+                        .await
+                        .unwrap(),
+                )
+            })
+    }
+
+    #[test]
+    fn extract_and_respond() {
+        assert_eq!(
+            call(Method::PUT, "/items/7", b"widget"),
+            (StatusCode::CREATED, Bytes::from_static(b"7=widget"))
+        );
+    }
+
+    #[test]
+    fn rejections() {
+        let (status, _) = call(Method::PUT, "/items/abc", b"widget");
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(Method::POST, "/items/7", b"widget");
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, body) = call(Method::PUT, "/items/7", b"\xff");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.starts_with(b"Deserialization error"));
+    }
+}
@@ -0,0 +1,143 @@
+//! Utilities for testing and debugging endpoints.
+
+#[cfg(feature = "har")]
+mod conformance;
+#[cfg(feature = "har")]
+mod har;
+
+use std::fmt::Write;
+
+use bytes::BytesMut;
+use http::{
+    header::{AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION},
+    HeaderName, HeaderValue, Method,
+};
+
+#[cfg(feature = "har")]
+pub use self::{
+    conformance::Conformance,
+    har::{from_har, HarEntry},
+};
+
+/// Placeholder for redacted header values.
+const REDACTED: &str = "<redacted>";
+
+/// Convert a request into an equivalent `curl` command.
+///
+/// The method, URL, headers, and body are shell-escaped for POSIX shells.
+/// Bodies that aren't valid UTF-8 are written using ANSI-C quoting (`$'...'`).
+///
+/// If `redact_auth` is set, the values of `Authorization`, `Proxy-Authorization`, `Cookie`,
+/// and headers marked as [sensitive](HeaderValue::is_sensitive) are replaced with `<redacted>`.
+#[must_use]
+pub fn to_curl(req: &http::Request<BytesMut>, redact_auth: bool) -> String {
+    let mut cmd = String::from("curl");
+
+    if req.method() != Method::GET {
+        let _ = write!(cmd, " -X {}", shell_quote(req.method().as_str()));
+    }
+    let _ = write!(cmd, " {}", shell_quote(&req.uri().to_string()));
+
+    for (name, value) in req.headers() {
+        let value = if redact_auth && is_sensitive(name, value) {
+            REDACTED.into()
+        } else {
+            String::from_utf8_lossy(value.as_bytes())
+        };
+        let _ = write!(cmd, " -H {}", shell_quote(&format!("{name}: {value}")));
+    }
+
+    if !req.body().is_empty() {
+        let body =
+            std::str::from_utf8(req.body()).map_or_else(|_| ansi_c_quote(req.body()), shell_quote);
+        let _ = write!(cmd, " --data-binary {body}");
+    }
+
+    cmd
+}
+
+fn is_sensitive(name: &HeaderName, value: &HeaderValue) -> bool {
+    value.is_sensitive() || [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE].contains(name)
+}
+
+/// Quote a string for a POSIX shell using single quotes.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote arbitrary bytes for a POSIX shell using ANSI-C quoting.
+fn ansi_c_quote(bytes: &[u8]) -> String {
+    let mut quoted = String::from("$'");
+    for &b in bytes {
+        match b {
+            b'\'' | b'\\' => {
+                quoted.push('\\');
+                quoted.push(char::from(b));
+            }
+            0x20..=0x7e => quoted.push(char::from(b)),
+            _ => {
+                let _ = write!(quoted, "\\x{b:02x}");
+            }
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::CONTENT_TYPE;
+
+    use super::*;
+
+    fn request() -> http::Request<BytesMut> {
+        http::Request::builder()
+            .method(Method::POST)
+            .uri("https://example.com/users?name=o'brien")
+            .header(CONTENT_TYPE, "application/json")
+            .header(AUTHORIZATION, "Bearer secret")
+            .body(BytesMut::from(&br#"{"name":"it's"}"#[..]))
+            .unwrap()
+    }
+
+    #[test]
+    fn curl_command() {
+        assert_eq!(
+            to_curl(&request(), false),
+            r#"curl -X 'POST' 'https://example.com/users?name=o'\''brien' -H 'content-type: application/json' -H 'authorization: Bearer secret' --data-binary '{"name":"it'\''s"}'"#
+        );
+    }
+
+    #[test]
+    fn curl_command_redacted() {
+        let mut req = request();
+        let mut api_key = HeaderValue::from_static("key");
+        api_key.set_sensitive(true);
+        req.headers_mut()
+            .insert(HeaderName::from_static("x-api-key"), api_key);
+
+        let cmd = to_curl(&req, true);
+        assert!(cmd.contains("-H 'authorization: <redacted>'"));
+        assert!(cmd.contains("-H 'x-api-key: <redacted>'"));
+        assert!(cmd.contains("-H 'content-type: application/json'"));
+        assert!(!cmd.contains("secret"));
+    }
+
+    #[test]
+    fn curl_command_get_binary() {
+        let req = http::Request::builder()
+            .uri("https://example.com/")
+            .body(BytesMut::from(&b"\x00\xff'\\a"[..]))
+            .unwrap();
+        assert_eq!(
+            to_curl(&req, false),
+            r"curl 'https://example.com/' --data-binary $'\x00\xff\'\\a'"
+        );
+
+        let req = http::Request::builder()
+            .uri("https://example.com/")
+            .body(BytesMut::new())
+            .unwrap();
+        assert_eq!(to_curl(&req, false), "curl 'https://example.com/'");
+    }
+}
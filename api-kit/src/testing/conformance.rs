@@ -0,0 +1,291 @@
+//! Checking endpoint definitions against recorded interactions.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{
+    auth::Authenticator,
+    error::{ConformanceError, FromHttpResponseError, HarError},
+    testing::{from_har, HarEntry},
+    Endpoint, IncomingResponse, OutgoingRequest,
+};
+
+/// A set of recorded interactions to check endpoint definitions against.
+///
+/// Each interaction is a [`HarEntry`] with a name, e.g. exported from the
+/// network panel of a browser or recorded by a proxy.
+/// [`Conformance::check`] builds a request with [`OutgoingRequest::try_into_http_request`]
+/// and compares it to the recorded request,
+/// then converts the recorded response with
+/// [`IncomingResponse::try_from_http_response_by_status`].
+/// This catches drift between endpoint types and the live API.
+#[derive(Debug, Default)]
+pub struct Conformance {
+    interactions: BTreeMap<String, HarEntry>,
+}
+
+impl Conformance {
+    /// Creates an empty set of interactions.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            interactions: BTreeMap::new(),
+        }
+    }
+
+    /// Load the interactions of a directory.
+    ///
+    /// Every `.json` file in `dir` must contain a single HAR entry (see [`from_har`]),
+    /// and is named after its file stem, e.g. `get_user` for `get_user.json`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, HarError> {
+        let mut conformance = Self::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            conformance.insert(name, from_har(&fs::read_to_string(&path)?)?);
+        }
+        Ok(conformance)
+    }
+
+    /// Adds a recorded interaction named `name`, replacing any with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, interaction: HarEntry) -> &mut Self {
+        self.interactions.insert(name.into(), interaction);
+        self
+    }
+
+    /// Returns the names of the interactions, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.interactions.keys().map(String::as_str)
+    }
+
+    /// Check `req` against the interaction named `name`.
+    ///
+    /// The built request must have the recorded method, URI and body.
+    /// JSON bodies are compared as JSON values, so key order and whitespace don't matter.
+    /// Every header set on the built request must have the recorded value,
+    /// except for the [authentication headers](crate::auth::AuthScheme::auth_headers) of `auth`,
+    /// whose credentials usually differ from the recording.
+    /// Other recorded headers, such as `User-Agent`, are ignored.
+    ///
+    /// On success, returns the converted recorded response,
+    /// or the endpoint error if the recorded response is an error response.
+    pub fn check<R, A>(
+        &self,
+        name: &str,
+        req: R,
+        base_url: &str,
+        auth: A,
+        auth_data: &A::AuthData,
+    ) -> Result<Result<R::IncomingResponse, <R as Endpoint>::Error>, ConformanceError>
+    where
+        R: OutgoingRequest,
+        A: Authenticator,
+    {
+        let interaction = self
+            .interactions
+            .get(name)
+            .ok_or_else(|| ConformanceError::MissingInteraction(name.to_owned()))?;
+        let recorded = &interaction.request;
+
+        let auth_headers = auth.auth_headers();
+        let built = req.try_into_http_request(base_url, auth, auth_data)?;
+
+        mismatch("method", recorded.method(), built.method())?;
+        mismatch("uri", recorded.uri(), built.uri())?;
+        for name in built.headers().keys() {
+            if auth_headers.contains(name) {
+                continue;
+            }
+            let expected: Vec<_> = recorded.headers().get_all(name).iter().collect();
+            let actual: Vec<_> = built.headers().get_all(name).iter().collect();
+            if expected != actual {
+                return Err(ConformanceError::RequestMismatch {
+                    part: "header",
+                    expected: format!("{name}: {expected:?}"),
+                    actual: format!("{name}: {actual:?}"),
+                });
+            }
+        }
+        if !bodies_match(recorded.body(), built.body()) {
+            return Err(ConformanceError::RequestMismatch {
+                part: "body",
+                expected: String::from_utf8_lossy(recorded.body()).into_owned(),
+                actual: String::from_utf8_lossy(built.body()).into_owned(),
+            });
+        }
+
+        match R::IncomingResponse::try_from_http_response_by_status(interaction.response.clone()) {
+            Ok(res) => Ok(Ok(res)),
+            Err(
+                FromHttpResponseError::EndpointError(err)
+                | FromHttpResponseError::RateLimited { error: err, .. },
+            ) => Ok(Err(err)),
+            Err(FromHttpResponseError::Deserialize(err)) => Err(ConformanceError::Deserialize(err)),
+        }
+    }
+}
+
+fn mismatch<T: PartialEq + ToString + ?Sized>(
+    part: &'static str,
+    expected: &T,
+    actual: &T,
+) -> Result<(), ConformanceError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(ConformanceError::RequestMismatch {
+            part,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        })
+    }
+}
+
+fn bodies_match(expected: &[u8], actual: &[u8]) -> bool {
+    if expected == actual {
+        return true;
+    }
+    match (
+        serde_json::from_slice::<serde_json::Value>(expected),
+        serde_json::from_slice::<serde_json::Value>(actual),
+    ) {
+        (Ok(expected), Ok(actual)) => expected == actual,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::{ACCEPT, AUTHORIZATION};
+
+    use super::*;
+    use crate::{
+        auth::bearer::BearerAuth,
+        tests::{TestError, TestRequest, TestResponse},
+    };
+
+    fn interaction(url: &str, accept: &str, status: u16) -> HarEntry {
+        from_har(&format!(
+            r#"{{
+                "request": {{
+                    "method": "GET",
+                    "url": "{url}",
+                    "headers": [
+                        {{"name": "accept", "value": "{accept}"}},
+                        {{"name": "authorization", "value": "Bearer recorded"}},
+                        {{"name": "user-agent", "value": "curl/8.0"}}
+                    ]
+                }},
+                "response": {{"status": {status}, "headers": []}}
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    fn check(
+        conformance: &Conformance,
+        name: &str,
+        accept: bool,
+    ) -> Result<Result<TestResponse, TestError>, ConformanceError> {
+        conformance.check(
+            name,
+            TestRequest { id: 1, accept },
+            "https://example.com",
+            BearerAuth::new(),
+            &"token".to_owned(),
+        )
+    }
+
+    #[test]
+    fn matching_interaction() {
+        let mut conformance = Conformance::new();
+        conformance
+            .insert(
+                "get_item",
+                interaction("https://example.com/items/1", "application/json", 200),
+            )
+            .insert(
+                "missing_item",
+                interaction("https://example.com/items/1", "application/json", 404),
+            );
+        assert_eq!(
+            conformance.names().collect::<Vec<_>>(),
+            ["get_item", "missing_item"]
+        );
+
+        assert!(matches!(
+            check(&conformance, "get_item", true),
+            Ok(Ok(TestResponse))
+        ));
+        assert!(matches!(
+            check(&conformance, "missing_item", true),
+            Ok(Err(TestError))
+        ));
+        // The recorded `Accept` header isn't required
+        assert!(check(&conformance, "get_item", false).is_ok());
+    }
+
+    #[test]
+    fn drift() {
+        let mut conformance = Conformance::new();
+        conformance
+            .insert(
+                "moved",
+                interaction("https://example.com/v2/items/1", "application/json", 200),
+            )
+            .insert(
+                "accept",
+                interaction("https://example.com/items/1", "text/plain", 200),
+            );
+
+        assert!(matches!(
+            check(&conformance, "moved", true).unwrap_err(),
+            ConformanceError::RequestMismatch { part: "uri", .. }
+        ));
+        let err = check(&conformance, "accept", true).unwrap_err();
+        assert!(matches!(
+            err,
+            ConformanceError::RequestMismatch { part: "header", .. }
+        ));
+        assert!(err.to_string().contains(ACCEPT.as_str()));
+        assert!(!err.to_string().contains(AUTHORIZATION.as_str()));
+        assert!(matches!(
+            check(&conformance, "unknown", true).unwrap_err(),
+            ConformanceError::MissingInteraction(name) if name == "unknown"
+        ));
+    }
+
+    #[test]
+    fn json_bodies() {
+        assert!(bodies_match(br#"{"a":1,"b":2}"#, br#"{ "b": 2, "a": 1 }"#));
+        assert!(!bodies_match(br#"{"a":1}"#, br#"{"a":2}"#));
+        assert!(!bodies_match(b"a=1", b"a=2"));
+    }
+
+    #[test]
+    fn from_dir() {
+        let dir = std::env::temp_dir().join(format!("api-kit-conformance-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("get_item.json"),
+            r#"{
+                "request": {"method": "GET", "url": "https://example.com/items/1"},
+                "response": {"status": 200}
+            }"#,
+        )
+        .unwrap();
+        fs::write(dir.join("README.md"), "not an interaction").unwrap();
+
+        let conformance = Conformance::from_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let conformance = conformance.unwrap();
+        assert_eq!(conformance.names().collect::<Vec<_>>(), ["get_item"]);
+        assert!(check(&conformance, "get_item", false).is_ok());
+    }
+}
@@ -0,0 +1,211 @@
+//! Loading requests and responses from HAR (HTTP Archive) entries.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::{Bytes, BytesMut};
+use http::Version;
+use serde::Deserialize;
+
+use crate::error::HarError;
+
+/// A request and its response, loaded from a HAR entry.
+#[derive(Debug)]
+pub struct HarEntry {
+    /// The recorded request.
+    pub request: http::Request<BytesMut>,
+    /// The recorded response.
+    pub response: http::Response<Bytes>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    request: Request,
+    response: Response,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    #[serde(default)]
+    http_version: String,
+    #[serde(default)]
+    headers: Vec<Header>,
+    post_data: Option<Content>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    #[serde(default)]
+    http_version: String,
+    #[serde(default)]
+    headers: Vec<Header>,
+    content: Option<Content>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    name: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Content {
+    #[serde(default)]
+    text: String,
+    encoding: Option<String>,
+}
+
+impl Content {
+    fn decode(self) -> Result<Vec<u8>, HarError> {
+        match self.encoding.as_deref() {
+            Some("base64") => Ok(STANDARD.decode(self.text)?),
+            Some(encoding) => Err(HarError::UnsupportedEncoding(encoding.to_owned())),
+            None => Ok(self.text.into_bytes()),
+        }
+    }
+}
+
+/// Parse a single HAR entry into a request and response.
+///
+/// The `entry` is the JSON of one element of a HAR file's `log.entries` array.
+/// Base64-encoded bodies are decoded, and HTTP/2 pseudo-headers (e.g. `:authority`) are skipped.
+pub fn from_har(entry: &str) -> Result<HarEntry, HarError> {
+    let entry: Entry = serde_json::from_str(entry)?;
+
+    let mut request = http::Request::builder()
+        .method(entry.request.method.as_str())
+        .uri(entry.request.url)
+        .version(parse_version(&entry.request.http_version));
+    for header in headers(&entry.request.headers) {
+        request = request.header(&header.name, &header.value);
+    }
+    let body = entry.request.post_data.map(Content::decode).transpose()?;
+    let request = request.body(body.map(|b| BytesMut::from(&b[..])).unwrap_or_default())?;
+
+    let mut response = http::Response::builder()
+        .status(entry.response.status)
+        .version(parse_version(&entry.response.http_version));
+    for header in headers(&entry.response.headers) {
+        response = response.header(&header.name, &header.value);
+    }
+    let body = entry.response.content.map(Content::decode).transpose()?;
+    let response = response.body(body.map(Bytes::from).unwrap_or_default())?;
+
+    Ok(HarEntry { request, response })
+}
+
+fn headers(headers: &[Header]) -> impl Iterator<Item = &Header> {
+    headers.iter().filter(|h| !h.name.starts_with(':'))
+}
+
+fn parse_version(version: &str) -> Version {
+    match version.to_ascii_uppercase().as_str() {
+        "HTTP/0.9" => Version::HTTP_09,
+        "HTTP/1.0" => Version::HTTP_10,
+        "HTTP/2" | "HTTP/2.0" | "H2" => Version::HTTP_2,
+        "HTTP/3" | "HTTP/3.0" | "H3" => Version::HTTP_3,
+        _ => Version::HTTP_11,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header::CONTENT_TYPE, Method, StatusCode};
+
+    use super::*;
+
+    const ENTRY: &str = r#"{
+        "startedDateTime": "2024-01-01T00:00:00.000Z",
+        "request": {
+            "method": "POST",
+            "url": "https://example.com/items?page=1",
+            "httpVersion": "HTTP/2",
+            "headers": [
+                {"name": ":authority", "value": "example.com"},
+                {"name": "content-type", "value": "application/json"},
+                {"name": "x-multi", "value": "a"},
+                {"name": "x-multi", "value": "b"}
+            ],
+            "queryString": [{"name": "page", "value": "1"}],
+            "postData": {"mimeType": "application/json", "text": "{\"name\":\"item\"}"}
+        },
+        "response": {
+            "status": 201,
+            "statusText": "Created",
+            "httpVersion": "HTTP/2",
+            "headers": [{"name": "content-type", "value": "application/octet-stream"}],
+            "content": {"size": 4, "mimeType": "application/octet-stream", "text": "AAEC/w==", "encoding": "base64"}
+        }
+    }"#;
+
+    #[test]
+    fn har_entry() {
+        let entry = from_har(ENTRY).unwrap();
+
+        let req = entry.request;
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.uri(), "https://example.com/items?page=1");
+        assert_eq!(req.version(), Version::HTTP_2);
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(req.headers().get_all("x-multi").iter().count(), 2);
+        assert!(!req.headers().contains_key("authority"));
+        assert_eq!(&req.body()[..], br#"{"name":"item"}"#);
+
+        let res = entry.response;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.headers()[CONTENT_TYPE], "application/octet-stream");
+        assert_eq!(&res.body()[..], b"\x00\x01\x02\xff");
+    }
+
+    #[test]
+    fn har_entry_without_bodies() {
+        let entry = from_har(
+            r#"{
+                "request": {"method": "GET", "url": "https://example.com/", "headers": []},
+                "response": {"status": 204, "headers": []}
+            }"#,
+        )
+        .unwrap();
+        assert!(entry.request.body().is_empty());
+        assert_eq!(entry.request.version(), Version::HTTP_11);
+        assert!(entry.response.body().is_empty());
+    }
+
+    #[test]
+    fn har_entry_errors() {
+        assert!(matches!(from_har("{}").unwrap_err(), HarError::Json(_)));
+        assert!(matches!(
+            from_har(
+                r#"{
+                    "request": {"method": "GET", "url": "https://example.com/"},
+                    "response": {"status": 200, "content": {"text": "!!", "encoding": "base64"}}
+                }"#
+            )
+            .unwrap_err(),
+            HarError::Base64(_)
+        ));
+        assert!(matches!(
+            from_har(
+                r#"{
+                    "request": {"method": "GET", "url": "https://example.com/"},
+                    "response": {"status": 200, "content": {"text": "", "encoding": "rot13"}}
+                }"#
+            )
+            .unwrap_err(),
+            HarError::UnsupportedEncoding(_)
+        ));
+        assert!(matches!(
+            from_har(
+                r#"{
+                    "request": {"method": "GET", "url": "not a url"},
+                    "response": {"status": 200}
+                }"#
+            )
+            .unwrap_err(),
+            HarError::Http(_)
+        ));
+    }
+}
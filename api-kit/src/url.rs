@@ -3,18 +3,36 @@ use std::borrow::Cow;
 use percent_encoding::{AsciiSet, CONTROLS};
 use serde::{ser, Serialize};
 
-use crate::error::{IntoHttpError, UrlError};
+use crate::{
+    error::{IntoHttpError, UrlError},
+    metadata::{QueryStyle, UnusedPathArgs},
+};
+
+mod query;
 
 /// Constructs a complete URL from a base URL, an endpoint, and parameters.
 ///
 /// - The `base_url` is the base URL of the API: `https://api.trakt.tv`.
+///   It is joined to the `endpoint` with a single slash,
+///   unless the `endpoint` is empty, which refers to the base URL itself.
 /// - The `endpoint` is the path of the specific endpoint with parameters
 ///   enclosed in curly braces:
-/// `/shows/{id}/seasons/{season}/episodes/{episode}`.
+///   `/shows/{id}/seasons/{season}/episodes/{episode}`.
 /// - The `params` is a struct that will be serialized into the parameters of
-///   the `endpoint`.
+///   the `endpoint`. Their values are percent-encoded, including `/`,
+///   so each value stays within a single path segment, and `;` and `=`,
+///   so values can't be mistaken for matrix parameters,
+///   and `.` and `..` values are an [`UrlError::DotSegment`].
+///   Dotted parameters like `{org.id}` are filled from fields of nested structs.
+///   If an intermediate field is `None`, its dotted parameters are left unfilled
+///   and an [`UrlError::UnfilledField`] is returned.
+///   All unfilled parameters are returned together,
+///   see [`UrlError::unfilled_fields`].
+///   Fields without a parameter are handled as selected by `unused`.
+///   Optional trailing parameters like `{tab?}` are omitted with their preceding `/`
+///   when their field is `None` or missing.
 /// - The `query` is a struct that will be serialized into the query parameters
-///   of the URL.
+///   of the URL in the given `style`.
 ///
 /// # Errors
 ///
@@ -24,12 +42,73 @@ pub fn construct_url(
     endpoint: &str,
     params: &impl Serialize,
     query: &impl Serialize,
+    style: QueryStyle,
+    unused: UnusedPathArgs,
 ) -> Result<String, IntoHttpError> {
     // Serialize the url parameters
-    let url = to_string(base_url, endpoint, params)?;
+    let url = to_string(base_url, endpoint, params, unused)?;
+    with_query(url, query, style)
+}
+
+/// Constructs a complete URL like [`construct_url`],
+/// with matrix parameters attached to a path segment, e.g. `/users;version=2/posts`.
+///
+/// - The `segment` is the index of the path segment of the `endpoint`
+///   the matrix parameters are attached to, starting at `0`,
+///   e.g. `0` for `users` in `/users/{id}/posts`.
+///   Segments are counted after the path arguments are filled,
+///   and segments of the `base_url` are not counted.
+///   A `segment` past the end of the path is an [`UrlError::MatrixSegmentNotFound`].
+/// - The `matrix` is a struct that will be serialized into `;key=value` parameters,
+///   appended after the path argument of the segment, if any.
+///   Sequences are serialized as repeated keys, e.g. `;ids=1;ids=2`,
+///   and `None` values are skipped.
+///   Keys and values are percent-encoded, including `;`, `=` and `/`.
+/// - The query string follows the whole path, as with [`construct_url`].
+///
+/// # Errors
+///
+/// Returns an [`IntoHttpError`] if the URL cannot be constructed.
+#[allow(clippy::too_many_arguments)]
+pub fn construct_matrix_url(
+    base_url: &str,
+    endpoint: &str,
+    params: &impl Serialize,
+    segment: usize,
+    matrix: &impl Serialize,
+    query: &impl Serialize,
+    style: QueryStyle,
+    unused: UnusedPathArgs,
+) -> Result<String, IntoHttpError> {
+    let mut path = path_to_string(endpoint, params, unused)?;
+
+    // Serialize the matrix parameters after the segment
+    let pairs = query::pairs(matrix)?;
+    if !pairs.is_empty() {
+        let start = usize::from(path.starts_with('/'));
+        let end = path[start..]
+            .match_indices('/')
+            .map(|(i, _)| start + i)
+            .chain([path.len()])
+            .nth(segment)
+            .ok_or(UrlError::MatrixSegmentNotFound(segment))?;
+        path.insert_str(end, &format!(";{}", pairs.join(";")));
+    }
+
+    let url = join(base_url, endpoint, &path);
+    with_query(url, query, style)
+}
 
+fn with_query(
+    url: String,
+    query: &impl Serialize,
+    style: QueryStyle,
+) -> Result<String, IntoHttpError> {
     // Serialize the query parameters
-    let query = serde_urlencoded::to_string(query)?;
+    let query = match style {
+        QueryStyle::UrlEncoded => serde_urlencoded::to_string(query)?,
+        QueryStyle::Bracketed => query::to_string(query)?,
+    };
 
     // If there are query parameters, append them to the URL
     let url = if query.is_empty() {
@@ -41,9 +120,80 @@ pub fn construct_url(
     Ok(url)
 }
 
+/// Matches a request path against an endpoint, extracting the path arguments.
+///
+/// The arguments are returned percent-decoded, in the order they appear in the `endpoint`.
+/// Each argument matches a non-empty string without a `/`, up to the next literal part
+/// of the `endpoint`.
+/// Optional parameters that are omitted from the `path` are omitted from the arguments.
+///
+/// Unless `strict` is set, a trailing slash on either the `endpoint` or the `path` is ignored.
+///
+/// Returns `None` if the path doesn't match, the `endpoint` is invalid,
+/// or the `endpoint` contains adjacent parameters, which can't be matched unambiguously.
+pub fn match_endpoint<'p>(
+    endpoint: &str,
+    path: &'p str,
+    strict: bool,
+) -> Option<Vec<Cow<'p, str>>> {
+    let (endpoint, mut path) = if strict {
+        (endpoint, path)
+    } else {
+        (
+            endpoint.strip_suffix('/').unwrap_or(endpoint),
+            path.strip_suffix('/').unwrap_or(path),
+        )
+    };
+
+    let parts = parse_endpoint(endpoint).ok()?;
+    let mut args = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            Part::Raw(raw) => {
+                // The rest of the optional segments are omitted
+                if matches!(parts.get(i + 1), Some(Part::Optional(_)))
+                    && raw.strip_suffix('/') == Some(path)
+                {
+                    path = "";
+                    break;
+                }
+                path = path.strip_prefix(raw)?;
+            }
+            Part::Param(_) | Part::Optional(_) => {
+                let end = match (parts.get(i + 1), parts.get(i + 2)) {
+                    (Some(Part::Raw(raw)), next) => match path.find(raw) {
+                        Some(end) => end,
+                        // The following optional segment is omitted
+                        None if matches!(next, Some(Part::Optional(_))) => path.len(),
+                        None => return None,
+                    },
+                    (Some(_), _) => return None,
+                    (None, _) => path.len(),
+                };
+                let (arg, rest) = path.split_at(end);
+                if arg.is_empty() || arg.contains('/') {
+                    return None;
+                }
+                args.push(
+                    percent_encoding::percent_decode_str(arg)
+                        .decode_utf8()
+                        .ok()?,
+                );
+                path = rest;
+            }
+        }
+    }
+
+    path.is_empty().then_some(args)
+}
+
 struct UrlSerializer<'a> {
     /// The parts of the URL endpoint
     parts: Vec<Part<'a>>,
+    /// How fields without a parameter are handled.
+    unused: UnusedPathArgs,
+    /// The first ignored field without a parameter.
+    ignored: Option<&'static str>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,19 +204,61 @@ enum Part<'a> {
     ///
     /// The parameter is either borrowed (unfilled) or owned (filled).
     Param(Cow<'a, str>),
+    /// An optional parameter, written `{name?}`, that is omitted with its preceding `/`
+    /// when it isn't filled.
+    ///
+    /// Optional parameters must be trailing segments of the endpoint, like `/{a?}/{b?}`.
+    Optional(Cow<'a, str>),
 }
 
-fn to_string(base_url: &str, endpoint: &str, value: &impl Serialize) -> Result<String, UrlError> {
-    let mut parts = parse_endpoint(endpoint)?;
-    parts.insert(0, Part::Raw(base_url));
+impl Part<'_> {
+    /// Fill a parameter with its serialized `value`.
+    fn fill(&mut self, value: String) {
+        if let Self::Param(p) | Self::Optional(p) = self {
+            *p = Cow::Owned(value);
+        }
+    }
+}
+
+fn to_string(
+    base_url: &str,
+    endpoint: &str,
+    value: &impl Serialize,
+    unused: UnusedPathArgs,
+) -> Result<String, UrlError> {
+    let path = path_to_string(endpoint, value, unused)?;
+    Ok(join(base_url, endpoint, &path))
+}
 
-    let mut serializer = UrlSerializer { parts };
+/// Serializes `value` into the path of the `endpoint`.
+fn path_to_string(
+    endpoint: &str,
+    value: &impl Serialize,
+    unused: UnusedPathArgs,
+) -> Result<String, UrlError> {
+    let mut serializer = UrlSerializer::new(parse_endpoint(endpoint)?, unused);
     value.serialize(&mut serializer)?;
     serializer.end()
 }
 
+/// Join the base URL and the serialized `path` of the `endpoint` with exactly one slash.
+/// An empty endpoint is the base URL itself.
+fn join(base_url: &str, endpoint: &str, path: &str) -> String {
+    if endpoint.starts_with('/') {
+        format!("{}{path}", base_url.strip_suffix('/').unwrap_or(base_url))
+    } else if endpoint.is_empty() || base_url.ends_with('/') {
+        format!("{base_url}{path}")
+    } else {
+        format!("{base_url}/{path}")
+    }
+}
+
 /// Parses the endpoint into parts
 ///
+/// Unclosed, nested, unopened, and empty parameters are an [`UrlError::InvalidEndpoint`],
+/// as are optional parameters that aren't trailing segments.
+/// Adjacent parameters like `{a}{b}` are allowed.
+///
 /// Example endpoint: `/shows/{id}/seasons/{season}/episodes/{episode}`
 ///
 /// Example parts:
@@ -76,7 +268,7 @@ fn to_string(base_url: &str, endpoint: &str, value: &impl Serialize) -> Result<S
 /// - `Param("season")`
 /// - `Raw("/episodes/")`
 /// - `Param("episode")`
-fn parse_endpoint(s: &str) -> Result<Vec<Part>, UrlError> {
+fn parse_endpoint(s: &str) -> Result<Vec<Part<'_>>, UrlError> {
     let mut parts = Vec::new();
     let mut start = 0;
     let mut in_param = false;
@@ -107,9 +299,17 @@ fn parse_endpoint(s: &str) -> Result<Vec<Part>, UrlError> {
             // Mark that we're no longer in a parameter
             in_param = false;
 
+            // Empty parameters are an error
+            if start == i {
+                return Err(UrlError::InvalidEndpoint);
+            }
+
             // Add the parameter to the parts
-            if start != i {
-                parts.push(Part::Param(Cow::Borrowed(&s[start..i])));
+            let name = &s[start..i];
+            match name.strip_suffix('?') {
+                Some("") => return Err(UrlError::InvalidEndpoint),
+                Some(name) => parts.push(Part::Optional(Cow::Borrowed(name))),
+                None => parts.push(Part::Param(Cow::Borrowed(name))),
             }
 
             // Move the start to the end of the parameter
@@ -127,26 +327,83 @@ fn parse_endpoint(s: &str) -> Result<Vec<Part>, UrlError> {
         parts.push(Part::Raw(&s[start..]));
     }
 
+    // Optional parameters must be trailing segments, like `/{a?}/{b?}`
+    if let Some(first) = parts.iter().position(|p| matches!(p, Part::Optional(_))) {
+        let after_slash =
+            first > 0 && matches!(parts[first - 1], Part::Raw(raw) if raw.ends_with('/'));
+        let trailing = parts[first..].iter().enumerate().all(|(i, p)| match p {
+            Part::Optional(_) => i % 2 == 0,
+            Part::Raw(raw) => i % 2 == 1 && *raw == "/",
+            Part::Param(_) => false,
+        });
+        if !after_slash || !trailing || !matches!(parts.last(), Some(Part::Optional(_))) {
+            return Err(UrlError::InvalidEndpoint);
+        }
+    }
+
     Ok(parts)
 }
 
 impl<'a> UrlSerializer<'a> {
+    const fn new(parts: Vec<Part<'a>>, unused: UnusedPathArgs) -> Self {
+        Self {
+            parts,
+            unused,
+            ignored: None,
+        }
+    }
+
+    /// Returns the parameters that are not filled yet.
+    fn placeholders(&self) -> Vec<String> {
+        self.parts
+            .iter()
+            .filter_map(|p| match p {
+                Part::Param(Cow::Borrowed(k)) | Part::Optional(Cow::Borrowed(k)) => {
+                    Some((*k).to_owned())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn end(self) -> Result<String, UrlError> {
         let mut url = String::new();
+        let mut omitted = None;
+        let mut unfilled = Vec::new();
         for part in self.parts {
             match part {
-                Part::Raw(s) => url.push_str(s),
-                Part::Param(p) => match p {
-                    Cow::Borrowed(k) => return Err(UrlError::UnfilledField(k.to_owned())),
-                    Cow::Owned(v) => url.push_str(&v),
-                },
+                Part::Raw(s) => {
+                    if omitted.is_none() {
+                        url.push_str(s);
+                    }
+                }
+                Part::Param(Cow::Borrowed(k)) => unfilled.push(k.to_owned()),
+                Part::Param(Cow::Owned(v)) => url.push_str(&v),
+                Part::Optional(Cow::Borrowed(k)) => {
+                    // Omit the segment with its preceding slash, and all following segments
+                    if omitted.is_none() {
+                        url.pop();
+                        omitted = Some(k);
+                    }
+                }
+                Part::Optional(Cow::Owned(v)) => {
+                    // Following segments can't be filled without the omitted segment
+                    if let Some(k) = omitted.filter(|k| !unfilled.iter().any(|u| u == k)) {
+                        unfilled.push(k.to_owned());
+                    }
+                    url.push_str(&v);
+                }
             }
         }
-        Ok(url)
+        match unfilled.len() {
+            0 => Ok(url),
+            1 => Err(UrlError::UnfilledField(unfilled.remove(0))),
+            _ => Err(UrlError::UnfilledFields(unfilled)),
+        }
     }
 }
 
-impl<'a, 'b> ser::Serializer for &'a mut UrlSerializer<'b> {
+impl ser::Serializer for &mut UrlSerializer<'_> {
     type Ok = ();
 
     type Error = UrlError;
@@ -258,11 +515,17 @@ impl<'a, 'b> ser::Serializer for &'a mut UrlSerializer<'b> {
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(UrlError::ValueNotSupported {
+            field: "",
+            kind: "sequence",
+        })
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(UrlError::ValueNotSupported {
+            field: "",
+            kind: "tuple",
+        })
     }
 
     fn serialize_tuple_struct(
@@ -270,7 +533,10 @@ impl<'a, 'b> ser::Serializer for &'a mut UrlSerializer<'b> {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(UrlError::ValueNotSupported {
+            field: "",
+            kind: "tuple struct",
+        })
     }
 
     fn serialize_tuple_variant(
@@ -280,11 +546,17 @@ impl<'a, 'b> ser::Serializer for &'a mut UrlSerializer<'b> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(UrlError::ValueNotSupported {
+            field: "",
+            kind: "tuple variant",
+        })
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(UrlError::ValueNotSupported {
+            field: "",
+            kind: "map",
+        })
     }
 
     fn serialize_struct(
@@ -302,11 +574,14 @@ impl<'a, 'b> ser::Serializer for &'a mut UrlSerializer<'b> {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(UrlError::ValueNotSupported {
+            field: "",
+            kind: "struct variant",
+        })
     }
 }
 
-impl<'a, 'b> ser::SerializeStruct for &'a mut UrlSerializer<'b> {
+impl ser::SerializeStruct for &mut UrlSerializer<'_> {
     type Ok = ();
     type Error = UrlError;
 
@@ -315,57 +590,133 @@ impl<'a, 'b> ser::SerializeStruct for &'a mut UrlSerializer<'b> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        // Search for the key in the parts
+        // Search for the key in the parts, and for dotted parameters of nested fields
         let mut part = None;
-        for p in &mut self.parts {
-            match p {
-                Part::Param(p) => match p {
-                    Cow::Borrowed(k) if *k == key => {
-                        part = Some(p);
-                        break;
-                    }
-                    _ => {}
-                },
-                Part::Raw(_) => {}
+        let mut nested = Vec::new();
+        for (i, p) in self.parts.iter().enumerate() {
+            if let Part::Param(Cow::Borrowed(k)) | Part::Optional(Cow::Borrowed(k)) = p {
+                if *k == key {
+                    part = Some(i);
+                    break;
+                }
+                if let Some(rest) = k.strip_prefix(key).and_then(|k| k.strip_prefix('.')) {
+                    nested.push((i, rest));
+                }
+            }
+        }
+
+        if let Some(i) = part {
+            // Serialize the value into the part
+            let mut serializer = UrlValueSerializer {
+                field: key,
+                ..UrlValueSerializer::default()
+            };
+            value.serialize(&mut serializer)?;
+            // `None` optional parameters are left unfilled, and omitted
+            if serializer.none && matches!(self.parts[i], Part::Optional(_)) {
+                return Ok(());
+            }
+            // Dot segments would be removed from the path when the URL is normalized
+            if matches!(serializer.value.as_str(), "." | "..") {
+                return Err(UrlError::DotSegment(key));
             }
+            self.parts[i].fill(serializer.value);
+            return Ok(());
         }
 
-        // If the key was not found, this is an error
-        let part = part.ok_or(UrlError::KeyNotFound(key))?;
+        // The field has no parameter
+        if nested.is_empty() {
+            match self.unused {
+                UnusedPathArgs::Ignore => {
+                    self.ignored.get_or_insert(key);
+                    return Ok(());
+                }
+                UnusedPathArgs::Reject => return Err(UrlError::UnusedField(key)),
+            }
+        }
 
-        // Serialize the value into the part
-        let mut serializer = UrlValueSerializer::default();
+        // Serialize the nested struct into its dotted parameters
+        let mut serializer = UrlSerializer::new(
+            nested
+                .iter()
+                .map(|&(i, rest)| match self.parts[i] {
+                    Part::Optional(_) => Part::Optional(Cow::Borrowed(rest)),
+                    _ => Part::Param(Cow::Borrowed(rest)),
+                })
+                .collect(),
+            self.unused,
+        );
         value.serialize(&mut serializer)?;
-        let value = serializer.value;
-
-        *part = Cow::Owned(value);
+        for ((i, _), p) in nested.into_iter().zip(serializer.parts) {
+            if let Part::Param(Cow::Owned(v)) | Part::Optional(Cow::Owned(v)) = p {
+                self.parts[i].fill(v);
+            }
+        }
 
         Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        // An ignored field is likely misnamed if a parameter is left unfilled
+        let unfilled = self
+            .parts
+            .iter()
+            .any(|p| matches!(p, Part::Param(Cow::Borrowed(_))));
+        match self.ignored {
+            Some(key) if unfilled => Err(UrlError::KeyNotFound {
+                key,
+                placeholders: self.placeholders(),
+            }),
+            _ => Ok(()),
+        }
     }
 }
 
 #[derive(Debug, Clone, Default)]
 struct UrlValueSerializer {
+    /// The field being serialized, for error messages.
+    field: &'static str,
     value: String,
+    /// Whether the value is `None`.
+    none: bool,
+}
+
+impl UrlValueSerializer {
+    const fn not_supported(&self, kind: &'static str) -> UrlError {
+        UrlError::ValueNotSupported {
+            field: self.field,
+            kind,
+        }
+    }
 }
 
+/// Characters that are percent-encoded in path arguments.
+///
+/// These are the characters that aren't allowed in path segments by RFC 3986,
+/// including `/`, so that each argument stays within a single segment,
+/// and `;` and `=`, so that arguments can't be mistaken for matrix parameters.
 const PATH_SET: &AsciiSet = &CONTROLS
     .add(b'~')
     .add(b' ')
     .add(b'"')
     .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b';')
+    .add(b'=')
     .add(b'<')
     .add(b'>')
     .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
     .add(b'`')
     .add(b'{')
+    .add(b'|')
     .add(b'}');
 
-impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
+impl ser::Serializer for &mut UrlValueSerializer {
     type Ok = ();
     type Error = UrlError;
 
@@ -459,6 +810,7 @@ impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.none = true;
         self.serialize_unit()
     }
 
@@ -504,11 +856,11 @@ impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("sequence"))
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("tuple"))
     }
 
     fn serialize_tuple_struct(
@@ -516,7 +868,7 @@ impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("tuple struct"))
     }
 
     fn serialize_tuple_variant(
@@ -526,11 +878,11 @@ impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("tuple variant"))
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("map"))
     }
 
     fn serialize_struct(
@@ -538,7 +890,7 @@ impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
         _name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("struct"))
     }
 
     fn serialize_struct_variant(
@@ -548,82 +900,92 @@ impl<'a> ser::Serializer for &'a mut UrlValueSerializer {
         _variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        Err(self.not_supported("struct variant"))
     }
 }
 
+/// Placeholder serializer for unsupported compound values.
+///
+/// It is uninhabited: serializing such values fails before a compound
+/// serializer is needed, so none of its methods can be called.
 #[derive(Debug)]
-struct ErrorSerializer;
+enum ErrorSerializer {}
 
+#[allow(clippy::uninhabited_references)] // `self` can never exist
 impl ser::SerializeSeq for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
 
     fn serialize_element<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
+#[allow(clippy::uninhabited_references)]
 impl ser::SerializeTuple for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
 
     fn serialize_element<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
+#[allow(clippy::uninhabited_references)]
 impl ser::SerializeTupleStruct for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
 
     fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
+#[allow(clippy::uninhabited_references)]
 impl ser::SerializeTupleVariant for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
 
     fn serialize_field<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
+#[allow(clippy::uninhabited_references)]
 impl ser::SerializeMap for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
 
     fn serialize_key<T: ?Sized + Serialize>(&mut self, _key: &T) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn serialize_value<T: ?Sized + Serialize>(&mut self, _value: &T) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
+#[allow(clippy::uninhabited_references)]
 impl ser::SerializeStruct for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
@@ -633,14 +995,15 @@ impl ser::SerializeStruct for ErrorSerializer {
         _key: &'static str,
         _value: &T,
     ) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
+#[allow(clippy::uninhabited_references)]
 impl ser::SerializeStructVariant for ErrorSerializer {
     type Ok = ();
     type Error = UrlError;
@@ -650,11 +1013,11 @@ impl ser::SerializeStructVariant for ErrorSerializer {
         _key: &'static str,
         _value: &T,
     ) -> Result<(), Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match *self {}
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(UrlError::ValueNotSupported)
+        match self {}
     }
 }
 
@@ -733,10 +1096,440 @@ mod tests {
             limit: None,
         };
 
-        let url = construct_url(base_url, endpoint, &params, &query).unwrap();
+        let url = construct_url(
+            base_url,
+            endpoint,
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
         assert_eq!(url, "https://example.com/shows/1?page=1");
     }
 
+    /// Custom serialization of a timestamp type in different formats.
+    mod timestamp {
+        use serde::Serializer;
+
+        #[derive(Clone, Copy)]
+        pub struct Timestamp {
+            pub days: u64,
+            pub secs: u64,
+        }
+
+        pub mod epoch {
+            use serde::Serializer;
+
+            pub fn serialize<S: Serializer>(
+                ts: &super::Timestamp,
+                s: S,
+            ) -> Result<S::Ok, S::Error> {
+                s.serialize_u64(ts.days * 86_400 + ts.secs)
+            }
+        }
+
+        pub mod date_only {
+            use serde::Serializer;
+
+            pub fn serialize<S: Serializer>(
+                ts: &super::Timestamp,
+                s: S,
+            ) -> Result<S::Ok, S::Error> {
+                s.collect_str(&format_args!("1970-01-{:02}", ts.days + 1))
+            }
+        }
+
+        pub fn rfc3339<S: Serializer>(ts: &Timestamp, s: S) -> Result<S::Ok, S::Error> {
+            let (h, m, sec) = (ts.secs / 3600, ts.secs / 60 % 60, ts.secs % 60);
+            s.collect_str(&format_args!(
+                "1970-01-{:02}T{h:02}:{m:02}:{sec:02}Z",
+                ts.days + 1
+            ))
+        }
+    }
+
+    #[test]
+    fn construct_url_custom_serialization() {
+        use timestamp::{date_only, epoch, rfc3339, Timestamp};
+
+        #[derive(Serialize)]
+        struct Params {
+            #[serde(with = "date_only")]
+            day: Timestamp,
+            #[serde(serialize_with = "rfc3339")]
+            at: Timestamp,
+        }
+        #[derive(Serialize)]
+        struct Query {
+            #[serde(with = "epoch")]
+            since: Timestamp,
+            #[serde(serialize_with = "rfc3339")]
+            until: Timestamp,
+        }
+
+        let ts = Timestamp {
+            days: 1,
+            secs: 3723,
+        };
+        let params = Params { day: ts, at: ts };
+        let query = Query {
+            since: ts,
+            until: ts,
+        };
+
+        let url = construct_url(
+            "https://example.com",
+            "/logs/{day}/{at}",
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(
+            url,
+            "https://example.com/logs/1970-01-02/1970-01-02T01:02:03Z\
+             ?since=90123&until=1970-01-02T01%3A02%3A03Z"
+        );
+    }
+
+    #[test]
+    fn construct_url_newtypes_and_enums() {
+        #[derive(Serialize)]
+        struct UserId(u64);
+
+        #[derive(Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Kind {
+            Movie,
+            TvShow,
+        }
+
+        #[derive(Serialize)]
+        enum Lookup {
+            Id(UserId),
+        }
+
+        #[derive(Serialize)]
+        struct Params {
+            id: UserId,
+            kind: Kind,
+            lookup: Lookup,
+        }
+        #[derive(Serialize)]
+        struct Query {
+            owner: UserId,
+            kind: Kind,
+        }
+        #[derive(Serialize)]
+        struct Wrapped(Params);
+
+        let params = Params {
+            id: UserId(42),
+            kind: Kind::TvShow,
+            lookup: Lookup::Id(UserId(7)),
+        };
+        let query = Query {
+            owner: UserId(1),
+            kind: Kind::Movie,
+        };
+
+        let url = construct_url(
+            "https://example.com",
+            "/users/{id}/{kind}/{lookup}",
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(
+            url,
+            "https://example.com/users/42/tv_show/7?owner=1&kind=movie"
+        );
+
+        // Newtype wrappers around the whole parameter struct are unwrapped too
+        let url = construct_url(
+            "https://example.com",
+            "/users/{id}/{kind}/{lookup}",
+            &Wrapped(params),
+            &(),
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(url, "https://example.com/users/42/tv_show/7");
+    }
+
+    #[test]
+    fn construct_url_unsupported_value() {
+        #[derive(Serialize)]
+        struct Params {
+            id: i32,
+            tags: Vec<&'static str>,
+        }
+
+        let params = Params {
+            id: 1,
+            tags: vec!["a", "b"],
+        };
+        let err = to_string(
+            "https://example.com",
+            "/items/{id}/{tags}",
+            &params,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            UrlError::ValueNotSupported {
+                field: "tags",
+                kind: "sequence"
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "sequence values are not supported in path position for field `tags`"
+        );
+    }
+
+    #[test]
+    fn construct_url_nested() {
+        #[derive(Serialize)]
+        struct Org {
+            id: u32,
+        }
+        #[derive(Serialize)]
+        struct Repo {
+            name: &'static str,
+            owner: Option<Org>,
+        }
+        #[derive(Serialize)]
+        struct Params {
+            org: Option<Org>,
+            repo: Repo,
+        }
+        #[derive(Serialize)]
+        struct Wrapper {
+            org: Org,
+        }
+
+        let endpoint = "/orgs/{org.id}/repos/{repo.name}/{repo.owner.id}";
+        let params = Params {
+            org: Some(Org { id: 1 }),
+            repo: Repo {
+                name: "api kit",
+                owner: Some(Org { id: 2 }),
+            },
+        };
+        let url = construct_url(
+            "https://example.com",
+            endpoint,
+            &params,
+            &(),
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(url, "https://example.com/orgs/1/repos/api%20kit/2");
+
+        // Intermediate `None` fields leave their parameters unfilled
+        let params = Params {
+            org: None,
+            repo: Repo {
+                name: "api-kit",
+                owner: None,
+            },
+        };
+        assert_eq!(
+            to_string(
+                "https://example.com",
+                endpoint,
+                &params,
+                UnusedPathArgs::Ignore
+            )
+            .unwrap_err(),
+            UrlError::UnfilledFields(vec!["org.id".to_owned(), "repo.owner.id".to_owned()])
+        );
+
+        // Nested fields must have a parameter like top-level fields
+        let params = Wrapper { org: Org { id: 1 } };
+        assert_eq!(
+            to_string(
+                "https://example.com",
+                "/orgs/{org.name}",
+                &params,
+                UnusedPathArgs::Ignore
+            )
+            .unwrap_err(),
+            UrlError::KeyNotFound {
+                key: "id",
+                placeholders: vec!["name".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn construct_url_encodes_path_args() {
+        #[derive(Serialize)]
+        struct Params {
+            name: &'static str,
+        }
+
+        const ENDPOINT: &str = "/users/{name}/repos";
+        let url = |value| {
+            construct_url(
+                "https://example.com",
+                ENDPOINT,
+                &Params { name: value },
+                &(),
+                QueryStyle::UrlEncoded,
+                UnusedPathArgs::Ignore,
+            )
+        };
+        for (value, expected) in [
+            ("a/b", "https://example.com/users/a%2Fb/repos"),
+            ("../admin", "https://example.com/users/..%2Fadmin/repos"),
+            (
+                "x?admin=1#top",
+                "https://example.com/users/x%3Fadmin%3D1%23top/repos",
+            ),
+            (
+                "a b[1]|c",
+                "https://example.com/users/a%20b%5B1%5D%7Cc/repos",
+            ),
+            ("a;v=2", "https://example.com/users/a%3Bv%3D2/repos"),
+            ("zoë", "https://example.com/users/zo%C3%AB/repos"),
+            (
+                "ünï/cødé",
+                "https://example.com/users/%C3%BCn%C3%AF%2Fc%C3%B8d%C3%A9/repos",
+            ),
+        ] {
+            let url = url(value).unwrap();
+            assert_eq!(url, expected);
+            let path = url.strip_prefix("https://example.com").unwrap();
+            assert_eq!(
+                match_endpoint(ENDPOINT, path, true),
+                Some(vec![Cow::Borrowed(value)])
+            );
+        }
+
+        for value in [".", ".."] {
+            assert!(matches!(
+                url(value).unwrap_err(),
+                IntoHttpError::Url(UrlError::DotSegment("name"))
+            ));
+        }
+    }
+
+    #[test]
+    fn optional_segments() {
+        #[derive(Serialize)]
+        struct Params {
+            id: u32,
+            tab: Option<&'static str>,
+            page: Option<u32>,
+        }
+        #[derive(Serialize)]
+        struct Id {
+            id: u32,
+        }
+
+        const ENDPOINT: &str = "/users/{id}/{tab?}/{page?}";
+        let url = |tab, page| {
+            construct_url(
+                "https://example.com",
+                ENDPOINT,
+                &Params { id: 1, tab, page },
+                &(),
+                QueryStyle::UrlEncoded,
+                UnusedPathArgs::Ignore,
+            )
+        };
+        assert_eq!(
+            url(Some("posts"), Some(2)).unwrap(),
+            "https://example.com/users/1/posts/2"
+        );
+        assert_eq!(
+            url(Some("posts"), None).unwrap(),
+            "https://example.com/users/1/posts"
+        );
+        assert_eq!(url(None, None).unwrap(), "https://example.com/users/1");
+        // Later segments can't be filled without the earlier ones
+        assert!(matches!(
+            url(None, Some(2)).unwrap_err(),
+            IntoHttpError::Url(UrlError::UnfilledField(field)) if field == "tab"
+        ));
+
+        // Missing optional fields are omitted, missing required fields are an error
+        assert_eq!(
+            to_string(
+                "https://example.com",
+                ENDPOINT,
+                &Id { id: 1 },
+                UnusedPathArgs::Ignore
+            )
+            .unwrap(),
+            "https://example.com/users/1"
+        );
+        assert_eq!(
+            to_string(
+                "https://example.com",
+                "/{tab?}",
+                &(),
+                UnusedPathArgs::Ignore
+            )
+            .unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            to_string(
+                "https://example.com",
+                "/users/{id}/{tab?}",
+                &(),
+                UnusedPathArgs::Ignore
+            )
+            .unwrap_err(),
+            UrlError::UnfilledField("id".to_owned())
+        );
+
+        assert_eq!(
+            match_endpoint(ENDPOINT, "/users/1/posts/2", true),
+            Some(vec![Cow::Borrowed("1"), "posts".into(), "2".into()])
+        );
+        assert_eq!(
+            match_endpoint(ENDPOINT, "/users/1/posts", true),
+            Some(vec![Cow::Borrowed("1"), "posts".into()])
+        );
+        assert_eq!(
+            match_endpoint(ENDPOINT, "/users/1", true),
+            Some(vec![Cow::Borrowed("1")])
+        );
+        assert_eq!(
+            match_endpoint("/users/{tab?}", "/users", true),
+            Some(vec![])
+        );
+        assert_eq!(match_endpoint(ENDPOINT, "/users/1/", true), None);
+        assert_eq!(match_endpoint(ENDPOINT, "/users/1/a/2/3", true), None);
+
+        for endpoint in [
+            "/users/{tab?}/posts",
+            "/users/{tab?}{page?}",
+            "/users{tab?}",
+            "/users/{tab?}/{id}",
+            "/users/{tab?}/",
+            "{tab?}",
+            "/users/{?}",
+        ] {
+            assert_eq!(
+                parse_endpoint(endpoint).unwrap_err(),
+                UrlError::InvalidEndpoint,
+                "{endpoint}"
+            );
+        }
+    }
+
     #[test]
     fn construct_url_no_query() {
         #[derive(Serialize)]
@@ -751,7 +1544,15 @@ mod tests {
         let params = Params { id: 1 };
         let query = Query;
 
-        let url = construct_url(base_url, endpoint, &params, &query).unwrap();
+        let url = construct_url(
+            base_url,
+            endpoint,
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
         assert_eq!(url, "https://example.com/shows/1");
     }
 
@@ -769,7 +1570,15 @@ mod tests {
         let params = Params { id: 1 };
         let query = Query;
 
-        let res = construct_url(base_url, endpoint, &params, &query).unwrap_err();
+        let res = construct_url(
+            base_url,
+            endpoint,
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap_err();
         assert!(matches!(
             res,
             IntoHttpError::Url(UrlError::UnfilledField(_))
@@ -778,6 +1587,175 @@ mod tests {
             unreachable!()
         };
         assert_eq!(key, "unfilled");
+
+        // All unfilled fields are returned together
+        let endpoint = "/shows/{show}/seasons/{id}/episodes/{episode}";
+        let res = construct_url(
+            base_url,
+            endpoint,
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap_err();
+        let IntoHttpError::Url(err) = res else {
+            panic!("unexpected error: {res}")
+        };
+        assert_eq!(
+            err,
+            UrlError::UnfilledFields(vec!["show".to_owned(), "episode".to_owned()])
+        );
+        assert_eq!(err.unfilled_fields(), ["show", "episode"]);
+        assert_eq!(err.to_string(), r#"Unfilled fields: ["show", "episode"]"#);
+        assert_eq!(
+            UrlError::UnfilledField("id".to_owned()).unfilled_fields(),
+            ["id"]
+        );
+        assert!(UrlError::TopLevel.unfilled_fields().is_empty());
+    }
+
+    #[test]
+    fn construct_matrix_url_params() {
+        #[derive(Serialize)]
+        struct Params {
+            id: &'static str,
+        }
+        #[derive(Serialize)]
+        struct Matrix {
+            version: u32,
+            tags: Vec<&'static str>,
+            name: Option<&'static str>,
+        }
+        #[derive(Serialize)]
+        struct Query {
+            page: u32,
+        }
+
+        let url = |user, segment, matrix: &Matrix| {
+            construct_matrix_url(
+                "https://example.com/api",
+                "/users/{id}/posts",
+                &Params { id: user },
+                segment,
+                matrix,
+                &Query { page: 2 },
+                QueryStyle::UrlEncoded,
+                UnusedPathArgs::Ignore,
+            )
+        };
+        let matrix = Matrix {
+            version: 2,
+            tags: vec!["a", "b"],
+            name: Some("x;y=z/w ü"),
+        };
+        assert_eq!(
+            url("a;b", 0, &matrix).unwrap(),
+            "https://example.com/api/users;version=2;tags=a;tags=b;name=x%3By%3Dz%2Fw%20%C3%BC/a%3Bb/posts?page=2"
+        );
+        // Matrix parameters follow the path argument of the segment,
+        // whose `;` and `=` are encoded so they can't be mistaken for matrix parameters
+        assert_eq!(
+            url("a;b", 1, &matrix).unwrap(),
+            "https://example.com/api/users/a%3Bb;version=2;tags=a;tags=b;name=x%3By%3Dz%2Fw%20%C3%BC/posts?page=2"
+        );
+        let matrix = Matrix {
+            version: 1,
+            tags: Vec::new(),
+            name: None,
+        };
+        assert_eq!(
+            url("k=v", 1, &matrix).unwrap(),
+            "https://example.com/api/users/k%3Dv;version=1/posts?page=2"
+        );
+        assert_eq!(
+            url("a;b", 2, &matrix).unwrap(),
+            "https://example.com/api/users/a%3Bb/posts;version=1?page=2"
+        );
+        assert!(matches!(
+            url("a;b", 3, &matrix).unwrap_err(),
+            IntoHttpError::Url(UrlError::MatrixSegmentNotFound(3))
+        ));
+
+        // Empty matrix parameters leave the path unchanged
+        assert_eq!(
+            construct_matrix_url(
+                "https://example.com",
+                "/users",
+                &(),
+                7,
+                &(),
+                &(),
+                QueryStyle::UrlEncoded,
+                UnusedPathArgs::Ignore
+            )
+            .unwrap(),
+            "https://example.com/users"
+        );
+    }
+
+    #[test]
+    fn construct_url_unused() {
+        #[derive(Serialize)]
+        struct Params {
+            user_id: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Extra {
+            id: i32,
+            user_id: i32,
+        }
+
+        fn url(
+            endpoint: &str,
+            params: &impl Serialize,
+            unused: UnusedPathArgs,
+        ) -> Result<String, IntoHttpError> {
+            construct_url(
+                "https://example.com",
+                endpoint,
+                params,
+                &(),
+                QueryStyle::UrlEncoded,
+                unused,
+            )
+        }
+
+        // A misnamed field is reported instead of being ignored, as it leaves `{id}` unfilled
+        let res = url(
+            "/users/{id}",
+            &Params { user_id: 1 },
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            res,
+            IntoHttpError::Url(UrlError::KeyNotFound { key: "user_id", placeholders })
+                if placeholders == ["id"]
+        ));
+        let res = url(
+            "/users/{id}",
+            &Params { user_id: 1 },
+            UnusedPathArgs::Reject,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            res,
+            IntoHttpError::Url(UrlError::UnusedField("user_id"))
+        ));
+
+        // Extra fields are only rejected when asked to
+        let extra = Extra { id: 1, user_id: 2 };
+        assert_eq!(
+            url("/users/{id}", &extra, UnusedPathArgs::Ignore).unwrap(),
+            "https://example.com/users/1"
+        );
+        let res = url("/users/{id}", &extra, UnusedPathArgs::Reject).unwrap_err();
+        assert!(matches!(
+            res,
+            IntoHttpError::Url(UrlError::UnusedField("user_id"))
+        ));
     }
 
     #[test]
@@ -794,8 +1772,62 @@ mod tests {
         let params = Params { id: 1 };
         let query = Query;
 
-        let res = construct_url(base_url, endpoint, &params, &query).unwrap_err();
+        let res = construct_url(
+            base_url,
+            endpoint,
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap_err();
         assert!(matches!(res, IntoHttpError::Url(UrlError::InvalidEndpoint)));
+
+        for endpoint in [
+            "/shows/{id",
+            "/shows/{}",
+            "/shows/{}/{id}",
+            "/shows/id}",
+            "{",
+        ] {
+            let res = construct_url(
+                base_url,
+                endpoint,
+                &params,
+                &query,
+                QueryStyle::UrlEncoded,
+                UnusedPathArgs::Ignore,
+            )
+            .unwrap_err();
+            assert!(
+                matches!(res, IntoHttpError::Url(UrlError::InvalidEndpoint)),
+                "{endpoint}"
+            );
+        }
+    }
+
+    #[test]
+    fn construct_url_adjacent_params() {
+        #[derive(Serialize)]
+        struct Params {
+            a: &'static str,
+            b: u32,
+        }
+
+        let params = Params { a: "v", b: 2 };
+        let url = construct_url(
+            "https://example.com",
+            "/{a}{b}",
+            &params,
+            &(),
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(url, "https://example.com/v2");
+
+        // Adjacent parameters can't be matched unambiguously
+        assert_eq!(match_endpoint("/{a}{b}", "/v2", false), None);
     }
 
     #[test]
@@ -810,10 +1842,133 @@ mod tests {
         let params = Params;
         let query = Query;
 
-        let url = construct_url(base_url, endpoint, &params, &query).unwrap();
+        let url = construct_url(
+            base_url,
+            endpoint,
+            &params,
+            &query,
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
         assert_eq!(url, "https://example.com/shows");
     }
 
+    #[test]
+    fn construct_url_root() {
+        #[derive(Serialize)]
+        struct Query {
+            page: u32,
+        }
+
+        for (base_url, endpoint, url) in [
+            ("https://host", "/", "https://host/"),
+            ("https://host/", "/", "https://host/"),
+            ("https://host", "", "https://host"),
+            ("https://host/", "", "https://host/"),
+            ("https://host/api", "/", "https://host/api/"),
+            ("https://host/api/", "users", "https://host/api/users"),
+            ("https://host/api", "users", "https://host/api/users"),
+            ("https://host/api/", "/users", "https://host/api/users"),
+        ] {
+            assert_eq!(
+                construct_url(
+                    base_url,
+                    endpoint,
+                    &(),
+                    &(),
+                    QueryStyle::UrlEncoded,
+                    UnusedPathArgs::Ignore
+                )
+                .unwrap(),
+                url
+            );
+        }
+
+        let url = construct_url(
+            "https://host",
+            "",
+            &(),
+            &Query { page: 2 },
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(url, "https://host?page=2");
+    }
+
+    #[test]
+    fn construct_url_trailing_slash() {
+        #[derive(Serialize)]
+        struct Params {
+            id: i32,
+        }
+
+        let url = construct_url(
+            "https://example.com",
+            "/users/{id}",
+            &Params { id: 1 },
+            &(),
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(url, "https://example.com/users/1");
+
+        let url = construct_url(
+            "https://example.com",
+            "/users/{id}/",
+            &Params { id: 1 },
+            &(),
+            QueryStyle::UrlEncoded,
+            UnusedPathArgs::Ignore,
+        )
+        .unwrap();
+        assert_eq!(url, "https://example.com/users/1/");
+    }
+
+    #[test]
+    fn match_endpoint_args() {
+        assert_eq!(
+            match_endpoint(
+                "/shows/{id}/seasons/{season}",
+                "/shows/abc/seasons/2",
+                false
+            ),
+            Some(vec![Cow::Borrowed("abc"), Cow::Borrowed("2")])
+        );
+        assert_eq!(
+            match_endpoint("/users/{name}", "/users/a%2Fb%20c", false),
+            Some(vec![Cow::Owned("a/b c".to_owned())])
+        );
+        assert_eq!(match_endpoint("/users", "/users", false), Some(vec![]));
+
+        assert_eq!(match_endpoint("/users/{id}", "/users/", false), None);
+        assert_eq!(match_endpoint("/users/{id}", "/users/1/posts", false), None);
+        assert_eq!(match_endpoint("/users/{id}", "/groups/1", false), None);
+        assert_eq!(match_endpoint("/users/{a}{b}", "/users/1", false), None);
+        assert_eq!(match_endpoint("/users/{id", "/users/1", false), None);
+    }
+
+    #[test]
+    fn match_endpoint_trailing_slash() {
+        // Lenient
+        assert_eq!(match_endpoint("/users", "/users/", false), Some(vec![]));
+        assert_eq!(match_endpoint("/users/", "/users", false), Some(vec![]));
+        assert_eq!(
+            match_endpoint("/users/{id}", "/users/1/", false),
+            Some(vec![Cow::Borrowed("1")])
+        );
+        assert_eq!(match_endpoint("/", "", false), Some(vec![]));
+        assert_eq!(match_endpoint("", "/", false), Some(vec![]));
+
+        // Strict
+        assert_eq!(match_endpoint("/users", "/users", true), Some(vec![]));
+        assert_eq!(match_endpoint("/users", "/users/", true), None);
+        assert_eq!(match_endpoint("/users/", "/users", true), None);
+        assert_eq!(match_endpoint("/users/{id}", "/users/1/", true), None);
+    }
+
     #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
     #[test]
     fn url_value_serializer() {
@@ -877,6 +2032,10 @@ mod tests {
         serializer.serialize_str("hello?").unwrap();
         assert_eq!(serializer.value, "hello%3F");
 
+        let mut serializer = UrlValueSerializer::default();
+        serializer.serialize_str("100%25").unwrap();
+        assert_eq!(serializer.value, "100%2525");
+
         let mut serializer = UrlValueSerializer::default();
         serializer.serialize_bytes(b"hello?\xc3\x28\x00").unwrap();
         assert_eq!(serializer.value, "hello%3F%C3(%00");
@@ -916,19 +2075,28 @@ mod tests {
         let mut serializer = UrlValueSerializer::default();
         assert_eq!(
             serializer.serialize_seq(None).unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "sequence"
+            }
         );
 
         let mut serializer = UrlValueSerializer::default();
         assert_eq!(
             serializer.serialize_tuple(0).unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "tuple"
+            }
         );
 
         let mut serializer = UrlValueSerializer::default();
         assert_eq!(
             serializer.serialize_tuple_struct("name", 0).unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "tuple struct"
+            }
         );
 
         let mut serializer = UrlValueSerializer::default();
@@ -936,19 +2104,28 @@ mod tests {
             serializer
                 .serialize_tuple_variant("name", 0, "variant", 0)
                 .unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "tuple variant"
+            }
         );
 
         let mut serializer = UrlValueSerializer::default();
         assert_eq!(
             serializer.serialize_map(None).unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "map"
+            }
         );
 
         let mut serializer = UrlValueSerializer::default();
         assert_eq!(
             serializer.serialize_struct("name", 0).unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "struct"
+            }
         );
 
         let mut serializer = UrlValueSerializer::default();
@@ -956,14 +2133,17 @@ mod tests {
             serializer
                 .serialize_struct_variant("name", 0, "variant", 0)
                 .unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "struct variant"
+            }
         );
     }
 
     #[allow(clippy::too_many_lines)]
     #[test]
     fn url_serializer() {
-        let mut serializer = UrlSerializer { parts: vec![] };
+        let mut serializer = UrlSerializer::new(vec![], UnusedPathArgs::Ignore);
 
         assert_eq!(
             true.serialize(&mut serializer).unwrap_err(),
@@ -1035,7 +2215,10 @@ mod tests {
         );
         assert_eq!(
             (0, "variant").serialize(&mut serializer).unwrap_err(),
-            UrlError::ValueNotSupported
+            UrlError::ValueNotSupported {
+                field: "",
+                kind: "tuple"
+            }
         );
         assert_eq!(
             Ok::<_, bool>(true).serialize(&mut serializer).unwrap_err(),
@@ -1049,12 +2232,13 @@ mod tests {
         }
         let params = Params { id: 1 };
 
-        let mut serializer = UrlSerializer {
-            parts: vec![
+        let mut serializer = UrlSerializer::new(
+            vec![
                 Part::Param(Cow::Owned("raw".to_owned())),
                 Part::Param(Cow::Borrowed("id")),
             ],
-        };
+            UnusedPathArgs::Ignore,
+        );
 
         params.serialize(&mut serializer).unwrap();
         assert_eq!(
@@ -1067,16 +2251,24 @@ mod tests {
 
         let params = Params { id: 1 };
 
-        let mut serializer = UrlSerializer {
-            parts: vec![
+        let mut serializer = UrlSerializer::new(
+            vec![
                 Part::Param(Cow::Owned("raw".to_owned())),
                 Part::Param(Cow::Borrowed("i")),
             ],
-        };
+            UnusedPathArgs::Ignore,
+        );
 
         assert_eq!(
             params.serialize(&mut serializer).unwrap_err(),
-            UrlError::KeyNotFound("id")
+            UrlError::KeyNotFound {
+                key: "id",
+                placeholders: vec!["i".to_owned()],
+            }
+        );
+        assert_eq!(
+            params.serialize(&mut serializer).unwrap_err().to_string(),
+            r#"No path placeholder for field `id`, unfilled placeholders: ["i"]"#
         );
     }
 }
@@ -0,0 +1,98 @@
+//! Non-fatal issues encountered while building requests.
+
+use std::borrow::Cow;
+
+use displaydoc::Display;
+use http::{HeaderName, HeaderValue};
+
+/// A non-fatal issue encountered while building a request.
+///
+/// Warnings never prevent a request from being built,
+/// but may indicate that the request is not what the endpoint expects.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// Authentication scheme `{0}` is not accepted by the endpoint
+    UnlistedAuthScheme(&'static str),
+    /// Endpoint header missing from request: {0}
+    MissingHeader(HeaderName),
+    /// Request content type {actual:?} does not match the endpoint content type {expected:?}
+    ContentTypeMismatch {
+        expected: HeaderValue,
+        actual: Option<HeaderValue>,
+    },
+    /// Endpoint is deprecated: {0}
+    Deprecated(Cow<'static, str>),
+    /// {0}
+    Custom(Cow<'static, str>),
+}
+
+/// A collector of [`Warning`]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Warnings(Vec<Warning>);
+
+impl Warnings {
+    /// Creates an empty warnings collector.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds a warning to the collector.
+    #[inline]
+    pub fn push(&mut self, warning: Warning) {
+        self.0.push(warning);
+    }
+
+    /// Returns `true` if no warnings were collected.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of collected warnings.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns an iterator over the collected warnings.
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, Warning> {
+        self.0.iter()
+    }
+
+    /// Consumes the collector, returning the collected warnings.
+    #[inline]
+    #[must_use]
+    pub fn into_vec(self) -> Vec<Warning> {
+        self.0
+    }
+}
+
+impl Extend<Warning> for Warnings {
+    fn extend<T: IntoIterator<Item = Warning>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Warnings {
+    type Item = Warning;
+    type IntoIter = std::vec::IntoIter<Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Warnings {
+    type Item = &'a Warning;
+    type IntoIter = std::slice::Iter<'a, Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
@@ -0,0 +1,68 @@
+#![no_main]
+
+use api_kit::{
+    error::{IntoHttpError, UrlError},
+    metadata::Metadata,
+};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use serde::Serialize;
+
+#[derive(Debug, Arbitrary, Serialize)]
+struct Params {
+    id: String,
+    name: String,
+    n: i64,
+    f: f64,
+    c: char,
+    opt: Option<u32>,
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    template: String,
+    params: Params,
+}
+
+fuzz_target!(|input: Input| {
+    // Arbitrary templates must never panic
+    let meta = Metadata {
+        path: &input.template,
+        ..Metadata::default()
+    };
+    if let Ok(uri) = meta.make_url("https://example.com", &input.params, &()) {
+        assert_eq!(uri.host(), Some("example.com"));
+    }
+    let _ = meta.extract_path_args(&input.template, Default::default());
+
+    // Filled parameters of a well-formed template must produce a valid URI
+    // and round-trip through path matching
+    let meta = Metadata {
+        path: "/items/{id}/{name}/{n}/{f}/{c}/{opt}",
+        ..Metadata::default()
+    };
+    let p = &input.params;
+    let res = meta.make_url("https://example.com", &input.params, &());
+    if [p.id.as_str(), p.name.as_str(), p.c.encode_utf8(&mut [0; 4])]
+        .iter()
+        .any(|s| matches!(*s, "." | ".."))
+    {
+        // Dot segments are rejected
+        assert!(matches!(
+            res,
+            Err(IntoHttpError::Url(UrlError::DotSegment(_)))
+        ));
+        return;
+    }
+    let uri = res.expect("filled template does not produce a valid URI");
+
+    if [&p.id, &p.name].iter().all(|s| !s.is_empty()) && p.opt.is_some() {
+        let args = meta
+            .extract_path_args(uri.path(), Default::default())
+            .expect("constructed path does not match its template");
+        assert_eq!(args[0], p.id);
+        assert_eq!(args[1], p.name);
+        assert_eq!(args[2], p.n.to_string());
+        assert_eq!(args[4], p.c.to_string());
+    }
+});
@@ -0,0 +1,31 @@
+#![no_main]
+
+use api_kit::metadata::Metadata;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Arbitrary, Serialize, Deserialize)]
+struct Query {
+    q: String,
+    page: u32,
+    tag: Option<String>,
+    flag: bool,
+}
+
+fuzz_target!(|query: Query| {
+    let meta = Metadata {
+        path: "/search",
+        ..Metadata::default()
+    };
+    let uri = meta
+        .make_query_url("https://example.com", &(), &query)
+        .expect("query does not produce a valid URI");
+
+    let decoded: Query = serde_urlencoded::from_str(uri.query().unwrap_or_default())
+        .expect("query does not deserialize");
+    // An empty optional string is serialized the same as `None`
+    if query.tag.as_deref() != Some("") {
+        assert_eq!(decoded, query);
+    }
+});